@@ -0,0 +1,165 @@
+//! Events written in meta.toml as `[[event]]` tables, so a small calendar can be a single file.
+//!
+//! Each event is read from the whole meta file, so locations in diagnostics point into it. Events
+//! are given the path they would have as files of their own next to meta.toml, which is where their
+//! posters are found and what `extends` refers to.
+
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fmt, fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use miette::{NamedSource, Report};
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use toml::Spanned;
+
+use crate::{error::InvalidEventId, normalize};
+
+/// The key of the array of events in meta.toml.
+pub const KEY: &str = "event";
+
+#[derive(Deserialize)]
+struct Events {
+    #[serde(default, rename = "event")]
+    events: Vec<Spanned<Entry>>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    id: Option<Spanned<toml::Value>>,
+}
+
+/// Finds the events in a meta file, by their position and the paths they would have as files.
+/// Events without a usable id are reported and skipped.
+pub fn find(meta: &Path) -> Vec<(usize, PathBuf)> {
+    // Errors in reading or parsing the file are reported when it is read as the meta file.
+    let Ok(content) = fs::read_to_string(meta) else {
+        return Vec::new();
+    };
+    let content = normalize(content.into()).into_owned();
+    let Ok(Events { events }) = toml::from_str(&content) else {
+        return Vec::new();
+    };
+    let content = Arc::new(content);
+    let directory = meta.parent().unwrap_or(Path::new(""));
+    let mut ids = HashSet::new();
+    let mut found = Vec::new();
+    for (index, entry) in events.iter().enumerate() {
+        let problem = match &entry.get_ref().id {
+            None => "is missing",
+            Some(id) => match id.get_ref().as_str() {
+                None => "is not a string",
+                Some(id) if !is_file_stem(id) => "can't be a file name",
+                Some(id) if !ids.insert(id) => "is also used by another event in meta.toml",
+                Some(id) => {
+                    found.push((index, directory.join(format!("{id}.toml"))));
+                    continue;
+                }
+            },
+        };
+        let span = entry
+            .get_ref()
+            .id
+            .as_ref()
+            .map_or(entry.span(), Spanned::span);
+        eprintln!(
+            "{:?}",
+            Report::new(InvalidEventId {
+                problem,
+                src: NamedSource::new(meta.to_string_lossy(), content.clone()),
+                location: Some(span.into()),
+            }),
+        );
+    }
+    found
+}
+
+/// Whether `id` is the whole name of the file `{id}.toml`, so the event's id is the same as it
+/// would be for a file.
+fn is_file_stem(id: &str) -> bool {
+    let file = format!("{id}.toml");
+    let path = Path::new(&file);
+    !id.is_empty()
+        && path.file_name() == Some(OsStr::new(&file))
+        && path.file_stem() == Some(OsStr::new(id))
+}
+
+/// Deserializes the event at `index` in the meta file `content`, skipping the rest of the file.
+pub fn deserialize<'de, T: Deserialize<'de>>(
+    content: &'de str,
+    index: usize,
+) -> Result<T, toml::de::Error> {
+    toml::Deserializer::new(content).deserialize_map(Root {
+        index,
+        event: PhantomData,
+    })
+}
+
+struct Root<T> {
+    index: usize,
+    event: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for Root<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a meta file")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        let mut event = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == KEY {
+                event = Some(map.next_value_seed(Entries {
+                    index: self.index,
+                    event: PhantomData,
+                })?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        event.ok_or_else(|| de::Error::missing_field(KEY))
+    }
+}
+
+struct Entries<T> {
+    index: usize,
+    event: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for Entries<T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for Entries<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of events")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        for skipped in 0..self.index {
+            if seq.next_element::<IgnoredAny>()?.is_none() {
+                return Err(de::Error::invalid_length(skipped, &self));
+            }
+        }
+        let event = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(self.index, &self))?;
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(event)
+    }
+}
@@ -0,0 +1,55 @@
+//! Compressed copies of output files, for static hosts that serve them as they are to clients that
+//! accept them instead of compressing on every request.
+
+use std::io::Write;
+
+use brotli::enc::BrotliEncoderParams;
+use flate2::{write::GzEncoder, Compression};
+use miette::{Context, IntoDiagnostic, Result};
+
+/// Compresses `content`, the file `name`, with gzip and brotli at their best settings. The copies
+/// are named like `data.json.gz` and `data.json.br`. Neither records a time, so they only change
+/// when `content` does.
+fn precompress(name: &str, content: &[u8]) -> Result<[(String, Vec<u8>); 2]> {
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+    gzip.write_all(content).into_diagnostic()?;
+    let gzip = gzip.finish().into_diagnostic()?;
+
+    let mut brotli = Vec::new();
+    brotli::BrotliCompress(
+        &mut &content[..],
+        &mut brotli,
+        &BrotliEncoderParams::default(),
+    )
+    .into_diagnostic()?;
+
+    Ok([(format!("{name}.gz"), gzip), (format!("{name}.br"), brotli)])
+}
+
+/// Adds the compressed copies of `name` to `files`, if it is among them.
+pub fn add_copies(name: &str, files: &mut Vec<(String, Vec<u8>)>) -> Result<()> {
+    let Some((_, content)) = files.iter().find(|(n, _)| n == name) else {
+        return Ok(());
+    };
+    let copies =
+        precompress(name, content).wrap_err_with(|| format!("Compressing {name} failed."))?;
+    files.extend(copies);
+    Ok(())
+}
+
+/// The sizes of `name` and its compressed copies among `files`, like
+/// `data.json: 48213 bytes, 9120 gzipped, 7311 with brotli`.
+pub fn summary(name: &str, files: &[(String, Vec<u8>)]) -> Option<String> {
+    let size = |file_name: &str| {
+        files
+            .iter()
+            .find(|(n, _)| n == file_name)
+            .map(|(_, content)| content.len())
+    };
+    Some(format!(
+        "{name}: {} bytes, {} gzipped, {} with brotli",
+        size(name)?,
+        size(&format!("{name}.gz"))?,
+        size(&format!("{name}.br"))?,
+    ))
+}
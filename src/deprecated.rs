@@ -0,0 +1,46 @@
+use miette::{NamedSource, Report};
+
+use crate::{error::DeprecatedField, span::SourceMap};
+
+/// A field that was renamed. The old name keeps working through `#[serde(alias)]` on the field
+/// until the entry is removed from here.
+pub struct Renamed {
+    /// The tables containing the field. `*` matches any key.
+    pub path: &'static [&'static str],
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+pub const EVENT: &[Renamed] = &[Renamed {
+    path: &[],
+    old: "lang",
+    new: "languages",
+}];
+
+pub const META: &[Renamed] = &[Renamed {
+    path: &[],
+    old: "lang",
+    new: "languages",
+}];
+
+/// Warns about every use of a renamed field.
+pub fn check(renamed: &[Renamed], spans: Option<SourceMap>, src: impl Fn() -> NamedSource) {
+    let Some(spans) = spans else {
+        return;
+    };
+    for field in renamed {
+        let mut pattern = field.path.to_vec();
+        pattern.push(field.old);
+        for location in spans.find(&pattern) {
+            eprintln!(
+                "{:?}",
+                Report::new(DeprecatedField {
+                    old: field.old,
+                    new: field.new,
+                    src: src(),
+                    location,
+                })
+            );
+        }
+    }
+}
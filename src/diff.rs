@@ -0,0 +1,205 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use miette::{miette, Context, IntoDiagnostic, Result};
+use serde_json::Value;
+
+use crate::{
+    formats::Format,
+    output::{self, DateSet},
+    state::State,
+};
+
+/// Reads the time data.json was compiled at.
+pub fn compiled_time(output: &Path) -> Result<DateTime<Utc>> {
+    let path = output.join("data.json");
+    let data = fs::read(&path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not read {}", path.display()))?;
+    serde_json::from_slice::<Value>(&data)
+        .ok()
+        .and_then(|data| data.get("meta")?.get("ts")?.as_i64())
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .ok_or_else(|| miette!("{} does not have a compile time.", path.display()))
+}
+
+/// Prints what compiling would change in the output directory.
+pub fn report(
+    output: &Path,
+    rendered: &[(String, Vec<u8>)],
+    state_json: &[u8],
+    posters: &[(u8, PathBuf)],
+) {
+    for (name, content) in rendered {
+        let old = fs::read(output.join(name)).ok();
+        match &old {
+            None => println!("{name} would be created"),
+            Some(old) if old == content => println!("{name} would not change"),
+            Some(_) => println!("{name} would change"),
+        }
+        if *name == "data.json" {
+            report_events(output, old.as_deref(), content, state_json);
+        }
+    }
+
+    match fs::read(output.join("state.json")) {
+        Err(_) => println!("state.json would be created"),
+        Ok(old) if old == state_json => println!("state.json would not change"),
+        Ok(_) => println!("state.json would change"),
+    }
+
+    if posters.is_empty() {
+        println!("No posters would be written");
+    } else {
+        for (index, source) in posters {
+            let slot = format!("{index:02x}");
+            let action = if output.join("posters").join(&slot).exists() {
+                "replaced"
+            } else {
+                "added"
+            };
+            println!("Poster {slot} would be {action} by {}", source.display());
+        }
+    }
+}
+
+fn report_events(output: &Path, old_data: Option<&[u8]>, data_json: &[u8], state_json: &[u8]) {
+    let old_events = match old_data {
+        None => BTreeMap::new(),
+        // Events can only be told apart by the ids saved with them.
+        Some(old_data) => match events_by_id(
+            serde_json::from_slice(old_data).ok(),
+            &event_ids(&output.join("state.json")),
+        ) {
+            Some(events) => events,
+            None => return,
+        },
+    };
+    let new_ids = serde_json::from_slice::<State>(state_json)
+        .map(|state| state.events)
+        .unwrap_or_default();
+    let Some(new_events) = events_by_id(serde_json::from_slice(data_json).ok(), &new_ids) else {
+        return;
+    };
+    for (id, event) in &new_events {
+        match old_events.get(id) {
+            None => println!("  added event {id}"),
+            Some(old) if old != event => println!("  changed event {id}"),
+            Some(_) => {}
+        }
+    }
+    for id in old_events.keys() {
+        if !new_events.contains_key(id) {
+            println!("  removed event {id}");
+        }
+    }
+}
+
+/// What compiling would take away from the previous output.
+pub struct Losses {
+    /// The number of events in the previous output.
+    pub total: usize,
+    /// The ids of the events that would be removed or canceled entirely.
+    pub removed: Vec<String>,
+    /// The number of occurrences that would be newly canceled.
+    pub canceled: usize,
+}
+
+/// The formats that have every event, in the order their previous files are compared.
+const DATA_FORMATS: [Format; 2] = [Format::Json, Format::Msgpack];
+
+/// Compares `events`, which have the `ids`, with the previous output in `output`, or returns
+/// `None` if its state.json doesn't list its events. Cancellations are compared with the first of
+/// data.json and data.msgpack that `formats` writes, and left out if neither was written before.
+pub fn losses(
+    output: &Path,
+    formats: &[Format],
+    ids: &[Cow<str>],
+    events: &[output::Event],
+) -> Option<Losses> {
+    let old_ids = event_ids(&output.join("state.json"));
+    if old_ids.is_empty() {
+        return None;
+    }
+    let old_data = DATA_FORMATS
+        .into_iter()
+        .filter(|format| formats.contains(format))
+        .find_map(|format| {
+            let name = format.output_format().file_name();
+            read_data(format, &fs::read(output.join(name)).ok()?)
+        });
+    let old_events = events_by_id(old_data, &old_ids);
+    let new_events: BTreeMap<_, _> = ids.iter().map(Cow::as_ref).zip(events).collect();
+
+    let mut removed = Vec::new();
+    let mut canceled = 0;
+    for id in &old_ids {
+        let old = old_events.as_ref().and_then(|events| events.get(id));
+        let old_canceled = old.and_then(|old| old.get("canceled"));
+        if old_canceled == Some(&Value::Bool(true)) {
+            continue;
+        }
+        let Some(event) = new_events.get(id.as_str()) else {
+            removed.push(id.clone());
+            continue;
+        };
+        match &event.canceled {
+            DateSet::All(true) => removed.push(id.clone()),
+            DateSet::All(false) => {}
+            // Without the previous dates, every canceled date would look new.
+            DateSet::Dates(_) if old.is_none() => {}
+            DateSet::Dates(dates) => {
+                let old_dates = old_canceled
+                    .and_then(Value::as_array)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                canceled += dates
+                    .iter()
+                    .filter(|date| !old_dates.contains(&Value::from(date.to_string())))
+                    .count();
+            }
+        }
+    }
+    Some(Losses {
+        total: old_ids.len(),
+        removed,
+        canceled,
+    })
+}
+
+/// Reads a previous data.json or data.msgpack.
+fn read_data(format: Format, data: &[u8]) -> Option<Value> {
+    match format {
+        Format::Msgpack => rmp_serde::from_slice(data).ok(),
+        _ => serde_json::from_slice(data).ok(),
+    }
+}
+
+/// Reads the ids of the events in the previous output from its state.json.
+fn event_ids(path: &Path) -> Vec<String> {
+    fs::read(path)
+        .ok()
+        .and_then(|state| serde_json::from_slice::<State>(&state).ok())
+        .map(|state| state.events)
+        .unwrap_or_default()
+}
+
+/// Pairs the events of `data` with their `ids`, or returns `None` if they were written without
+/// them, like by versions that didn't save them.
+fn events_by_id(data: Option<Value>, ids: &[String]) -> Option<BTreeMap<String, Value>> {
+    let Some(Value::Object(mut data)) = data else {
+        return None;
+    };
+    let Some(Value::Array(events)) = data.remove("events") else {
+        return None;
+    };
+    if events.len() != ids.len() {
+        return None;
+    }
+    Some(ids.iter().cloned().zip(events).collect())
+}
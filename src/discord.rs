@@ -0,0 +1,278 @@
+//! Importing confirmations posted to a Discord channel into the event files.
+//!
+//! Each line of a message that looks like `confirm <event id> <date>...` adds the dates to the
+//! event's `confirmed` list. The files are edited in place, so comments and layout are kept.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+use chrono::NaiveDate;
+use miette::{miette, Context, IntoDiagnostic, Report, Result};
+use serde::Deserialize;
+
+use crate::{
+    collect_files,
+    discover::FileFilter,
+    error::{ConfirmedDateNotHeld, ConfirmedEveryDay, ConfirmedInvalidDate, ConfirmedUnknownEvent},
+    input::DateSet,
+    parse_events, read_event_files,
+    report::Errors,
+    resolved,
+    retry::Retry,
+    safely_save, Event,
+};
+
+#[derive(clap::Args)]
+pub struct ConfirmDiscordArgs {
+    /// The directory containing the event files.
+    input: PathBuf,
+    #[command(flatten)]
+    filter: FileFilter,
+    /// The id of the channel the confirmations are posted in.
+    #[arg(long)]
+    channel: String,
+    /// Only accept confirmations from this Discord user id. Can be given more than once.
+    #[arg(long = "author")]
+    authors: Vec<String>,
+    /// How many of the latest messages to read.
+    #[arg(long, default_value_t = 100)]
+    limit: usize,
+    /// Commit the changed event files with git.
+    #[arg(long)]
+    commit: bool,
+}
+
+const API: &str = "https://discord.com/api/v10";
+
+/// The most messages Discord returns for one request.
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct Message {
+    id: String,
+    content: String,
+    author: Author,
+}
+
+#[derive(Deserialize)]
+struct Author {
+    id: String,
+}
+
+pub fn run(args: ConfirmDiscordArgs, errors: Errors) -> ExitCode {
+    match confirm(&args, &errors) {
+        Ok(()) if errors.count() == 0 => ExitCode::SUCCESS,
+        Ok(()) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{error:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn confirm(args: &ConfirmDiscordArgs, errors: &Errors) -> Result<()> {
+    // The token is not accepted as an option, where it would be visible to other users.
+    let token = env::var("DISCORD_TOKEN")
+        .into_diagnostic()
+        .wrap_err("DISCORD_TOKEN must be set to the bot's token.")?;
+    let messages = fetch_messages(&args.channel, &token, args.limit)?;
+
+    let files = collect_files(&args.input, &args.filter);
+    let event_files = read_event_files(&files, errors);
+    let events = parse_events(&event_files, errors);
+
+    // Discord returns the newest messages first.
+    let mut confirmations = BTreeMap::<usize, BTreeSet<NaiveDate>>::new();
+    for message in messages.iter().rev() {
+        if !args.authors.is_empty() && !args.authors.contains(&message.author.id) {
+            continue;
+        }
+        for line in message.content.lines() {
+            let mut words = line.split_whitespace();
+            if words.next() != Some("confirm") {
+                continue;
+            }
+            let Some(id) = words.next() else {
+                continue;
+            };
+            let Some(index) = events.iter().position(|e| e.id() == id) else {
+                eprintln!(
+                    "{:?}",
+                    Report::new(ConfirmedUnknownEvent {
+                        message: message.id.clone(),
+                        id: id.to_owned(),
+                    })
+                );
+                continue;
+            };
+            for word in words {
+                match NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+                    Ok(date) => {
+                        confirmations.entry(index).or_default().insert(date);
+                    }
+                    Err(_) => eprintln!(
+                        "{:?}",
+                        Report::new(ConfirmedInvalidDate {
+                            message: message.id.clone(),
+                            word: word.to_owned(),
+                        })
+                    ),
+                }
+            }
+        }
+    }
+
+    let mut changed = Vec::new();
+    for (index, dates) in confirmations {
+        let event = &events[index];
+        match add_confirmations(event, &dates)
+            .wrap_err_with(|| format!("Updating {} failed.", event.source.path.display()))
+        {
+            Ok(true) => {
+                eprintln!("Updated {}", event.source.path.display());
+                changed.push(event.source.path);
+            }
+            Ok(false) => {}
+            Err(error) => eprintln!("{error:?}"),
+        }
+    }
+
+    if args.commit && !changed.is_empty() {
+        commit(&args.input, &changed)?;
+    }
+    Ok(())
+}
+
+fn fetch_messages(channel: &str, token: &str, limit: usize) -> Result<Vec<Message>> {
+    let mut messages = Vec::<Message>::new();
+    while messages.len() < limit {
+        let mut request = ureq::get(&format!("{API}/channels/{channel}/messages"))
+            .set("Authorization", &format!("Bot {token}"))
+            .query(
+                "limit",
+                &(limit - messages.len()).min(PAGE_SIZE).to_string(),
+            );
+        if let Some(oldest) = messages.last() {
+            request = request.query("before", &oldest.id);
+        }
+        let page: Vec<Message> = request
+            .call()
+            .into_diagnostic()
+            .and_then(|response| response.into_json().into_diagnostic())
+            .wrap_err("Reading messages from Discord failed.")?;
+        let last_page = page.len() < PAGE_SIZE;
+        messages.extend(page);
+        if last_page {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+/// Adds the dates to the event's confirmed list. Returns whether the file changed.
+fn add_confirmations(event: &Event, dates: &BTreeSet<NaiveDate>) -> Result<bool> {
+    let existing = match &event.event.confirmed {
+        DateSet::All(true) => {
+            eprintln!(
+                "{:?}",
+                Report::new(ConfirmedEveryDay {
+                    path: event.source.path.to_owned(),
+                })
+            );
+            return Ok(false);
+        }
+        DateSet::All(false) => BTreeSet::new(),
+        DateSet::Dates(dates) => dates.iter().map(|d| *d.as_ref()).collect(),
+    };
+    // Days off cancel occurrences without taking them off the schedule, so they don't change
+    // which dates can be confirmed.
+    let days_off = resolved::DaysOff::default();
+    let resolved = resolved::Event::resolve(event, &days_off)?;
+    let added: Vec<_> = dates
+        .iter()
+        .filter(|date| !existing.contains(date))
+        .filter(|&&date| {
+            let held = resolved.occurs_on(date).is_some();
+            if !held {
+                eprintln!(
+                    "{:?}",
+                    Report::new(ConfirmedDateNotHeld {
+                        path: event.source.path.to_owned(),
+                        date,
+                    })
+                );
+            }
+            held
+        })
+        .collect();
+    if added.is_empty() {
+        return Ok(false);
+    }
+    // Files in other formats are converted as they are read, so they can't be edited in place.
+    if !event.source.is_editable() {
+        return Err(miette!(
+            "{} is not a TOML file of its own, so {} confirmations can't be added to it.",
+            event.source.describe(),
+            added.len(),
+        ));
+    }
+
+    let mut document = event
+        .source
+        .content
+        .parse::<toml_edit::DocumentMut>()
+        .into_diagnostic()?;
+    let confirmed = document
+        .entry("confirmed")
+        .or_insert(toml_edit::value(toml_edit::Array::new()));
+    if !confirmed.is_array() {
+        // `confirmed = false` becomes a list.
+        *confirmed = toml_edit::value(toml_edit::Array::new());
+    }
+    let Some(confirmed) = confirmed.as_array_mut() else {
+        unreachable!();
+    };
+    for date in added {
+        confirmed.push(date.to_string());
+    }
+
+    let path = event.source.path;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().unwrap();
+    let content = document.to_string();
+    safely_save(directory, name, &Retry::default(), |t| {
+        t.write_all(content.as_bytes()).into_diagnostic()
+    })?;
+    Ok(true)
+}
+
+fn commit(input: &Path, changed: &[&Path]) -> Result<()> {
+    let changed = changed
+        .iter()
+        // Without the `\\?\` prefix of canonicalize on Windows, which git doesn't accept.
+        .map(dunce::canonicalize)
+        .collect::<Result<Vec<_>, _>>()
+        .into_diagnostic()?;
+    for arguments in [
+        &["add", "--"][..],
+        &["commit", "-m", "Confirm events from Discord", "--"][..],
+    ] {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(input)
+            .args(arguments)
+            .args(&changed)
+            .status()
+            .into_diagnostic()
+            .wrap_err("Could not run git.")?;
+        if !status.success() {
+            return Err(miette!("git {} failed with {status}.", arguments[0]));
+        }
+    }
+    Ok(())
+}
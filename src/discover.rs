@@ -0,0 +1,208 @@
+//! Finding the input files, which may be organized into subdirectories or packed in an archive.
+
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs::{self, File},
+    io::BufReader,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use globset::{GlobBuilder, GlobMatcher};
+use miette::{Context, IntoDiagnostic, Result};
+use tempfile::TempDir;
+
+use crate::is_defaults_file;
+
+/// The input files, which keeps any extracted archives until the files are no longer needed.
+pub struct Files {
+    paths: BTreeSet<PathBuf>,
+    /// The events in meta.toml, by their position and the paths they would have as files.
+    pub entries: Vec<(usize, PathBuf)>,
+    _extracted: Vec<TempDir>,
+}
+
+impl Files {
+    pub fn new(
+        paths: BTreeSet<PathBuf>,
+        entries: Vec<(usize, PathBuf)>,
+        extracted: Vec<TempDir>,
+    ) -> Self {
+        Self {
+            paths,
+            entries,
+            _extracted: extracted,
+        }
+    }
+}
+
+impl Deref for Files {
+    type Target = BTreeSet<PathBuf>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.paths
+    }
+}
+
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    fn of(path: &Path) -> Option<Self> {
+        if !path.is_file() {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extracts `input` to a temporary directory if it is an archive.
+///
+/// Returns the directory to read the input from. Archives that wrap everything in one directory,
+/// like the ones GitHub creates, are read from inside that directory.
+pub fn extract(input: &Path) -> Result<(PathBuf, Option<TempDir>)> {
+    let Some(kind) = ArchiveKind::of(input) else {
+        return Ok((input.to_owned(), None));
+    };
+    let wrap = || format!("Extracting {} failed.", input.display());
+    let prefix = format!(
+        "{}-",
+        input.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let directory = tempfile::Builder::new()
+        .prefix(&prefix)
+        .tempdir()
+        .into_diagnostic()
+        .wrap_err_with(wrap)?;
+    let file = BufReader::new(File::open(input).into_diagnostic().wrap_err_with(wrap)?);
+    // Both unpackers refuse entries that would be written outside the directory.
+    match kind {
+        ArchiveKind::Zip => zip::ZipArchive::new(file)
+            .and_then(|mut archive| archive.extract(directory.path()))
+            .into_diagnostic(),
+        ArchiveKind::Tar => tar::Archive::new(file)
+            .unpack(directory.path())
+            .into_diagnostic(),
+        ArchiveKind::TarGz => tar::Archive::new(GzDecoder::new(file))
+            .unpack(directory.path())
+            .into_diagnostic(),
+    }
+    .wrap_err_with(wrap)?;
+
+    let root = directory.path();
+    let entries = fs::read_dir(root)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .into_diagnostic()
+        .wrap_err_with(wrap)?;
+    let path = match &entries[..] {
+        [entry] if entry.path().is_dir() => entry.path(),
+        _ => root.to_owned(),
+    };
+    Ok((path, Some(directory)))
+}
+
+#[derive(Clone, Default, clap::Args)]
+pub struct FileFilter {
+    /// Only read files matching this glob pattern, relative to the input directory. Can be given
+    /// more than once.
+    #[arg(long = "include", value_name = "GLOB", value_parser = parse_glob)]
+    include: Vec<GlobMatcher>,
+    /// Skip files and directories matching this glob pattern, relative to the input directory.
+    /// Can be given more than once.
+    #[arg(long = "exclude", value_name = "GLOB", value_parser = parse_glob)]
+    exclude: Vec<GlobMatcher>,
+}
+
+fn parse_glob(pattern: &str) -> Result<GlobMatcher, globset::Error> {
+    // `*` stays within one directory, and `**` crosses directories.
+    Ok(GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher())
+}
+
+impl FileFilter {
+    /// Collects the files in `input` and its subdirectories.
+    ///
+    /// Hidden directories are skipped, and meta.toml is only read from `input` itself, where it is
+    /// not subject to `--include`. Defaults files are not subject to `--include` either.
+    pub fn walk(&self, input: &Path) -> BTreeSet<PathBuf> {
+        let mut files = BTreeSet::new();
+        self.walk_directory(input, input, &mut files);
+        files
+    }
+
+    /// Whether `walk` reads the files in `directory`, which is relative to the input directory.
+    pub fn enters(&self, directory: &Path) -> bool {
+        directory
+            .ancestors()
+            .filter(|path| !path.as_os_str().is_empty())
+            .all(|path| {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                !hidden && !self.exclude.iter().any(|glob| glob.is_match(path))
+            })
+    }
+
+    fn walk_directory(&self, root: &Path, directory: &Path, files: &mut BTreeSet<PathBuf>) {
+        let entries = match fs::read_dir(directory)
+            .into_diagnostic()
+            .wrap_err("Collecting input failed.")
+        {
+            Ok(entries) => entries,
+            Err(error) => {
+                eprintln!("{error:?}");
+                return;
+            }
+        };
+        for entry in entries {
+            let (path, file_type) = match entry
+                .and_then(|entry| Ok((entry.path(), entry.file_type()?)))
+                .into_diagnostic()
+                .wrap_err("Collecting input failed.")
+            {
+                Ok(entry) => entry,
+                Err(error) => {
+                    eprintln!("{error:?}");
+                    continue;
+                }
+            };
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if self.exclude.iter().any(|glob| glob.is_match(relative)) {
+                continue;
+            }
+            let name = path.file_name().unwrap_or_default();
+            if file_type.is_dir() {
+                if !name.to_string_lossy().starts_with('.') {
+                    self.walk_directory(root, &path, files);
+                }
+            } else if name == OsStr::new("meta.toml") {
+                if directory == root {
+                    files.insert(path);
+                }
+            } else if is_defaults_file(&path) {
+                // The events that are included still need their defaults.
+                files.insert(path);
+            } else if self.include.is_empty()
+                || self.include.iter().any(|glob| glob.is_match(relative))
+            {
+                files.insert(path);
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use std::{fmt, path::PathBuf};
+use std::{fmt, io, path::PathBuf, process::ExitStatus};
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, Weekday};
 use miette::{Diagnostic, NamedSource, SourceOffset, SourceSpan};
+use toml::Spanned;
 
 use crate::{Event, EventFile};
 
 #[derive(Debug, Diagnostic, thiserror::Error)]
+#[diagnostic(code(WC0001))]
 pub struct EventParseError {
     pub error: toml::de::Error,
     #[source_code]
@@ -22,7 +24,15 @@ impl fmt::Display for EventParseError {
 
 impl From<&EventFile<'_>> for NamedSource {
     fn from(value: &EventFile) -> Self {
-        NamedSource::new(value.path.to_string_lossy(), value.content.clone())
+        let name = match value.entry {
+            Some((meta, _)) => meta.to_string_lossy(),
+            None => value.path.to_string_lossy(),
+        };
+        // The locations in converted files are moved to the original when they are shown.
+        NamedSource::new(
+            name,
+            value.original.clone().unwrap_or(value.content.clone()),
+        )
     }
 }
 
@@ -37,6 +47,7 @@ impl EventParseError {
 }
 
 #[derive(Debug, Diagnostic, thiserror::Error)]
+#[diagnostic(code(WC0002))]
 pub struct StateParseError {
     pub error: serde_json::Error,
     #[source_code]
@@ -70,6 +81,7 @@ impl StateParseError {
 
 #[derive(Debug, Diagnostic, thiserror::Error)]
 #[error("Unknown time zone {name:?}")]
+#[diagnostic(code(WC0003))]
 pub struct MissingTimeZone {
     name: String,
     #[source_code]
@@ -80,17 +92,49 @@ pub struct MissingTimeZone {
 
 impl MissingTimeZone {
     pub fn new(event: &Event) -> Self {
+        Self::from_spanned(event.event.timezone(), event.timezone_file.into())
+    }
+
+    pub fn from_spanned(timezone: &Spanned<impl AsRef<str>>, src: NamedSource) -> Self {
         Self {
-            name: event.event.timezone.as_ref().as_ref().to_owned(),
-            src: event.source.into(),
-            location: event.event.timezone.span().into(),
+            name: timezone.get_ref().as_ref().to_owned(),
+            src,
+            location: timezone.span().into(),
         }
     }
 }
 
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event uses time zone {name:?}, but organizer {organizer} is in {expected:?}")]
+#[help("Check that the event is not using the wrong region's time zone")]
+#[diagnostic(code(WC0004), severity("warning"))]
+pub struct OrganizerTimeZoneMismatch {
+    pub name: String,
+    pub organizer: String,
+    pub expected: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event time zone changed from {previous:?} to {name:?} since the last compile")]
+#[help("Check that the event is not using the wrong region's time zone")]
+#[diagnostic(code(WC0005), severity("warning"))]
+pub struct TimeZoneChanged {
+    pub name: String,
+    pub previous: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
 #[derive(Debug, Diagnostic, thiserror::Error)]
 #[error("Image {path:?} is too large ({width}x{height})")]
 #[help("Images cannot be larger than 2048x2048")]
+#[diagnostic(code(WC0006))]
 pub struct ImageTooLarge {
     pub path: PathBuf,
     pub width: usize,
@@ -100,7 +144,7 @@ pub struct ImageTooLarge {
 #[derive(Debug, Diagnostic, thiserror::Error)]
 #[error("Ignoring poster {extra:?} and using {found:?} instead")]
 #[help("Events should only have one poster")]
-#[diagnostic(severity("warning"))]
+#[diagnostic(code(WC0007), severity("warning"))]
 pub struct MultiplePosters {
     pub found: PathBuf,
     pub extra: PathBuf,
@@ -108,7 +152,7 @@ pub struct MultiplePosters {
 
 #[derive(Debug, Diagnostic, thiserror::Error)]
 #[error("The event is confirmed for {date}, but the event is not happening on this day.")]
-#[diagnostic(severity("warning"))]
+#[diagnostic(code(WC0008), severity("warning"))]
 pub struct ConfirmedOutOfRange {
     pub date: NaiveDate,
     #[source_code]
@@ -119,7 +163,7 @@ pub struct ConfirmedOutOfRange {
 
 #[derive(Debug, Diagnostic, thiserror::Error)]
 #[error("The event is canceled for {date}, but the event is not happening on this day.")]
-#[diagnostic(severity("warning"))]
+#[diagnostic(code(WC0009), severity("warning"))]
 pub struct CanceledOutOfRange {
     pub date: NaiveDate,
     #[source_code]
@@ -127,3 +171,779 @@ pub struct CanceledOutOfRange {
     #[label]
     pub location: SourceSpan,
 }
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The note for {weekday} expires on {date}, which is a {actual}.")]
+#[help("The expiry date is the last day the note is shown")]
+#[diagnostic(code(WC0010), severity("warning"))]
+pub struct NoteExpiresOnOtherDay {
+    pub date: NaiveDate,
+    pub actual: Weekday,
+    pub weekday: Weekday,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The description is {length} characters long, but the limit is {max}.")]
+#[diagnostic(code(WC0011), severity("warning"))]
+pub struct DescriptionTooLong {
+    pub length: usize,
+    pub max: usize,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event {path:?} does not have a poster.")]
+#[help("Add an image with the same name as the event file, or set poster")]
+#[diagnostic(code(WC0012), severity("warning"))]
+pub struct MissingPoster {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event {path:?} does not have a hashtag.")]
+#[diagnostic(code(WC0013), severity("warning"))]
+pub struct MissingHashtag {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Weeks are specified, but the event is held every day.")]
+#[help("Add day sections for the days of the week the event is held")]
+#[diagnostic(code(WC0014), severity("warning"))]
+pub struct WeeksWithAllDays {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The day has different times for {platform}, but the event is not on {platform}.")]
+#[help("Add {platform} to platforms")]
+#[diagnostic(code(WC0015))]
+pub struct UnsupportedPlatformOverride {
+    pub platform: &'static str,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{first:?} and {second:?} only differ by case or Unicode normalization")]
+#[help("These are the same file on case-insensitive filesystems like macOS and Windows use. Rename one of them")]
+#[diagnostic(code(WC0016))]
+pub struct FileNameCollision {
+    pub first: PathBuf,
+    pub second: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Writing {path:?} failed, retrying ({attempt} of {attempts})")]
+#[diagnostic(code(WC0017), severity("warning"))]
+pub struct RetryingIo {
+    pub path: PathBuf,
+    pub attempt: u32,
+    pub attempts: u32,
+    #[source]
+    pub error: io::Error,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{path:?} is not up to date")]
+#[help("Compile the input again to update it")]
+#[diagnostic(code(WC0018))]
+pub struct OutputOutOfDate {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event file name {path:?} is not valid Unicode")]
+#[help("The file name is used as the event's id. Rename the file")]
+#[diagnostic(code(WC0019))]
+pub struct NonUnicodeFileName {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("There is no event with the id {id:?}")]
+#[help("Related events are referred to by their file name without the extension")]
+#[diagnostic(code(WC0020))]
+pub struct UnknownRelatedEvent {
+    pub id: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{old:?} was renamed to {new:?}")]
+#[help("The old name still works for now, but support for it will be removed")]
+#[diagnostic(code(WC0021), severity("warning"))]
+pub struct DeprecatedField {
+    pub old: &'static str,
+    pub new: &'static str,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{first:?} and {second:?} define the same event id")]
+#[help("Events are identified by their file name, which must be unique across all input directories and their subdirectories. Rename one of them")]
+#[diagnostic(code(WC0022))]
+pub struct DuplicateEventId {
+    pub first: PathBuf,
+    pub second: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event starts at {local} {timezone} on {day}, which is in the quiet hours")]
+#[help("Check that the start time is not a mix-up between AM and PM")]
+#[diagnostic(code(WC0023), severity("warning"))]
+pub struct QuietHours {
+    pub local: String,
+    pub timezone: String,
+    pub day: &'static str,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("There is a cancellation reason for {date}, but the event is not canceled on this day.")]
+#[help("Add the date to canceled")]
+#[diagnostic(code(WC0024), severity("warning"))]
+pub struct ReasonWithoutCancellation {
+    pub date: NaiveDate,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("every and anchor must be given together.")]
+#[help("Set anchor to a date when the event is held, and every to the number of weeks between occurrences")]
+#[diagnostic(code(WC0025))]
+pub struct IntervalWithoutAnchor {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("every can't be combined with weeks.")]
+#[help("Use either weeks of the month, or every with an anchor")]
+#[diagnostic(code(WC0026))]
+pub struct IntervalWithWeeks {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event is listed for {date}, but the event is not happening on this day.")]
+#[diagnostic(code(WC0027), severity("warning"))]
+pub struct DateOutOfRange {
+    pub date: NaiveDate,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("sessions can't be combined with days.")]
+#[help("List the days either as [days.<day>] sections or as [[sessions]], not both")]
+#[diagnostic(code(WC0028))]
+pub struct SessionsWithDays {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("There is more than one session on the same day.")]
+#[help("Merge the sessions, or use platforms for a second session on another platform")]
+#[diagnostic(code(WC0029))]
+pub struct DuplicateSession {
+    #[source_code]
+    pub src: NamedSource,
+    #[label("the first session")]
+    pub first: SourceSpan,
+    #[label("the same day again")]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event has an override for {date}, but the event is not happening on this day.")]
+#[diagnostic(code(WC0030), severity("warning"))]
+pub struct OverrideOutOfRange {
+    pub date: NaiveDate,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("monthly must be a day of the month from 1 to 31.")]
+#[diagnostic(code(WC0031))]
+pub struct MonthlyOutOfRange {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("monthly can't be combined with days, weeks, every, or dates.")]
+#[help("Monthly events are held on the same day of every month, whatever the day of the week")]
+#[diagnostic(code(WC0032))]
+pub struct MonthlyWithWeeklySchedule {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{link:?} is not a web address.")]
+#[help("Links must be full addresses starting with https://")]
+#[diagnostic(code(WC0033))]
+pub struct InvalidLink {
+    pub link: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The {language} section of the meta file doesn't have its own title.")]
+#[help("Add a translated title to the language section")]
+#[diagnostic(code(WC0034), severity("warning"))]
+pub struct MissingLanguageTitle {
+    pub language: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("White text over {path:?} has a contrast ratio of {contrast:.1}, but at least {min:.1} is needed.")]
+#[help("Darken the poster so that the event name shown over it can be read")]
+#[diagnostic(code(WC0035), severity("warning"))]
+pub struct PosterLowContrast {
+    pub path: PathBuf,
+    pub contrast: f64,
+    pub min: f64,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event is skipped on {date}, but the event is not happening on this day.")]
+#[diagnostic(code(WC0036), severity("warning"))]
+pub struct SkippedOutOfRange {
+    pub date: NaiveDate,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The {stage} hook {command:?} failed with {status}.")]
+#[diagnostic(code(WC0037))]
+pub struct HookFailed {
+    pub stage: &'static str,
+    pub command: String,
+    pub status: ExitStatus,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The break ends before it starts.")]
+#[help("until is the last day of the break, and must not be before from")]
+#[diagnostic(code(WC0038))]
+pub struct HiatusEndsBeforeStart {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event is tentative for {date}, but the event is not happening on this day.")]
+#[diagnostic(code(WC0039), severity("warning"))]
+pub struct TentativeOutOfRange {
+    pub date: NaiveDate,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[help("Check the new times, and tell the organizers of the affected events")]
+#[diagnostic(code(WC0040), severity("warning"))]
+pub struct TzdbChanged {
+    /// The zones used by events whose upcoming offsets changed.
+    pub zones: Vec<String>,
+}
+
+impl fmt::Display for TzdbChanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The time zone data changed since the last compile.")?;
+        if !self.zones.is_empty() {
+            write!(
+                f,
+                " Upcoming events in these time zones are now at different times: {}",
+                self.zones.join(", "),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Both duration and end are set.")]
+#[help("Set only one of them. end is the time the event ends, and duration is how long it lasts")]
+#[diagnostic(code(WC0041))]
+pub struct DurationWithEnd {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event has neither a duration nor an end time.")]
+#[help("Add duration = \"1:00\" for how long the event lasts, or end = \"23:00\" for when it ends")]
+#[diagnostic(code(WC0042))]
+pub struct MissingDuration {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event has unstable fields, but --unstable was not given.")]
+#[help("Unstable fields may change in any version. Compile with --unstable to include them")]
+#[diagnostic(code(WC0043))]
+pub struct UnstableWithoutFlag {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event has no time zone.")]
+#[help("Add timezone = \"Asia/Tokyo\" with the event's time zone, or set one in _defaults.toml")]
+#[diagnostic(code(WC0044))]
+pub struct MissingTimeZoneField {
+    #[source_code]
+    pub src: NamedSource,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("There is no event file {path:?} to extend.")]
+#[help("The path is relative to the directory of the event file, like extends = \"base.toml\"")]
+#[diagnostic(code(WC0045))]
+pub struct ExtendsNotFound {
+    pub path: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Another event extends this one, but it extends a file itself.")]
+#[help("Extend the same file from both events instead")]
+#[diagnostic(code(WC0046))]
+pub struct ExtendsChain {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The poster {path:?} has no {field}.")]
+#[help("Set {field} next to the poster, or turn off lint.{field} in meta.toml")]
+#[diagnostic(code(WC0047), severity("warning"))]
+pub struct MissingPosterCredit {
+    pub field: &'static str,
+    pub path: PathBuf,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The tag {tag:?} is not in the meta file's list of tags.")]
+#[help("The tags in meta.toml are: {known}")]
+#[diagnostic(code(WC0048))]
+pub struct UnknownTag {
+    pub tag: String,
+    pub known: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("languages.{language} has a {day} section, but the event is not held on {day}.")]
+#[help("Language sections translate the event's days, so they can only have the days the event is held on")]
+#[diagnostic(code(WC0049), severity("warning"))]
+pub struct LanguageDayNotHeld {
+    pub language: String,
+    pub day: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label("the event's days")]
+    pub days: Option<SourceSpan>,
+    #[label("not one of them")]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Line {line} of the bundled time zone data could not be read: {message}")]
+#[help("The zones that need this line are left out, so events in them can't be compiled")]
+#[diagnostic(code(WC0050), severity("warning"))]
+pub struct TzdbLineError {
+    pub line: usize,
+    pub message: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The time zone data for {name:?} could not be read.")]
+#[help("Update the compiler, or compile with one whose time zone data can be read")]
+#[diagnostic(code(WC0051))]
+pub struct BrokenTimeZone {
+    name: String,
+    #[source_code]
+    src: NamedSource,
+    #[label]
+    location: SourceSpan,
+}
+
+impl BrokenTimeZone {
+    pub fn new(event: &Event) -> Self {
+        Self::from_spanned(event.event.timezone(), event.timezone_file.into())
+    }
+
+    pub fn from_spanned(timezone: &Spanned<impl AsRef<str>>, src: NamedSource) -> Self {
+        Self {
+            name: timezone.get_ref().as_ref().to_owned(),
+            src,
+            location: timezone.span().into(),
+        }
+    }
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error(
+    "The output directory {} is {} the input directory {}.",
+    output.display(),
+    if output == input { "the same as" } else { "inside" },
+    input.display(),
+)]
+#[help("Write the output somewhere else, or exclude it with --exclude")]
+#[diagnostic(code(WC0052))]
+pub struct OutputInInput {
+    pub output: PathBuf,
+    pub input: PathBuf,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{handle:?} is not a {service} account.")]
+#[help("{expected}")]
+#[diagnostic(code(WC0053))]
+pub struct InvalidAccount {
+    pub service: &'static str,
+    pub handle: String,
+    pub expected: &'static str,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[cfg(feature = "rsvp")]
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The RSVP counts could not be fetched from {url}: {reason}")]
+#[help("The counts from the last fetch are used instead until they are older than the max_age of [rsvp]")]
+#[diagnostic(code(WC0054), severity("warning"))]
+pub struct RsvpFetchFailed {
+    pub url: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The color {color} has a contrast ratio of {contrast:.1} against the background, but at least {min:.1} is needed.")]
+#[help("Choose a color that stands out more from {background}")]
+#[diagnostic(code(WC0055), severity("warning"))]
+pub struct ColorLowContrast {
+    pub color: String,
+    pub background: String,
+    pub contrast: f64,
+    pub min: f64,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("This compile would remove {removed} of the {total} events ({events}), more than the {max}% allowed.")]
+#[help("Check that the input is complete")]
+#[diagnostic(code(WC0056), severity("warning"))]
+pub struct ManyEventsRemoved {
+    pub removed: usize,
+    pub total: usize,
+    pub max: f64,
+    pub events: String,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("This compile would cancel {canceled} more occurrences, more than the {max} allowed.")]
+#[help("Check that the cancellations are intended")]
+#[diagnostic(code(WC0057), severity("warning"))]
+pub struct ManyOccurrencesCanceled {
+    pub canceled: usize,
+    pub max: usize,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{count} events are featured, but at most {max} can be: {events}.")]
+#[help("Remove featured from some of the events, or raise max_featured")]
+#[diagnostic(code(WC0058))]
+pub struct TooManyFeatured {
+    pub count: usize,
+    pub max: usize,
+    pub events: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label("the limit")]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Only the top of the event file can have a list of worlds.")]
+#[help("Set one world here, or add days to the entries of the list at the top of the file")]
+#[diagnostic(code(WC0059))]
+pub struct RotationInSection {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("There is a note for {date}, but the event is not happening on this day.")]
+#[diagnostic(code(WC0060), severity("warning"))]
+pub struct NoteOutOfRange {
+    pub date: NaiveDate,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("No other event is in the series {id}.")]
+#[help("Check the spelling of the series, or remove it if the event is not part of one")]
+#[diagnostic(code(WC0061), severity("warning"))]
+pub struct LonelySeries {
+    pub id: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The description has {problem}.")]
+#[help("Links need a full web address, like https://example.com")]
+#[diagnostic(code(WC0062))]
+pub struct InvalidMarkup {
+    pub problem: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The holidays can't be loaded: {reason}.")]
+#[diagnostic(code(WC0063))]
+pub struct HolidaysUnavailable {
+    pub reason: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event is canceled on holidays, but the meta file doesn't set any.")]
+#[help("Set holidays in meta.toml, like holidays = \"JP\"")]
+#[diagnostic(code(WC0064), severity("warning"))]
+pub struct HolidaysNotSet {
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The {format} file can't be read as an event: {reason}")]
+#[diagnostic(code(WC0065))]
+pub struct EventConversionError {
+    pub format: &'static str,
+    pub reason: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The event's id {problem}.")]
+#[diagnostic(code(WC0066))]
+pub struct InvalidEventId {
+    pub problem: &'static str,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("join_via is {join_via:?}, but {field} is not set for the event.")]
+#[diagnostic(code(WC0067))]
+pub struct JoinViaWithoutField {
+    pub join_via: &'static str,
+    pub field: &'static str,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The language fallbacks loop: {languages}.")]
+#[diagnostic(code(WC0068))]
+pub struct FallbackLoop {
+    pub languages: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: SourceSpan,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error(
+    "languages.{language} is never shown, since it isn't a language of the calendar and no \
+     language falls back to it."
+)]
+#[diagnostic(code(WC0069), severity("warning"))]
+pub struct UnreachableLanguage {
+    pub language: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The {field} of {event:?} is {length} characters long, but meta.toml limits it to {max}.")]
+#[diagnostic(code(WC0070))]
+pub struct TextTooLong {
+    pub event: String,
+    pub field: &'static str,
+    pub length: usize,
+    pub max: usize,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("The {field} of {event:?} has {kind}, {code}, which the world can't show.")]
+#[diagnostic(code(WC0071))]
+pub struct InvisibleCharacter {
+    pub event: String,
+    pub field: &'static str,
+    pub kind: &'static str,
+    pub code: String,
+    #[source_code]
+    pub src: NamedSource,
+    #[label]
+    pub location: Option<SourceSpan>,
+}
+
+#[cfg(feature = "discord")]
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Message {message} confirms {id:?}, which is not an event.")]
+#[help("Events are referred to by their file name without the extension")]
+#[diagnostic(code(WC0072), severity("warning"))]
+pub struct ConfirmedUnknownEvent {
+    pub message: String,
+    pub id: String,
+}
+
+#[cfg(feature = "discord")]
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("Message {message} has {word:?}, which is not a date.")]
+#[help("Dates are written like 2023-06-30")]
+#[diagnostic(code(WC0073), severity("warning"))]
+pub struct ConfirmedInvalidDate {
+    pub message: String,
+    pub word: String,
+}
+
+#[cfg(feature = "discord")]
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{path:?} is confirmed for every day, so the confirmations were ignored.")]
+#[diagnostic(code(WC0074), severity("warning"))]
+pub struct ConfirmedEveryDay {
+    pub path: PathBuf,
+}
+
+#[cfg(feature = "discord")]
+#[derive(Debug, Diagnostic, thiserror::Error)]
+#[error("{path:?} is not held on {date}, so the confirmation was ignored.")]
+#[diagnostic(code(WC0075), severity("warning"))]
+pub struct ConfirmedDateNotHeld {
+    pub path: PathBuf,
+    pub date: NaiveDate,
+}
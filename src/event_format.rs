@@ -0,0 +1,666 @@
+//! Event files can also be written in YAML or JSON, for tools that produce those. They are
+//! converted to TOML as they are read, so the rest of the compiler only sees TOML. Locations in
+//! diagnostics point into the converted file, and are moved to the same keys and values in the
+//! original file when the diagnostics are shown.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fmt,
+    ops::Range,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use miette::{Diagnostic, LabeledSpan, SourceCode, SourceSpan};
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+use crate::span::SourceMap;
+
+/// The languages event files can be written in.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum EventFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl EventFormat {
+    /// The format of an event file, or `None` if the file isn't one. YAML and JSON event files
+    /// end in `.event.yaml`, `.event.yml` or `.event.json`, so that other YAML and JSON files in
+    /// the input directories, like `package.json`, aren't read as events.
+    pub fn of(path: &Path) -> Option<Self> {
+        let format = match path.extension()?.to_str()? {
+            "toml" => EventFormat::Toml,
+            "yaml" | "yml" => EventFormat::Yaml,
+            "json" => EventFormat::Json,
+            _ => return None,
+        };
+        if format != EventFormat::Toml
+            && Path::new(path.file_stem()?).extension() != Some(OsStr::new("event"))
+        {
+            return None;
+        }
+        Some(format)
+    }
+
+    /// The id of the event in the file at `path`, which is the file name without the extension,
+    /// and without `.event` for YAML and JSON files.
+    pub fn id(path: &Path) -> Option<&OsStr> {
+        let stem = path.file_stem()?;
+        match Self::of(path)? {
+            EventFormat::Toml => Some(stem),
+            EventFormat::Yaml | EventFormat::Json => Path::new(stem).file_stem(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EventFormat::Toml => "TOML",
+            EventFormat::Yaml => "YAML",
+            EventFormat::Json => "JSON",
+        }
+    }
+}
+
+/// Why a file couldn't be converted, and where in the original file if that is known.
+#[derive(Debug)]
+pub struct ConversionError {
+    pub reason: String,
+    pub location: Option<SourceSpan>,
+}
+
+/// A YAML or JSON event file that was converted to TOML.
+pub struct Converted {
+    /// The length of the converted file.
+    toml_len: usize,
+    /// The file as it is written, which diagnostics show.
+    original: Arc<String>,
+    /// The spans of keys and values in the converted file, with the spans of the same keys and
+    /// values in the original file.
+    spans: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl Converted {
+    /// Moves a span in the converted file to the innermost key or value containing it in the
+    /// original file.
+    fn relocate(&self, span: &SourceSpan) -> SourceSpan {
+        let start = span.offset();
+        let end = start + span.len();
+        // Errors about the whole event, like a missing field, cover the whole file.
+        if start == 0 && end >= self.toml_len {
+            return (0, self.original.len()).into();
+        }
+        self.spans
+            .iter()
+            .filter(|(toml, _)| toml.start <= start && end <= toml.end)
+            .min_by_key(|(toml, _)| toml.len())
+            .map_or((0, 0).into(), |(_, original)| {
+                (original.start, original.len()).into()
+            })
+    }
+}
+
+/// The converted event files by the name their diagnostics show them with. Diagnostics only give
+/// their source code as a `dyn SourceCode`, so this is how `Relocated` finds the conversion.
+static CONVERTED: Mutex<BTreeMap<String, Arc<Converted>>> = Mutex::new(BTreeMap::new());
+
+/// Converts the content of a YAML or JSON event file to TOML, and returns it with the original.
+/// The conversion is kept for the diagnostics about the file, which show it as `name`.
+pub fn convert(
+    format: EventFormat,
+    name: String,
+    original: String,
+) -> Result<(String, Arc<String>), ConversionError> {
+    let table = to_toml(format, &original)?;
+    let toml = toml::to_string(&table).map_err(|error| ConversionError {
+        reason: error.to_string(),
+        location: None,
+    })?;
+
+    let mut located: Vec<_> = SourceMap::parse(&toml)
+        .map(|map| map.all())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(path, toml_key, toml_value)| {
+            let key = probe(&original, &path, true).map(|start| {
+                let key = path.last().map(String::as_str);
+                start..scalar_end(&original, start, key)
+            });
+            let value = probe(&original, &path, false)
+                .map(|start| start..value_end(&original, start, value_at(&table, &path)));
+            (path, toml_key.zip(key), toml_value.zip(value))
+        })
+        .collect();
+    // Tables and arrays end where the last of their contents does, or after their closing
+    // bracket if they are written in brackets.
+    for index in 0..located.len() {
+        let end = located
+            .iter()
+            .filter(|(path, _, _)| {
+                path.len() > located[index].0.len() && path.starts_with(&located[index].0)
+            })
+            .filter_map(|(_, _, value)| Some(value.as_ref()?.1.end))
+            .max();
+        if let (Some((_, value)), Some(end)) = (&mut located[index].2, end) {
+            value.end = value.end.max(end);
+            let close = match original[value.start..].chars().next() {
+                Some('[') => ']',
+                Some('{') => '}',
+                _ => continue,
+            };
+            let rest = &original[value.end..];
+            let after = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+            if after.starts_with(close) {
+                value.end += rest.len() - after.len() + 1;
+            }
+        }
+    }
+
+    let converted = Arc::new(Converted {
+        // Spans of the whole file leave out the last line break.
+        toml_len: toml.trim_end().len(),
+        original: Arc::new(original),
+        spans: located
+            .into_iter()
+            .flat_map(|(_, key, value)| [key, value])
+            .flatten()
+            .collect(),
+    });
+    let original = converted.original.clone();
+    CONVERTED.lock().unwrap().insert(name, converted);
+    Ok((toml, original))
+}
+
+/// A diagnostic with the locations in converted event files moved to the original files.
+pub struct Relocated<'a> {
+    inner: &'a dyn Diagnostic,
+    converted: Option<Arc<Converted>>,
+    source: Option<Box<Relocated<'a>>>,
+    related: Option<Vec<Relocated<'a>>>,
+}
+
+impl<'a> Relocated<'a> {
+    pub fn new(inner: &'a dyn Diagnostic) -> Self {
+        let converted = inner.source_code().and_then(|source| {
+            let contents = source.read_span(&(0, 0).into(), 0, 0).ok()?;
+            CONVERTED.lock().unwrap().get(contents.name()?).cloned()
+        });
+        Relocated {
+            inner,
+            converted,
+            source: inner
+                .diagnostic_source()
+                .map(|source| Box::new(Relocated::new(source))),
+            related: inner
+                .related()
+                .map(|related| related.map(Relocated::new).collect()),
+        }
+    }
+}
+
+impl fmt::Debug for Relocated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.inner, f)
+    }
+}
+
+impl fmt::Display for Relocated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.inner, f)
+    }
+}
+
+impl std::error::Error for Relocated<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl Diagnostic for Relocated<'_> {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.code()
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.inner.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.inner.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.inner.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let labels = self.inner.labels()?;
+        let Some(converted) = &self.converted else {
+            return Some(labels);
+        };
+        Some(Box::new(labels.map(|label| {
+            LabeledSpan::new_with_span(
+                label.label().map(str::to_owned),
+                converted.relocate(label.inner()),
+            )
+        })))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        let related = self.related.as_ref()?;
+        Some(Box::new(related.iter().map(|d| d as &dyn Diagnostic)))
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.source.as_deref().map(|d| d as &dyn Diagnostic)
+    }
+}
+
+/// The marker of the errors `probe` causes.
+const PROBE: &str = "$__wc_compiler_probe";
+
+/// Finds where the key or the value at `path` starts in a YAML or JSON file, which is where a
+/// deserializer that fails when it gets there reports the error.
+fn probe(original: &str, path: &[String], key: bool) -> Option<usize> {
+    // JSON is read as YAML too, since serde_json reports where values end instead.
+    let deserializer = serde_yaml_ng::Deserializer::from_str(original);
+    let error = Probe { path, key }.deserialize(deserializer).err()?;
+    if !error.to_string().contains(PROBE) {
+        return None;
+    }
+    let location = error.location()?;
+    let line_start: usize = original
+        .split_inclusive('\n')
+        .take(location.line().checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    // Columns count characters.
+    let column = original[line_start..]
+        .char_indices()
+        .nth(location.column().checked_sub(1)?)
+        .map_or(original.len() - line_start, |(index, _)| index);
+    Some(line_start + column)
+}
+
+struct Probe<'a> {
+    path: &'a [String],
+    /// Whether to stop at the key of the last part of the path instead of its value.
+    key: bool,
+}
+
+impl Probe<'_> {
+    fn scalar<E: de::Error>(self) -> Result<(), E> {
+        match self.path {
+            [] => Err(E::custom(PROBE)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Probe<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Probe<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        self.scalar()
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let Some((first, rest)) = self.path.split_first() else {
+            return Err(de::Error::custom(PROBE));
+        };
+        // Array elements have no key.
+        let (Ok(index), false) = (first.parse::<usize>(), rest.is_empty() && self.key) else {
+            return Ok(());
+        };
+        for _ in 0..index {
+            if seq.next_element::<IgnoredAny>()?.is_none() {
+                return Ok(());
+            }
+        }
+        seq.next_element_seed(Probe {
+            path: rest,
+            key: self.key,
+        })?;
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let Some((first, rest)) = self.path.split_first() else {
+            return Err(de::Error::custom(PROBE));
+        };
+        let fail = rest.is_empty() && self.key;
+        while let Some(found) = map.next_key_seed(KeyProbe { key: first, fail })? {
+            if found {
+                return map.next_value_seed(Probe {
+                    path: rest,
+                    key: self.key,
+                });
+            }
+            map.next_value::<IgnoredAny>()?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds `key` in a map, and fails there if `fail` is set.
+struct KeyProbe<'a> {
+    key: &'a str,
+    fail: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for KeyProbe<'_> {
+    type Value = bool;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeyProbe<'_> {
+    type Value = bool;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a key")
+    }
+
+    fn visit_str<E: de::Error>(self, key: &str) -> Result<bool, E> {
+        match (key == self.key, self.fail) {
+            (true, true) => Err(E::custom(PROBE)),
+            (found, _) => Ok(found),
+        }
+    }
+}
+
+/// The value at `path` in a converted file.
+fn value_at<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    let mut value = table.get(first)?;
+    for key in rest {
+        value = match value {
+            toml::Value::Table(table) => table.get(key)?,
+            toml::Value::Array(array) => array.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Where the value at `start` that was converted to `value` ends. Tables and arrays are extended
+/// to their contents later.
+fn value_end(original: &str, start: usize, value: Option<&toml::Value>) -> usize {
+    let rest = &original[start..];
+    match value {
+        Some(toml::Value::String(text)) => scalar_end(original, start, Some(text)),
+        Some(toml::Value::Table(_) | toml::Value::Array(_)) => {
+            if rest.starts_with("{}") || rest.starts_with("[]") {
+                start + 2
+            } else {
+                start
+            }
+        }
+        _ => {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || ",]}#".contains(c))
+                .unwrap_or(rest.len());
+            start + len
+        }
+    }
+}
+
+/// Where the key or string at `start` ends, which is written as `text` if it isn't quoted.
+fn scalar_end(original: &str, start: usize, text: Option<&str>) -> usize {
+    let rest = &original[start..];
+    let mut chars = rest.char_indices();
+    let len = match chars.next() {
+        Some((_, quote @ ('"' | '\''))) => {
+            let mut len = rest.len();
+            while let Some((index, c)) = chars.next() {
+                if c == '\\' && quote == '"' {
+                    chars.next();
+                } else if c == quote {
+                    // Single quotes are escaped by doubling them.
+                    if quote == '\'' && rest[index + 1..].starts_with('\'') {
+                        chars.next();
+                        continue;
+                    }
+                    len = index + 1;
+                    break;
+                }
+            }
+            len
+        }
+        _ => match text.filter(|text| rest.starts_with(text)) {
+            Some(text) => text.len(),
+            // Plain text over several lines is only located by its first line.
+            None => rest.find('\n').unwrap_or(rest.len()),
+        },
+    };
+    start + len
+}
+
+/// Converts the content of a YAML or JSON event file to a TOML table.
+fn to_toml(format: EventFormat, content: &str) -> Result<toml::Table, ConversionError> {
+    match format {
+        EventFormat::Toml => toml::from_str(content).map_err(|error| ConversionError {
+            reason: error.message().to_owned(),
+            location: error.span().map(Into::into),
+        }),
+        EventFormat::Yaml => {
+            let value: serde_yaml_ng::Value =
+                serde_yaml_ng::from_str(content).map_err(|error| ConversionError {
+                    location: error.location().map(|location| location.index().into()),
+                    reason: error.to_string(),
+                })?;
+            write_toml(from_yaml(value, ""))
+        }
+        EventFormat::Json => {
+            let value: serde_json::Value =
+                serde_json::from_str(content).map_err(|error| ConversionError {
+                    location: offset(content, error.line(), error.column()).map(Into::into),
+                    reason: error.to_string(),
+                })?;
+            write_toml(from_json(value, ""))
+        }
+    }
+}
+
+fn write_toml(value: Result<toml::Value, String>) -> Result<toml::Table, ConversionError> {
+    let reason = match value {
+        Ok(toml::Value::Table(table)) => return Ok(table),
+        Ok(_) => "the file has to be a map of fields".to_owned(),
+        Err(reason) => reason,
+    };
+    Err(ConversionError {
+        reason,
+        location: None,
+    })
+}
+
+/// How the value at `path` is called in errors.
+fn describe(path: &str) -> String {
+    if path.is_empty() {
+        "the file".to_owned()
+    } else {
+        path.to_owned()
+    }
+}
+
+fn child(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn from_json(value: serde_json::Value, path: &str) -> Result<toml::Value, String> {
+    use serde_json::Value;
+    Ok(match value {
+        Value::Null => {
+            return Err(format!(
+                "{} is null, which TOML doesn't have, so it has to be left out",
+                describe(path),
+            ))
+        }
+        Value::Bool(value) => toml::Value::Boolean(value),
+        Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(integer), _) => toml::Value::Integer(integer),
+            (None, Some(float)) => toml::Value::Float(float),
+            (None, None) => return Err(format!("{} is too large", describe(path))),
+        },
+        Value::String(value) => toml::Value::String(value),
+        Value::Array(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| from_json(item, &format!("{path}[{index}]")))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => toml::Value::Table(
+            fields
+                .into_iter()
+                .map(|(key, field)| {
+                    let field = from_json(field, &child(path, &key))?;
+                    Ok((key, field))
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+fn from_yaml(value: serde_yaml_ng::Value, path: &str) -> Result<toml::Value, String> {
+    use serde_yaml_ng::Value;
+    Ok(match value {
+        Value::Null => {
+            return Err(format!(
+                "{} is null, which TOML doesn't have, so it has to be left out",
+                describe(path),
+            ))
+        }
+        Value::Bool(value) => toml::Value::Boolean(value),
+        Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(integer), _) => toml::Value::Integer(integer),
+            (None, Some(float)) => toml::Value::Float(float),
+            (None, None) => return Err(format!("{} is too large", describe(path))),
+        },
+        Value::String(value) => toml::Value::String(value),
+        Value::Sequence(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| from_yaml(item, &format!("{path}[{index}]")))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(fields) => toml::Value::Table(
+            fields
+                .into_iter()
+                .map(|(key, field)| {
+                    let Value::String(key) = key else {
+                        return Err(format!("{} has a key that isn't a string", describe(path)));
+                    };
+                    let field = from_yaml(field, &child(path, &key))?;
+                    Ok((key, field))
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+        Value::Tagged(_) => return Err(format!("{} has a YAML tag", describe(path))),
+    })
+}
+
+/// The byte offset of a line and column, which both start at 1.
+fn offset(content: &str, line: usize, column: usize) -> Option<usize> {
+    let start: usize = content
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    Some((start + column.saturating_sub(1)).min(content.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the text `toml` of the converted file `name` is shown in the original.
+    fn relocated<'a>(name: &str, toml: &str, original: &'a str, text: &str) -> &'a str {
+        let converted = CONVERTED.lock().unwrap()[name].clone();
+        let span = converted.relocate(&(toml.find(text).unwrap(), text.len()).into());
+        &original[span.offset()..span.offset() + span.len()]
+    }
+
+    #[test]
+    fn yaml_locations_are_moved_to_the_original() {
+        let original = "# Party\nname: \"Ünï \\\"q\\\"\"\nstart: 21:00\ntags:\n  - a\n  - 'b''c'\n";
+        let (toml, _) = convert(EventFormat::Yaml, "a.event.yaml".into(), original.into()).unwrap();
+        assert_eq!(
+            relocated("a.event.yaml", &toml, original, r#""Ünï \"q\"""#),
+            r#""Ünï \"q\"""#
+        );
+        assert_eq!(relocated("a.event.yaml", &toml, original, "start"), "start");
+        assert_eq!(
+            relocated("a.event.yaml", &toml, original, "\"21:00\""),
+            "21:00"
+        );
+        assert_eq!(
+            relocated("a.event.yaml", &toml, original, "\"b'c\""),
+            "'b''c'"
+        );
+    }
+
+    #[test]
+    fn json_locations_are_moved_to_the_original() {
+        let original = "{\n\t\"name\": \"パーティー\",\n\t\"days\": {\"monday\": {}},\n\t\"weeks\": [1, 3]\n}\n";
+        let (toml, _) = convert(EventFormat::Json, "a.event.json".into(), original.into()).unwrap();
+        assert_eq!(
+            relocated("a.event.json", &toml, original, "\"パーティー\""),
+            "\"パーティー\""
+        );
+        assert_eq!(relocated("a.event.json", &toml, original, "3"), "3");
+        assert_eq!(
+            relocated("a.event.json", &toml, original, "[1, 3]"),
+            "[1, 3]"
+        );
+        assert_eq!(
+            relocated("a.event.json", &toml, original, "[days.monday]"),
+            "{}"
+        );
+        assert_eq!(relocated("a.event.json", &toml, original, &toml), original);
+    }
+}
@@ -0,0 +1,638 @@
+use std::process::ExitCode;
+
+use miette::miette;
+
+#[derive(clap::Args)]
+pub struct ExplainArgs {
+    /// The diagnostic code, like WC0003.
+    code: String,
+}
+
+/// Detailed guidance for each diagnostic code in error.rs. Codes are never reused.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "WC0001",
+        "A TOML file could not be parsed.
+
+Either the file is not valid TOML, or it contains a field that the compiler does not
+recognize. Check the spelling of the field against the README. Field names are case
+sensitive, and day and language sections must be written as [days.monday] and
+[languages.ja].",
+    ),
+    (
+        "WC0002",
+        "state.json in the output directory could not be parsed.
+
+The state file records which poster slots are in use. It is written by the compiler and
+should not be edited by hand. If it was damaged, restore it from the previously published
+output. Deleting it makes the compiler start over, which may show users the wrong posters
+until their caches expire.",
+    ),
+    (
+        "WC0003",
+        "The time zone is not known.
+
+Time zones must be IANA time zone names like \"America/New_York\" or \"Asia/Tokyo\".
+Abbreviations like \"EST\" or \"JST\" and offsets like \"+09:00\" are not accepted. See
+https://en.wikipedia.org/wiki/List_of_tz_database_time_zones",
+    ),
+    (
+        "WC0004",
+        "The event's time zone does not match its organizer's time zone.
+
+The organizer's time zone is declared in the [organizers] section of meta.toml. Events
+compiled under the wrong region's time zone show the wrong times to everyone, so check
+that the event's timezone is correct. If the event really is held in a different time
+zone than its organizer lives in, this warning can be ignored.",
+    ),
+    (
+        "WC0005",
+        "The event's time zone changed since the last compile.
+
+This is usually a mistake, such as copying another event's file as a template. If the
+change was intended, the warning will not appear again after the next successful
+compile.",
+    ),
+    (
+        "WC0006",
+        "The poster image is too large.
+
+Posters cannot be larger than 2048x2048 pixels because VRChat cannot load larger images.
+Resize the image and try again.",
+    ),
+    (
+        "WC0007",
+        "The event has more than one poster.
+
+Posters are found by replacing the extension of the event file with .webp, .jpeg, .jpg,
+or .png. Only the first one found is used. Delete the extra image files.",
+    ),
+    (
+        "WC0008",
+        "A confirmed date is not a day when the event is held.
+
+Check that the date is correct and that the day of the week has a section in [days].
+Dates outside of start_date and end_date are also not held.",
+    ),
+    (
+        "WC0009",
+        "A canceled date is not a day when the event is held.
+
+Check that the date is correct and that the day of the week has a section in [days].
+Dates outside of start_date and end_date, and weeks not listed in weeks, are also not
+held.",
+    ),
+    (
+        "WC0010",
+        "A note expires on a different day of the week than the day it is for.
+
+The expiry date is the last day the note is shown. Notes usually expire on the day of the
+occurrence they describe, so check that the date is correct.",
+    ),
+    (
+        "WC0011",
+        "The description is longer than the limit.
+
+Long descriptions overflow the text in the world. Shorten the description, or change
+max_description_length in the [lint] section of meta.toml.",
+    ),
+    (
+        "WC0012",
+        "The event does not have a poster.
+
+Add an image with the same name as the event file, or set poster to the image's file name.
+This lint can be turned off with missing_poster = false in the [lint] section of
+meta.toml.",
+    ),
+    (
+        "WC0013",
+        "The event does not have a hashtag.
+
+This lint can be turned off with missing_hashtag = false in the [lint] section of
+meta.toml.",
+    ),
+    (
+        "WC0014",
+        "Weeks are specified, but the event is held every day.
+
+weeks limits the event to certain weeks of the month, which is rarely intended for daily
+events. Add sections to [days] for the days of the week the event is held.",
+    ),
+    (
+        "WC0015",
+        "A day has different times for a platform the event is not on.
+
+Add the platform to platforms, or remove the override.",
+    ),
+    (
+        "WC0016",
+        "Two input files have names that only differ by case or Unicode normalization.
+
+Windows and macOS treat these names as the same file, so the compiled output depends on
+which system it was compiled on. Rename one of the files.",
+    ),
+    (
+        "WC0017",
+        "Writing an output file failed for a reason that is usually temporary.
+
+Another program, like a file sync client or a virus scanner, may be holding the file open.
+The compiler waits and tries again. The number of retries and the wait can be changed with
+--retries and --retry-delay.",
+    ),
+    (
+        "WC0018",
+        "The compiled output does not match the input.
+
+--verify compiles the input as of the time data.json was compiled, and compares the result
+with data.json. A difference means the input changed after the last compile, or the output
+was edited by hand. Compile the input again and publish the new output.",
+    ),
+    (
+        "WC0019",
+        "The name of an event file is not valid Unicode.
+
+The file name is used as the event's id in the compiled data, which must be Unicode. This
+can happen with files copied from older systems that used a different encoding. Rename the
+file, and use `name` inside the file if the event's name needs special characters.",
+    ),
+    (
+        "WC0020",
+        "An event lists a related event that does not exist.
+
+Related events are referred to by their id, which is the name of their file without the
+.toml extension. Check the spelling, or remove the id if the other event was deleted.",
+    ),
+    (
+        "WC0021",
+        "A field was renamed, and the file still uses the old name.
+
+The old name is accepted for a while so that existing files keep working, but it will stop
+working in a later version. Replace it with the new name. The fmt command does this for event
+files.",
+    ),
+    (
+        "WC0022",
+        "Two event files in the input directories or their subdirectories have the same name.
+
+An event's id is its file name without the extension, and the ids of all events in a
+calendar must be unique. Rename one of the files, and update any `related` lists that refer
+to it.",
+    ),
+    (
+        "WC0023",
+        "The event starts during the quiet hours configured in meta.toml.
+
+Quiet hours are set per time zone with [[lint.quiet_hours]]. They apply to events in that
+time zone and to events with sections for the listed languages. A start in the middle of the
+night is often a mix-up between AM and PM, like 2:00 instead of 14:00. If the time is
+intended, the warning can be ignored.",
+    ),
+    (
+        "WC0024",
+        "A cancellation reason is given for a date that is not canceled.
+
+Reasons in canceled_reasons, or in a language's canceled_reasons, are only shown for dates
+that are also listed in canceled. Add the date to canceled, or remove the reason.",
+    ),
+    (
+        "WC0025",
+        "Only one of every and anchor is set.
+
+An event held every few weeks needs both: every is the number of weeks between occurrences,
+and anchor is a date in a week when the event is held. Weeks start on Monday.",
+    ),
+    (
+        "WC0026",
+        "every is used together with weeks.
+
+weeks selects weeks of the month, and every selects weeks counted from the anchor. The two
+can't be combined. Remove weeks from the event and its days, or remove every and anchor.",
+    ),
+    (
+        "WC0027",
+        "A date in dates is not a day when the event is held.
+
+dates limits the event to the listed dates, but the rest of the schedule still applies. Check
+that the date is correct and that the day of the week has a section in [days]. Dates outside
+of start_date and end_date, and weeks excluded by weeks or every, are also not held.",
+    ),
+    (
+        "WC0028",
+        "An event file has both [[sessions]] and [days] sections.
+
+Sessions are another way to write the day sections, so only one of them can be used in a file.
+Move the details of each day section into a session with the same day, or the other way around.",
+    ),
+    (
+        "WC0029",
+        "Two sessions in an event file are on the same day of the week.
+
+An event can only be held once a day. If the times differ by platform, give one session with
+platform overrides in it.",
+    ),
+    (
+        "WC0030",
+        "An override is for a date when the event is not held.
+
+Overrides change a single occurrence of the event, so the date must be one of its occurrences.
+Check that the date is correct and that the event is held on that day of the week. Dates
+outside of start_date and end_date, or excluded by weeks, every, or dates, are not held.",
+    ),
+    (
+        "WC0031",
+        "monthly is not a day of the month.
+
+monthly is the day of the month the event is held, from 1 to 31. Months without that day use
+their last day, so 31 means the last day of every month.",
+    ),
+    (
+        "WC0032",
+        "monthly is combined with a schedule based on the day of the week.
+
+A monthly event is held on the same date every month, so it can't also be limited to some days
+of the week with day sections or sessions, or to some weeks with weeks or every. Remove either
+monthly or the other fields. Use dates for events held on a few specific dates.",
+    ),
+    (
+        "WC0033",
+        "A link in the meta file or in the links of an event is not a web address.
+
+Links are opened from the calendar, so they must be complete addresses with https:// or
+http:// at the start, like https://example.com/calendar.",
+    ),
+    (
+        "WC0034",
+        "A language section of the meta file doesn't have a translated title.
+
+This is reported by the lint command when language_titles is enabled in [lint]. Add a title to
+the language section that is different from the calendar's title.",
+    ),
+    (
+        "WC0035",
+        "A poster is too bright for the event name to be read over it.
+
+This is reported by the lint command when poster_contrast is enabled in [lint]. The world shows
+the event name in white over the poster, so the poster needs to be dark enough on average.
+Darken the image, or add a darker area behind where the name is shown. The required contrast
+ratio can be changed with min_poster_contrast.",
+    ),
+    (
+        "WC0036",
+        "A date in skipped is not a day when the event is held.
+
+Skipping a date removes that occurrence, so the date must be one the event would be held on.
+Check that the date is correct and that the day of the week has a section in [days]. A date
+that is skipped this way is ignored.",
+    ),
+    (
+        "WC0037",
+        "A hook command from the meta file failed.
+
+The command exited with an error, so the compile was stopped. Its own output should explain
+why. A failed before_write hook stops the output from being written. When an after_write hook
+fails, the output has already been written.",
+    ),
+    (
+        "WC0038",
+        "The event's hiatus ends before it starts.
+
+from is the first day of the break and until is the last day, so until can't be before
+from. Check that the year is correct for breaks over the new year.",
+    ),
+    (
+        "WC0039",
+        "A tentative date is not a day when the event is held.
+
+Check that the date is correct and that the day of the week has a section in [days].
+Dates outside of start_date and end_date are also not held.",
+    ),
+    (
+        "WC0040",
+        "The bundled time zone data changed since the last compile.
+
+The compiler includes a copy of the IANA time zone database, and state.json records a
+checksum of it. An update to the database can change when daylight saving time starts or
+ends, or the offset of a whole region, which moves the published times of events in that
+time zone. The time zones listed are used by events and have different offsets from now
+until the end of the period covered by the last compile. Check that the new times are
+correct, and tell the organizers of the affected events. The warning does not appear again
+after the next compile.",
+    ),
+    (
+        "WC0041",
+        "Both duration and end are set for the event or one of its days.
+
+The length of an event can be given as a duration, like duration = \"1:30\", or as the time
+it ends, like end = \"23:30\". Only one of them can be used in the same section. Remove the
+one that is wrong.",
+    ),
+    (
+        "WC0042",
+        "The event has neither a duration nor an end time.
+
+Every event needs to say how long it lasts, either with duration, like duration = \"1:30\",
+or with the time it ends, like end = \"23:30\". An end time at or before the start time is
+on the next day. Day sections can change the duration or end, but the event itself needs
+one for the days that don't.",
+    ),
+    (
+        "WC0043",
+        "The event has an [unstable] section, but the compiler was run without --unstable.
+
+New fields are tried out in [unstable] before they become part of the event format, and
+they may still change or be removed in any version. They are only copied to the output when
+the calendar opts in with --unstable, so that frontends don't start depending on them by
+accident. Add --unstable to the compile, or remove the section.",
+    ),
+    (
+        "WC0070",
+        "A name, description or hashtag is longer than meta.toml allows.
+
+The [max_length] table in meta.toml limits how many characters each field can have, so that the
+text fits where the world shows it. Descriptions are counted without their markup, and an event
+without a name is counted by its file name. Shorten the text, or raise the limit.",
+    ),
+    (
+        "WC0071",
+        "A name or hashtag has a character that the world can't show.
+
+Control characters like line breaks, zero-width characters like U+200B, and bidi control characters
+like U+202E break the text where the world shows it, and can make a name look like another one or
+hide part of it. They are usually pasted in by accident. Remove the character from the text, or
+rename the file if the event is named by it.",
+    ),
+    (
+        "WC0044",
+        "The event has no time zone.
+
+Every event needs a time zone, like timezone = \"Asia/Tokyo\", for its times to mean
+anything. It can be set in the event file, or in a _defaults.toml in the event's directory
+or a directory above it, for all the events there.",
+    ),
+    (
+        "WC0045",
+        "There is no event file to extend.
+
+extends names another event file, relative to the directory of the event file, like
+extends = \"base.toml\" or extends = \"../base.toml\". The file has to be one of the
+event files being compiled, so it can't be left out with --include or --exclude, and it
+can't be a _defaults.toml.",
+    ),
+    (
+        "WC0046",
+        "An event that is extended extends a file itself.
+
+Events can only extend an event that doesn't extend another file, so every field comes
+from at most two files. Make both events extend the same file instead.",
+    ),
+    (
+        "WC0047",
+        "A poster doesn't have a credit or license.
+
+This is reported by the lint command when poster_credit or poster_license is enabled in [lint].
+Set poster_credit to who made the poster, or poster_license to the license it is shared under,
+in the same table as the poster. For the poster found by the event file's name, set them at the
+top of the event file.",
+    ),
+    (
+        "WC0048",
+        "An event has a tag that is not in the meta file's list of tags.
+
+When the meta file has a list of tags, like tags = [\"music\", \"dance\"], events can only use
+those tags, so that a typo doesn't become a new tag. Fix the tag in the event file, or add it
+to the list in meta.toml.",
+    ),
+    (
+        "WC0049",
+        "A language section has a day the event is not held on.
+
+Language sections translate the names, notes, and other text of the event and its days. They
+don't change which days the event is held on, so a day section in a language section that the
+event itself doesn't have has no effect. This usually happens when a language section is
+copied from another event. Remove the day from the language section, or add it to the event.",
+    ),
+    (
+        "WC0050",
+        "A line in the bundled time zone database could not be read.
+
+The compiler includes its own copy of the time zone database. If a line in it can't be read,
+the zone or rule it belongs to is skipped and the rest of the database is used as usual. Events
+in the affected zones can't be compiled until the database is fixed. This is a problem with the
+compiler, not the input; please report it.",
+    ),
+    (
+        "WC0051",
+        "An event uses a time zone that could not be read from the bundled time zone database.
+
+The zone exists, but part of its data, or of the daylight saving time rules it uses, was
+skipped because it could not be read. A warning with code WC0050 explains which line failed.
+The event is left out of the output rather than compiled with wrong times. This is a problem
+with the compiler, not the input; please report it.",
+    ),
+    (
+        "WC0052",
+        "The output directory is an input directory, or inside one.
+
+The compiler reads every file in the input directories, so it would read the files it wrote
+on the previous compile as input, and report confusing errors about them. Write the output
+somewhere else. An output directory inside the input directory can be left out with
+--exclude, and hidden directories, whose names start with a dot, are always left out.",
+    ),
+    (
+        "WC0053",
+        "A social account of an event doesn't look like a handle on that platform.
+
+The compiler turns the handles of Bluesky, Misskey, YouTube, and Twitch accounts into links to
+their pages, so they must have the form each platform uses:
+
+    bluesky = \"myevent.bsky.social\"
+    misskey = \"@myevent@misskey.io\"
+    youtube = \"@myevent\"
+    twitch = \"my_event\"
+
+Write the handle, not the address of the page. A YouTube channel without a handle can be
+given by its id, which starts with UC.",
+    ),
+    (
+        "WC0054",
+        "The RSVP counts could not be fetched.
+
+With --rsvp, the compiler fetches the RSVP counts from the url in the [rsvp] section of the
+meta file. The source must be a JSON object of event ids to dates to counts, or a CSV file with
+event and date columns. If it can't be fetched or read, the counts from the last successful
+fetch, which are kept in state.json, are written instead until they are older than the max_age
+of [rsvp], 24 hours by default.",
+    ),
+    (
+        "WC0055",
+        "An event's color is too close to the background the world shows it on.
+
+This is reported by the lint command when color_background is set in [lint]. The world uses the
+color to highlight the event, so it needs to stand out from the background. The required contrast
+ratio can be changed with min_color_contrast.",
+    ),
+    (
+        "WC0056",
+        "A compile would remove many of the events of the previous compile.
+
+The compiler compares the events by id with the previous compile in the output directory. Events
+that are gone or canceled entirely count as removed. This catches mistakes like a wrong input path or a bad merge
+before they are published. With --strict, nothing is written. If the events were removed on
+purpose, compile without --strict, or raise max_removed_percent in the [guardrails] section of
+the meta file.",
+    ),
+    (
+        "WC0057",
+        "A compile would cancel many occurrences that the previous compile didn't.
+
+The compiler compares the canceled dates with data.json or data.msgpack in the output directory,
+whichever the compile writes. With --strict, nothing is written. If the cancellations are
+intended, compile without --strict, or raise max_canceled in the [guardrails] section of the meta
+file.",
+    ),
+    (
+        "WC0058",
+        "More events are featured than the meta file allows.
+
+The world has a few pickup slots for featured events, and max_featured in the meta file sets how
+many events can set featured = true. Remove featured from the events that shouldn't be picked up
+now, or raise max_featured if the world has more slots.",
+    ),
+    (
+        "WC0059",
+        "A day or language section has a list of worlds.
+
+Events that rotate between worlds list them in world at the top of the event file, with the days
+of the week and weeks of the month each world is used on. A day or language section can only set
+one world, which replaces the list on that day.",
+    ),
+    (
+        "WC0060",
+        "A note is given for a date when the event is not held.
+
+The dates in notes are checked against the event's schedule, like confirmed dates. The date may
+be mistyped, or the event may not be held on that day of the week, or it may be skipped or in a
+break. Notes for occurrences that have passed are left out of the output.",
+    ),
+    (
+        "WC0061",
+        "Only one event belongs to a series.
+
+A series groups events that go together, so it is expected to have at least two events. An event
+that is alone in its series usually has a typo in the series id. This can also happen while the
+other events of a series are being added, or after they have ended.",
+    ),
+    (
+        "WC0062",
+        "A description has markup that can't be turned into styled text.
+
+Descriptions can use **bold**, *italic* and [link](https://example.com) text, and links need full
+web addresses. Write the whole address, including https://, or put a backslash before the [ if the
+brackets aren't a link, like \\[. In TOML strings with double quotes, the backslash itself needs
+another backslash.",
+    ),
+    (
+        "WC0063",
+        "The holidays in the meta file can't be loaded.
+
+holidays is either a built-in set, like \"JP\", or the path of an ICS file relative to the meta
+file. An ICS file needs a DTSTART with a date in each event. Without the holidays, events with
+cancel_on_holidays wouldn't be canceled on them, so the compile fails.",
+    ),
+    (
+        "WC0064",
+        "An event is canceled on holidays, but the meta file doesn't set any holidays.
+
+cancel_on_holidays uses the holidays set in the meta file, so it does nothing without them. Set
+holidays in the meta file, or remove cancel_on_holidays from the event.",
+    ),
+    (
+        "WC0065",
+        "A YAML or JSON event file can't be read.
+
+Event files in YAML or JSON are converted to TOML before they are read, so they need to be valid
+in their own format and only contain values TOML has. TOML has no null, so fields that aren't set
+have to be left out instead, and the keys of every map have to be strings.",
+    ),
+    (
+        "WC0066",
+        "An event's id can't be used.
+
+Events in meta.toml are identified by their id, like other events are by their file name, so every
+[[event]] table needs an id that could be a file name and that no other event in meta.toml has.
+Events with such an id are skipped. An event file can't set an id, since its name is its id.",
+    ),
+    (
+        "WC0067",
+        "join_via needs the field with what people use to join that way.
+
+Calendars show instructions for joining by join_via, which need a group for \"group\", people in
+join for \"friend_request\", a world for \"open_instance\" and a Discord server for
+\"discord\". The field can be set in the same section or in the sections it overrides, like
+the top of the file for a day. Set the field, or change join_via.",
+    ),
+    (
+        "WC0068",
+        "Languages in meta.toml fall back to each other in a loop.
+
+A language with a fallback in meta.toml uses the sections of the language it falls back to, and
+then of that language's fallback, until the chain ends with the event's base details. A chain that
+comes back to a language it has already passed would never end, so one of the fallbacks in the
+loop has to be removed.",
+    ),
+    (
+        "WC0069",
+        "An event has a section for a language that no viewer gets.
+
+When meta.toml sets fallbacks, the language sections that are used are the ones for the calendar's
+languages, the languages with a fallback, and the languages they fall back to. A section for any
+other language is never shown. Add the language to the calendar, let a language fall back to it,
+or remove the section.",
+    ),
+    (
+        "WC0072",
+        "A Discord message confirms an event that doesn't exist.
+
+confirm-discord reads lines like confirm <event id> <date> from the channel. The event id is the
+name of the event file without its extension, like party for party.toml. The line was skipped,
+usually because of a typo in the id or because the event was renamed. Post the confirmation again
+with the right id.",
+    ),
+    (
+        "WC0073",
+        "A Discord message confirms a date that can't be read.
+
+The dates after the event id in a confirm line are written as year, month and day, like
+2023-06-30. The word was skipped, and the other dates in the line were still confirmed. Post the
+date again in that form.",
+    ),
+    (
+        "WC0074",
+        "A Discord message confirms an event that is already confirmed for every day.
+
+An event without a confirmed list, or with confirmed = true, is held on every day it is scheduled,
+so there is nothing to add. Set confirmed = [] in the event file for the organizers to confirm
+each date from Discord.",
+    ),
+    (
+        "WC0075",
+        "A Discord message confirms a date that the event isn't held on.
+
+Only dates the event is scheduled on can be confirmed, so the date was skipped. Check the day of
+the week, the weeks of the month, and the start_date, end_date and skipped dates of the event.",
+    ),
+];
+
+pub fn run(args: ExplainArgs) -> ExitCode {
+    let code = args.code.to_ascii_uppercase();
+    match EXPLANATIONS.iter().find(|(c, _)| *c == code) {
+        Some((code, explanation)) => {
+            println!("{code}: {explanation}");
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("{:?}", miette!("Unknown diagnostic code {:?}", args.code));
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use miette::{miette, Context, IntoDiagnostic, Result};
+
+use crate::{
+    collect_files, discover::FileFilter, format, parse_events, read_event_files, report::Errors,
+    Event,
+};
+
+#[derive(clap::Args)]
+pub struct ExportTomlArgs {
+    /// The directory containing the event files.
+    input: PathBuf,
+    #[command(flatten)]
+    filter: FileFilter,
+    /// The directory to write the canonical event files to.
+    output: PathBuf,
+    /// Overwrite existing files in the output directory.
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: ExportTomlArgs, errors: Errors) -> ExitCode {
+    if let Err(error) = fs::create_dir_all(&args.output)
+        .into_diagnostic()
+        .wrap_err("Could not create output directory")
+    {
+        eprintln!("{error:?}");
+        return ExitCode::FAILURE;
+    }
+
+    let files = collect_files(&args.input, &args.filter);
+    let event_files = read_event_files(&files, &errors);
+    let events = parse_events(&event_files, &errors);
+
+    for event in &events {
+        if errors.should_stop() {
+            break;
+        }
+        if let Err(error) = export(event, &args) {
+            eprintln!("{error:?}");
+        }
+    }
+
+    if errors.count() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn export(event: &Event, args: &ExportTomlArgs) -> Result<()> {
+    // Events written in YAML or JSON are exported as TOML too.
+    let name = format!("{}.toml", event.id());
+    let path = args.output.join(&name);
+    if !args.force && path.exists() {
+        return Err(miette!(
+            help = "Use --force to overwrite it",
+            "{} already exists.",
+            path.display(),
+        ));
+    }
+    // The same rendering as fmt, so exported files are already formatted.
+    let content = format::canonical(&event.event)
+        .wrap_err_with(|| format!("Exporting {} failed.", event.source.path.display()))?;
+    format::save(&path, &content)
+}
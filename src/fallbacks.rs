@@ -0,0 +1,110 @@
+//! Languages that fall back to other languages, like Portuguese to Spanish, for events that don't
+//! have a section for the viewer's language. The last language in a chain falls back to the
+//! event's base details, like languages without a fallback.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use miette::{NamedSource, Report};
+use toml::Spanned;
+
+use crate::{
+    error::{FallbackLoop, UnreachableLanguage},
+    input, Event, Language,
+};
+
+/// The languages `language` falls back to, in order. A chain that loops ends before the language
+/// that would repeat.
+fn chain(fallbacks: &BTreeMap<Language, Spanned<Language>>, language: &Language) -> Vec<Language> {
+    let mut chain = Vec::new();
+    let mut current = language;
+    while let Some(next) = fallbacks.get(current) {
+        let next = next.get_ref();
+        if next == language || chain.contains(next) {
+            break;
+        }
+        chain.push(next.clone());
+        current = next;
+    }
+    chain
+}
+
+/// The chain of every language with a fallback, for frontends to follow without looking each
+/// language up again.
+pub fn resolve(meta: &input::Meta) -> BTreeMap<Language, Vec<Language>> {
+    meta.fallbacks
+        .keys()
+        .map(|language| (language.clone(), chain(&meta.fallbacks, language)))
+        .filter(|(_, chain)| !chain.is_empty())
+        .collect()
+}
+
+/// Reports languages that fall back to themselves through their chain. Each loop is reported once,
+/// at its first language.
+pub fn check_loops(meta: &input::Meta, meta_file: &Arc<String>) {
+    for (language, fallback) in &meta.fallbacks {
+        let mut languages = vec![language.clone()];
+        languages.extend(chain(&meta.fallbacks, language));
+        let last = languages.last().unwrap();
+        if meta.fallbacks.get(last).map(Spanned::get_ref) != Some(language)
+            || languages.iter().any(|other| other < language)
+        {
+            continue;
+        }
+        languages.push(language.clone());
+        eprintln!(
+            "{:?}",
+            Report::new(FallbackLoop {
+                languages: languages
+                    .iter()
+                    .map(Language::code)
+                    .collect::<Vec<_>>()
+                    .join(" → "),
+                src: NamedSource::new("meta.toml", meta_file.clone()),
+                location: fallback.span().into(),
+            }),
+        );
+    }
+}
+
+/// Warns about the event's language sections that no viewer gets, when the calendar has
+/// fallbacks. A section is used for the calendar's languages, the languages with a fallback, and
+/// the languages in their chains.
+pub fn check_event(event: &Event, meta: &input::Meta) {
+    if meta.fallbacks.is_empty() {
+        return;
+    }
+    let mut shown: BTreeSet<Language> = meta.languages.keys().cloned().collect();
+    for language in meta.fallbacks.keys() {
+        shown.insert(language.clone());
+        shown.extend(chain(&meta.fallbacks, language));
+    }
+    let mut languages: Vec<_> = event
+        .event
+        .languages
+        .keys()
+        .filter(|language| !shown.contains(language))
+        .collect();
+    if languages.is_empty() {
+        return;
+    }
+    languages.sort();
+    let spans = event.source.source_map();
+    for language in languages {
+        let code = language.code();
+        let location = spans.as_ref().and_then(|s| {
+            s.span(&["languages", code])
+                .or_else(|| s.span(&["lang", code]))
+        });
+        eprintln!(
+            "{:?}",
+            Report::new(UnreachableLanguage {
+                language: code.to_owned(),
+                src: event.source.into(),
+                location,
+            }),
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use miette::{miette, Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+
+use crate::{
+    discover::FileFilter, error::EventParseError, event_format::EventFormat, input,
+    is_defaults_file, retry::Retry, safely_save, EventFile,
+};
+
+#[derive(clap::Args)]
+pub struct FmtArgs {
+    /// Event files, or directories containing event files.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Report files that are not formatted instead of rewriting them.
+    #[arg(long)]
+    check: bool,
+    /// Rewrite files even if they contain comments, which are lost.
+    #[arg(long)]
+    discard_comments: bool,
+    #[command(flatten)]
+    filter: FileFilter,
+}
+
+pub fn run(args: FmtArgs) -> ExitCode {
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if path.is_dir() {
+            files.extend(
+                args.filter
+                    .walk(path)
+                    .into_iter()
+                    .filter(|path| is_event_file(path)),
+            );
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut failed = false;
+    for file in &files {
+        match format_file(file, &args) {
+            Ok(true) => {}
+            Ok(false) => failed = true,
+            Err(error) => {
+                eprintln!("{error:?}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn is_event_file(path: &Path) -> bool {
+    path.file_name() != Some(OsStr::new("meta.toml"))
+        && !is_defaults_file(path)
+        && path.extension() == Some(OsStr::new("toml"))
+}
+
+/// Renders an event in the canonical event file format.
+pub fn canonical(event: &input::Event) -> Result<String> {
+    toml::to_string(event).into_diagnostic()
+}
+
+/// Returns whether the file is (now) formatted.
+fn format_file(path: &Path, args: &FmtArgs) -> Result<bool> {
+    let content = fs::read_to_string(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Reading {} failed.", path.display()))?;
+    let file = EventFile {
+        path,
+        format: EventFormat::Toml,
+        entry: None,
+        content: Arc::new(content),
+        original: None,
+    };
+    let event = input::Event::deserialize(toml::Deserializer::new(&file.content))
+        .map_err(|error| EventParseError::new(error, &file))
+        .wrap_err_with(|| format!("Parsing {} failed.", path.display()))?;
+    let formatted =
+        canonical(&event).wrap_err_with(|| format!("Formatting {} failed.", path.display()))?;
+
+    if formatted == *file.content {
+        return Ok(true);
+    }
+    if args.check {
+        eprintln!("{} is not formatted", path.display());
+        return Ok(false);
+    }
+    if !args.discard_comments && has_comments(&file.content) {
+        eprintln!(
+            "{:?}",
+            miette!(
+                help = "Use --discard-comments to format it anyway",
+                "{} was not formatted because its comments would be lost.",
+                path.display(),
+            ),
+        );
+        return Ok(false);
+    }
+
+    save(path, &formatted)?;
+    eprintln!("Formatted {}", path.display());
+    Ok(true)
+}
+
+/// Writes an event file rendered by [`canonical`].
+pub fn save(path: &Path, formatted: &str) -> Result<()> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .ok_or_else(|| miette!("{} is not a file.", path.display()))?;
+    safely_save(directory, name, &Retry::default(), |t| {
+        t.write_all(formatted.as_bytes()).into_diagnostic()
+    })
+}
+
+/// Finds `#` outside of strings.
+fn has_comments(content: &str) -> bool {
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => return true,
+            '"' | '\'' => {
+                let rest = chars.as_str();
+                let multiline = if c == '"' { "\"\"" } else { "''" };
+                if let Some(rest) = rest.strip_prefix(multiline) {
+                    let end = if c == '"' { "\"\"\"" } else { "'''" };
+                    let Some(index) = find_unescaped(rest, end, c == '"') else {
+                        return false;
+                    };
+                    chars = rest[index + end.len()..].chars();
+                } else {
+                    let Some(index) = find_unescaped(rest, &c.to_string(), c == '"') else {
+                        return false;
+                    };
+                    chars = rest[index + 1..].chars();
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+fn find_unescaped(haystack: &str, needle: &str, escapes: bool) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in haystack.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if escapes && c == '\\' {
+            escaped = true;
+        } else if haystack[index..].starts_with(needle) {
+            return Some(index);
+        }
+    }
+    None
+}
@@ -0,0 +1,215 @@
+//! An Atom feed of the occurrences in the week after the compile, for feed readers and for bots
+//! that post new entries to chat. Each occurrence is an entry of its own, which appears a week
+//! before it starts.
+
+use std::{fmt::Write, str::FromStr};
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use miette::{miette, Result};
+
+use super::{
+    html::{self, escape},
+    occurs_on, slots, uid, OutputFormat,
+};
+use crate::output::{Data, Event, EventDay, Override, PosterInfo};
+
+/// How far ahead of the compile occurrences are included.
+const DAYS: i64 = 7;
+
+pub struct Atom;
+
+impl OutputFormat for Atom {
+    fn file_name(&self) -> &'static str {
+        "feed.xml"
+    }
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>> {
+        let compiled = Utc
+            .timestamp_opt(data.meta.compiled_time, 0)
+            .single()
+            .ok_or_else(|| miette!("The compile time is out of range"))?;
+        let until = compiled + Duration::days(DAYS);
+
+        let mut occurrences = Vec::new();
+        for event in data.events {
+            let tz = Tz::from_str(event.timezone)
+                .map_err(|_| miette!("Unknown time zone {:?}", event.timezone))?;
+            occurrences.extend(upcoming(event, tz, compiled, until));
+        }
+        occurrences.sort_by_key(|occurrence| occurrence.start);
+
+        let meta = data.meta;
+        let mut out = String::new();
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"utf-8\"?>").unwrap();
+        writeln!(out, "<feed xmlns=\"http://www.w3.org/2005/Atom\">").unwrap();
+        writeln!(out, "<title>{}</title>", escape(meta.title)).unwrap();
+        if let Some(description) = meta.description {
+            writeln!(out, "<subtitle>{}</subtitle>", escape(description)).unwrap();
+        }
+        let id = match meta.link {
+            Some(link) => escape(link),
+            None => format!("urn:wc-compiler:{}", uid(meta.title)),
+        };
+        writeln!(out, "<id>{id}</id>").unwrap();
+        writeln!(out, "<updated>{}</updated>", timestamp(compiled)).unwrap();
+        writeln!(out, "<author><name>{}</name></author>", escape(meta.title)).unwrap();
+        if let Some(link) = meta.link {
+            writeln!(out, "<link rel=\"alternate\" href=\"{}\"/>", escape(link)).unwrap();
+        }
+        for (language, details) in &meta.languages {
+            if let Some(link) = details.link.filter(|&link| Some(link) != meta.link) {
+                writeln!(
+                    out,
+                    "<link rel=\"alternate\" hreflang=\"{}\" href=\"{}\"/>",
+                    language.code(),
+                    escape(link),
+                )
+                .unwrap();
+            }
+        }
+        for occurrence in &occurrences {
+            write_entry(&mut out, occurrence, compiled);
+        }
+        writeln!(out, "</feed>").unwrap();
+        Ok(out.into_bytes())
+    }
+}
+
+struct Occurrence<'a> {
+    event: &'a Event<'a>,
+    day: &'a EventDay<'a>,
+    date: NaiveDate,
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+    change: Option<&'a Override<'a>>,
+}
+
+/// The occurrences of the event that start from `from` until `until`, without the ones that are
+/// canceled, skipped or in a break.
+fn upcoming<'a>(
+    event: &'a Event<'a>,
+    tz: Tz,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<Occurrence<'a>> {
+    // Occurrences can start after midnight of the day they belong to.
+    let first = (from - Duration::days(1)).with_timezone(&tz).date_naive();
+    let last = until.with_timezone(&tz).date_naive();
+    let mut occurrences = Vec::new();
+    for slot in slots(event) {
+        let weeks = slot.day.info.weeks.or(event.info.weeks);
+        for date in first.iter_days().take_while(|date| *date <= last) {
+            let listed = event
+                .dates
+                .as_ref()
+                .is_none_or(|dates| dates.contains(&date));
+            let on_break = event
+                .hiatus
+                .as_ref()
+                .is_some_and(|hiatus| (hiatus.from..=hiatus.until).contains(&date));
+            if !listed
+                || on_break
+                || !occurs_on(date, event, slot.weekday, weeks)
+                || event.canceled.contains(&date)
+                || event.skipped.contains(&date)
+            {
+                continue;
+            }
+            let change = event.overrides.get(&date);
+            let start = change.and_then(|c| c.start).unwrap_or(slot.start);
+            let duration = change.and_then(|c| c.duration).unwrap_or(slot.duration);
+            let Some(start) = date
+                .and_time(NaiveTime::MIN)
+                .checked_add_signed(Duration::minutes(start.into()))
+                .and_then(|start| start.and_local_timezone(tz).earliest())
+            else {
+                continue;
+            };
+            let in_range = event.start_date.is_none_or(|s| s <= start.timestamp())
+                && event.end_date.is_none_or(|e| start.timestamp() < e);
+            if !in_range || start < from || until <= start {
+                continue;
+            }
+            occurrences.push(Occurrence {
+                event,
+                day: slot.day,
+                date,
+                start,
+                end: start + Duration::minutes(duration.into()),
+                change,
+            });
+        }
+    }
+    occurrences
+}
+
+fn write_entry(out: &mut String, occurrence: &Occurrence, compiled: DateTime<Utc>) {
+    let Occurrence {
+        event,
+        day,
+        date,
+        start,
+        end,
+        change,
+    } = occurrence;
+    let name = change
+        .and_then(|c| c.name)
+        .or(day.name)
+        .unwrap_or(&event.name);
+    // Entries appear a week before the occurrence, which stays their time so that readers don't
+    // see them as updated on every compile.
+    let updated = (start.with_timezone(&Utc) - Duration::days(DAYS)).min(compiled);
+
+    writeln!(out, "<entry>").unwrap();
+    writeln!(out, "<title>{}</title>", escape(name)).unwrap();
+    writeln!(out, "<id>urn:wc-compiler:{}:{date}</id>", uid(&event.name)).unwrap();
+    writeln!(out, "<updated>{}</updated>", timestamp(updated)).unwrap();
+    if let Some(web) = day.info.web.or(event.info.web) {
+        writeln!(out, "<link rel=\"alternate\" href=\"{}\"/>", escape(web)).unwrap();
+    }
+    for (language, details) in &event.languages {
+        if let Some(web) = details.info.web {
+            writeln!(
+                out,
+                "<link rel=\"alternate\" hreflang=\"{}\" href=\"{}\"/>",
+                language.code(),
+                escape(web),
+            )
+            .unwrap();
+        }
+    }
+    let poster: Option<PosterInfo> = change
+        .and_then(|c| c.poster)
+        .or(day.info.poster)
+        .or(event.info.poster);
+    if let Some(poster) = poster {
+        // Posters are published next to the feed, and have no extension.
+        writeln!(
+            out,
+            "<link rel=\"enclosure\" href=\"posters/{:02x}\"/>",
+            poster.number,
+        )
+        .unwrap();
+    }
+
+    let mut content = format!(
+        "<p>{}–{} ({})</p>",
+        start.format("%Y-%m-%d %H:%M"),
+        end.format("%H:%M"),
+        escape(event.timezone),
+    );
+    let description = html::description(&day.info).or_else(|| html::description(&event.info));
+    if let Some(description) = description {
+        write!(content, "<p>{description}</p>").unwrap();
+    }
+    for note in day.note.into_iter().chain(event.notes.get(date).copied()) {
+        write!(content, "<p>{}</p>", escape(note)).unwrap();
+    }
+    writeln!(out, "<content type=\"html\">{}</content>", escape(&content)).unwrap();
+    writeln!(out, "</entry>").unwrap();
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
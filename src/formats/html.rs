@@ -0,0 +1,218 @@
+//! index.html, a readable schedule to publish next to data.json. It links to each event's
+//! section from the top, shows the posters from the output directory, and shows the times in the
+//! viewer's time zone as well, with a small script that uses the zone rules from data.json.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use miette::{IntoDiagnostic, Result};
+
+use super::{clock, end_clock, slot_day, slots, uid, OutputFormat};
+use crate::output::{Data, EventInfo};
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: auto; }
+section { display: flex; gap: 1em; border-top: 1px solid #ccc; padding: 1em 0; }
+img { max-width: 12em; max-height: 12em; object-fit: contain; }
+.local { color: #555; }";
+
+/// Fills in each `.local` element with the time of the slot's next occurrence in the viewer's time
+/// zone. The event's offsets come from `zones`, like in the world, rather than the browser's own
+/// time zone data.
+const SCRIPT: &str = "function offset(rules, time) {
+  let minutes = 0;
+  for (const rule of rules) {
+    if (rule.s === undefined || rule.s <= time) minutes = rule.o || 0;
+  }
+  return minutes * 60;
+}
+const now = Date.now() / 1000;
+for (const element of document.querySelectorAll('.local')) {
+  const zone = zones[element.dataset.tz];
+  if (!zone) continue;
+  let day = Math.floor((now + offset(zone.r, now)) / 86400) * 86400;
+  const weekday = element.dataset.weekday;
+  if (weekday !== undefined) {
+    while (new Date(day * 1000).getUTCDay() != weekday) day += 86400;
+  }
+  const local = day + element.dataset.start * 60;
+  const start = local - offset(zone.r, local - offset(zone.r, local));
+  const end = start + element.dataset.duration * 60;
+  const time = { hour: '2-digit', minute: '2-digit' };
+  const first = weekday === undefined ? time : { weekday: 'long', ...time };
+  element.textContent = ' · ' + new Date(start * 1000).toLocaleString(undefined, first) + '–'
+    + new Date(end * 1000).toLocaleTimeString(undefined, time) + ' your time';
+}";
+
+pub struct Html;
+
+impl OutputFormat for Html {
+    fn file_name(&self) -> &'static str {
+        "index.html"
+    }
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>> {
+        let mut out = String::new();
+        let title = escape(data.meta.title);
+        writeln!(out, "<!DOCTYPE html>").unwrap();
+        writeln!(out, "<html>").unwrap();
+        writeln!(out, "<head>").unwrap();
+        writeln!(out, "<meta charset=\"utf-8\">").unwrap();
+        writeln!(
+            out,
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+        )
+        .unwrap();
+        writeln!(out, "<title>{title}</title>").unwrap();
+        writeln!(out, "<style>{STYLE}</style>").unwrap();
+        writeln!(out, "</head>").unwrap();
+        writeln!(out, "<body>").unwrap();
+        writeln!(out, "<h1>{title}</h1>").unwrap();
+        if let Some(description) = data.meta.description {
+            writeln!(out, "<p>{}</p>", escape(description)).unwrap();
+        }
+        if let Some(link) = data.meta.link {
+            let link = escape(link);
+            writeln!(out, "<p><a href=\"{link}\">{link}</a></p>").unwrap();
+        }
+
+        writeln!(out, "<nav>").unwrap();
+        writeln!(out, "<ul>").unwrap();
+        for event in data.events {
+            writeln!(
+                out,
+                "<li><a href=\"#{}\">{}</a></li>",
+                uid(&event.name),
+                escape(&event.name),
+            )
+            .unwrap();
+        }
+        writeln!(out, "</ul>").unwrap();
+        writeln!(out, "</nav>").unwrap();
+
+        for event in data.events {
+            writeln!(out, "<section id=\"{}\">", uid(&event.name)).unwrap();
+            if let Some(poster) = event.info.poster {
+                // Posters are published next to this file, and have no extension.
+                write!(
+                    out,
+                    "<div><img src=\"posters/{:02x}\" alt=\"\">",
+                    poster.number
+                )
+                .unwrap();
+                let credit = [event.info.poster_credit, event.info.poster_license]
+                    .into_iter()
+                    .flatten()
+                    .map(escape)
+                    .collect::<Vec<_>>();
+                if !credit.is_empty() {
+                    write!(out, "<br><small>{}</small>", credit.join(", ")).unwrap();
+                }
+                writeln!(out, "</div>").unwrap();
+            }
+            writeln!(out, "<div>").unwrap();
+            writeln!(out, "<h2>{}</h2>", escape(&event.name)).unwrap();
+            if let Some(description) = description(&event.info) {
+                writeln!(out, "<p>{description}</p>").unwrap();
+            }
+            writeln!(out, "<ul>").unwrap();
+            for slot in slots(event) {
+                write!(
+                    out,
+                    "<li>{} {}–{} ({})",
+                    slot_day(event, &slot),
+                    clock(slot.start),
+                    end_clock(&slot),
+                    escape(event.timezone),
+                )
+                .unwrap();
+                write!(
+                    out,
+                    "<span class=\"local\" data-tz=\"{}\" data-start=\"{}\" data-duration=\"{}\"",
+                    escape(event.timezone),
+                    slot.start,
+                    slot.duration,
+                )
+                .unwrap();
+                // Monthly events don't keep to a day of the week.
+                if event.monthly.is_none() {
+                    write!(
+                        out,
+                        " data-weekday=\"{}\"",
+                        slot.weekday.num_days_from_sunday()
+                    )
+                    .unwrap();
+                }
+                write!(out, "></span>").unwrap();
+                if let Some(note) = slot.day.note {
+                    write!(out, ": {}", escape(note)).unwrap();
+                }
+                writeln!(out, "</li>").unwrap();
+            }
+            writeln!(out, "</ul>").unwrap();
+            if let Some(web) = event.info.web {
+                let web = escape(web);
+                writeln!(out, "<p><a href=\"{web}\">{web}</a></p>").unwrap();
+            }
+            for (label, link) in &event.info.links.0 {
+                let (label, link) = (escape(label), escape(link));
+                writeln!(out, "<p><a href=\"{link}\">{label}</a></p>").unwrap();
+            }
+            writeln!(out, "</div>").unwrap();
+            writeln!(out, "</section>").unwrap();
+        }
+
+        // Only the time zones of the events, to keep the page small.
+        let zones: BTreeMap<_, _> = data
+            .events
+            .iter()
+            .filter_map(|event| Some((event.timezone, data.zones.get(event.timezone)?)))
+            .collect();
+        let zones = serde_json::to_string(&zones).into_diagnostic()?;
+        writeln!(out, "<script>").unwrap();
+        writeln!(out, "const zones = {zones};").unwrap();
+        writeln!(out, "{SCRIPT}").unwrap();
+        writeln!(out, "</script>").unwrap();
+        writeln!(out, "</body>").unwrap();
+        writeln!(out, "</html>").unwrap();
+        Ok(out.into_bytes())
+    }
+}
+
+pub(super) fn description(info: &EventInfo) -> Option<String> {
+    let Some(runs) = &info.description_runs else {
+        return info.description.as_deref().map(escape);
+    };
+    let mut out = String::new();
+    for run in runs {
+        if run.text == "\n" {
+            out.push_str("<br>");
+            continue;
+        }
+        let mut text = escape(&run.text);
+        if run.italic {
+            text = format!("<em>{text}</em>");
+        }
+        if run.bold {
+            text = format!("<strong>{text}</strong>");
+        }
+        if let Some(url) = &run.url {
+            text = format!("<a href=\"{}\">{text}</a>", escape(url));
+        }
+        out.push_str(&text);
+    }
+    Some(out)
+}
+
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
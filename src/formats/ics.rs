@@ -0,0 +1,417 @@
+use std::{collections::BTreeSet, fmt::Write, str::FromStr};
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::{OffsetComponents, Tz};
+use miette::{miette, Result};
+
+use super::{interval, occurs_on, slots, uid, OutputFormat, Slot};
+use crate::output::{Data, DateSet, Event, Zone};
+
+pub struct Ics;
+
+impl OutputFormat for Ics {
+    fn file_name(&self) -> &'static str {
+        "calendar.ics"
+    }
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>> {
+        let compiled = Utc
+            .timestamp_opt(data.meta.compiled_time, 0)
+            .single()
+            .ok_or_else(|| miette!("The compile time is out of range"))?;
+
+        let mut out = Lines::default();
+        out.line("BEGIN:VCALENDAR");
+        out.line("VERSION:2.0");
+        out.line("PRODID:-//wc-compiler//EN");
+        out.line(&format!("X-WR-CALNAME:{}", escape(data.meta.title)));
+        let timezones: BTreeSet<_> = data.events.iter().map(|event| event.timezone).collect();
+        for name in timezones {
+            if let Some(zone) = data.zones.get(name) {
+                let tz = Tz::from_str(name).map_err(|_| miette!("Unknown time zone {name:?}"))?;
+                write_timezone(&mut out, name, zone, tz, compiled);
+            }
+        }
+        for event in data.events {
+            let tz = Tz::from_str(event.timezone)
+                .map_err(|_| miette!("Unknown time zone {:?}", event.timezone))?;
+            for slot in slots(event) {
+                write_slot(&mut out, event, &slot, tz, compiled);
+            }
+        }
+        out.line("END:VCALENDAR");
+        Ok(out.0.into_bytes())
+    }
+}
+
+/// Writes the offsets of a time zone, which calendar apps need to place the times given with its
+/// TZID. The zone only has the transitions until the end of the period it was collected for. If
+/// its last transitions happened on the same days a year before, they repeat every year after it,
+/// and otherwise its last offset is kept.
+fn write_timezone(out: &mut Lines, name: &str, zone: &Zone, tz: Tz, compiled: DateTime<Utc>) {
+    let offsets: Vec<i16> = zone
+        .offsets
+        .iter()
+        .map(|rule| rule.offset.unwrap_or(0))
+        .collect();
+    // Transitions are written in the local time before them. The offset that is in effect at the
+    // compile has no start, and is written as if it had always been.
+    let onsets: Vec<Option<NaiveDateTime>> = zone
+        .offsets
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let from = offsets[index.checked_sub(1)?];
+            let onset = Utc.timestamp_opt(rule.start? + i64::from(from) * 60, 0);
+            Some(onset.single()?.naive_utc())
+        })
+        .collect();
+    out.line("BEGIN:VTIMEZONE");
+    out.line(&format!("TZID:{name}"));
+    for (index, (rule, &offset)) in zone.offsets.iter().zip(&offsets).enumerate() {
+        let from = index
+            .checked_sub(1)
+            .map_or(offset, |previous| offsets[previous]);
+        // Whether the offset is daylight saving time is up to the zone's rules, since a zone can
+        // also change its standard offset. Ireland's winter time, which the rules give as negative
+        // daylight saving time, is written as standard time like calendar apps expect.
+        let start = rule
+            .start
+            .and_then(|start| Utc.timestamp_opt(start, 0).single())
+            .unwrap_or(compiled);
+        let dst = tz.offset_from_utc_datetime(&start.naive_utc()).dst_offset();
+        let kind = if dst > Duration::zero() {
+            "DAYLIGHT"
+        } else {
+            "STANDARD"
+        };
+        out.line(&format!("BEGIN:{kind}"));
+        out.line(&format!(
+            "DTSTART:{}",
+            onsets[index].map_or_else(
+                || "19700101T000000".to_owned(),
+                |onset| onset.format("%Y%m%dT%H%M%S").to_string(),
+            ),
+        ));
+        out.line(&format!("TZOFFSETFROM:{}", utc_offset(from)));
+        out.line(&format!("TZOFFSETTO:{}", utc_offset(offset)));
+        // Only the last transition to each offset can repeat after the period.
+        let year_before = (offsets.len() <= index + 2)
+            .then(|| index.checked_sub(2))
+            .flatten()
+            .filter(|&earlier| offsets[earlier] == offset && offsets[earlier + 1] == from);
+        if let Some(rule) =
+            year_before.and_then(|earlier| yearly_rule(onsets[earlier]?, onsets[index]?))
+        {
+            out.line(&rule);
+        }
+        out.line(&format!("END:{kind}"));
+    }
+    out.line("END:VTIMEZONE");
+}
+
+/// The yearly rule of a transition, if the transition before it was at the same time on the same
+/// weekday of the same week of the month, a year earlier.
+fn yearly_rule(earlier: NaiveDateTime, onset: NaiveDateTime) -> Option<String> {
+    // Transitions on the last weekday of a month are written that way, since its week varies.
+    let week = |date: NaiveDate| {
+        if (date + Days::new(7)).month() != date.month() {
+            -1
+        } else {
+            (date.day0() / 7 + 1) as i8
+        }
+    };
+    let (date, earlier_date) = (onset.date(), earlier.date());
+    (earlier_date.year() + 1 == date.year()
+        && earlier_date.month() == date.month()
+        && earlier_date.weekday() == date.weekday()
+        && week(earlier_date) == week(date)
+        && earlier.time() == onset.time())
+    .then(|| {
+        format!(
+            "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={}{}",
+            date.month(),
+            week(date),
+            byday(date.weekday()),
+        )
+    })
+}
+
+/// Formats an offset in minutes as `+hhmm`.
+fn utc_offset(minutes: i16) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.unsigned_abs();
+    format!("{sign}{:02}{:02}", minutes / 60, minutes % 60)
+}
+
+fn write_slot(out: &mut Lines, event: &Event, slot: &Slot, tz: Tz, compiled: DateTime<Utc>) {
+    let weeks = slot.day.info.weeks.or(event.info.weeks);
+    let from_date = event
+        .start_date
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .map(|start| start.with_timezone(&tz).date_naive())
+        .into_iter()
+        // An occurrence that started on an earlier day can still be going.
+        .chain([(compiled - Duration::minutes(slot.duration.into()))
+            .with_timezone(&tz)
+            .date_naive()])
+        .max()
+        .unwrap();
+    let in_hiatus = |date: &NaiveDate| {
+        event
+            .hiatus
+            .as_ref()
+            .is_some_and(|hiatus| (hiatus.from..=hiatus.until).contains(date))
+    };
+    // Events with a list of dates have no rule, and each date is listed instead.
+    let listed: Option<Vec<_>> = event.dates.as_ref().map(|dates| {
+        dates
+            .iter()
+            .copied()
+            .filter(|date| from_date <= *date && !in_hiatus(date))
+            .filter(|date| occurs_on(*date, event, slot.weekday, weeks))
+            .collect()
+    });
+    let first = match &listed {
+        Some(listed) => listed.first().copied(),
+        None => {
+            // Look far enough ahead to find one occurrence in any month, or in any interval.
+            let window = interval(event).map_or(62, |(every, _)| usize::from(every.get()) * 7);
+            from_date
+                .iter_days()
+                .take(window)
+                .find(|date| !in_hiatus(date) && occurs_on(*date, event, slot.weekday, weeks))
+        }
+    };
+    let Some(first) = first else {
+        return;
+    };
+
+    let day = byday(slot.weekday);
+    let uid = uid(&event.name);
+
+    out.line("BEGIN:VEVENT");
+    out.line(&format!("UID:{uid}-{}@wc-compiler", day.to_lowercase()));
+    out.line(&format!("DTSTAMP:{}", compiled.format("%Y%m%dT%H%M%SZ")));
+    out.line(&format!(
+        "DTSTART;TZID={}:{}T{:02}{:02}00",
+        event.timezone,
+        first.format("%Y%m%d"),
+        slot.start / 60,
+        slot.start % 60,
+    ));
+    out.line(&format!(
+        "DURATION:PT{}H{}M",
+        slot.duration / 60,
+        slot.duration % 60
+    ));
+    if let Some(listed) = &listed {
+        for date in &listed[1..] {
+            out.line(&format!(
+                "RDATE;TZID={}:{}T{:02}{:02}00",
+                event.timezone,
+                date.format("%Y%m%d"),
+                slot.start / 60,
+                slot.start % 60,
+            ));
+        }
+    } else {
+        // The first occurrence is in an interval week, so the interval counts from there.
+        let mut rule = match (event.monthly, weeks, interval(event)) {
+            // The last of the candidate days that exist in the month, so that short months get
+            // their last day.
+            (Some(monthly @ 29..), _, _) => {
+                let days: Vec<_> = (28..=monthly).map(|d| d.to_string()).collect();
+                format!(
+                    "RRULE:FREQ=MONTHLY;BYMONTHDAY={};BYSETPOS=-1",
+                    days.join(",")
+                )
+            }
+            (Some(monthly), _, _) => format!("RRULE:FREQ=MONTHLY;BYMONTHDAY={monthly}"),
+            (None, Some(weeks), _) => {
+                let days: Vec<_> = weeks.iter().map(|week| format!("{week}{day}")).collect();
+                format!("RRULE:FREQ=MONTHLY;BYDAY={}", days.join(","))
+            }
+            (None, None, Some((every, _))) => {
+                format!("RRULE:FREQ=WEEKLY;INTERVAL={every};BYDAY={day}")
+            }
+            (None, None, None) => format!("RRULE:FREQ=WEEKLY;BYDAY={day}"),
+        };
+        if let Some(end) = event
+            .end_date
+            .and_then(|ts| Utc.timestamp_opt(ts - 1, 0).single())
+        {
+            write!(rule, ";UNTIL={}", end.format("%Y%m%dT%H%M%SZ")).unwrap();
+        }
+        out.line(&rule);
+    }
+    // Monthly events have one slot for every day of the week.
+    let in_slot = |date: &NaiveDate| event.monthly.is_some() || date.weekday() == slot.weekday;
+    let mut excluded = BTreeSet::new();
+    for set in [&event.canceled, &event.skipped] {
+        if let DateSet::Dates(dates) = set {
+            excluded.extend(dates.iter().copied().filter(in_slot));
+        }
+    }
+    // The rule doesn't know about the break, so its occurrences are excluded one by one.
+    if listed.is_none() {
+        if let Some(hiatus) = &event.hiatus {
+            excluded.extend(
+                hiatus
+                    .from
+                    .max(first)
+                    .iter_days()
+                    .take_while(|date| *date <= hiatus.until)
+                    .filter(|date| occurs_on(*date, event, slot.weekday, weeks)),
+            );
+        }
+    }
+    for date in &excluded {
+        out.line(&format!(
+            "EXDATE;TZID={}:{}T{:02}{:02}00",
+            event.timezone,
+            date.format("%Y%m%d"),
+            slot.start / 60,
+            slot.start % 60,
+        ));
+    }
+    write_details(out, event, slot, slot.day.name.unwrap_or(&event.name));
+    out.line("END:VEVENT");
+
+    // Overridden occurrences replace the ones from the rule.
+    for (date, change) in &event.overrides {
+        if !in_slot(date) || *date < first || excluded.contains(date) || in_hiatus(date) {
+            continue;
+        }
+        let start = change.start.unwrap_or(slot.start);
+        let duration = change.duration.unwrap_or(slot.duration);
+        out.line("BEGIN:VEVENT");
+        out.line(&format!("UID:{uid}-{}@wc-compiler", day.to_lowercase()));
+        out.line(&format!("DTSTAMP:{}", compiled.format("%Y%m%dT%H%M%SZ")));
+        out.line(&format!(
+            "RECURRENCE-ID;TZID={}:{}T{:02}{:02}00",
+            event.timezone,
+            date.format("%Y%m%d"),
+            slot.start / 60,
+            slot.start % 60,
+        ));
+        out.line(&format!(
+            "DTSTART;TZID={}:{}T{:02}{:02}00",
+            event.timezone,
+            date.format("%Y%m%d"),
+            start / 60,
+            start % 60,
+        ));
+        out.line(&format!("DURATION:PT{}H{}M", duration / 60, duration % 60));
+        let name = change.name.or(slot.day.name).unwrap_or(&event.name);
+        write_details(out, event, slot, name);
+        out.line("END:VEVENT");
+    }
+}
+
+fn write_details(out: &mut Lines, event: &Event, slot: &Slot, name: &str) {
+    out.line(&format!("SUMMARY:{}", escape(name)));
+    let description: Vec<_> = [
+        slot.day
+            .info
+            .description
+            .as_deref()
+            .or(event.info.description.as_deref()),
+        slot.day.note,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !description.is_empty() {
+        out.line(&format!(
+            "DESCRIPTION:{}",
+            escape(&description.join("\n\n"))
+        ));
+    }
+    if let Some(web) = slot.day.info.web.or(event.info.web) {
+        out.line(&format!("URL:{web}"));
+    }
+}
+
+fn byday(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Escapes a TEXT value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Content lines, folded at 75 octets and ended with CRLF.
+#[derive(Default)]
+struct Lines(String);
+
+impl Lines {
+    fn line(&mut self, line: &str) {
+        let mut length = 0;
+        for c in line.chars() {
+            if length + c.len_utf8() > 75 {
+                self.0.push_str("\r\n ");
+                length = 1;
+            }
+            self.0.push(c);
+            length += c.len_utf8();
+        }
+        self.0.push_str("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::Rule;
+
+    #[test]
+    fn lower_standard_offsets_are_not_daylight_saving_time() {
+        // Venezuela moved its standard time back half an hour at the end of 2007.
+        let zone = Zone {
+            offsets: vec![
+                Rule {
+                    start: None,
+                    offset: Some(-240),
+                },
+                Rule {
+                    start: Some(1_197_183_600),
+                    offset: Some(-270),
+                },
+            ],
+        };
+        let compiled = Utc.with_ymd_and_hms(2007, 6, 1, 0, 0, 0).unwrap();
+        let mut out = Lines::default();
+        write_timezone(
+            &mut out,
+            "America/Caracas",
+            &zone,
+            Tz::America__Caracas,
+            compiled,
+        );
+        assert!(!out.0.contains("DAYLIGHT"), "{}", out.0);
+    }
+}
@@ -0,0 +1,18 @@
+use miette::{IntoDiagnostic, Result};
+
+use super::OutputFormat;
+use crate::output::Data;
+
+pub struct Json;
+
+impl OutputFormat for Json {
+    fn file_name(&self) -> &'static str {
+        "data.json"
+    }
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>> {
+        let mut json = serde_json::to_vec(&data.written()).into_diagnostic()?;
+        json.push(b'\n');
+        Ok(json)
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::Write;
+
+use miette::Result;
+
+use super::{clock, end_clock, slot_day, slots, OutputFormat};
+use crate::output::Data;
+
+pub struct Markdown;
+
+impl OutputFormat for Markdown {
+    fn file_name(&self) -> &'static str {
+        "events.md"
+    }
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>> {
+        let mut out = String::new();
+        writeln!(out, "# {}", escape(data.meta.title)).unwrap();
+        if let Some(description) = data.meta.description {
+            writeln!(out, "\n{}", escape(description)).unwrap();
+        }
+        if let Some(link) = data.meta.link {
+            writeln!(out, "\n<{link}>").unwrap();
+        }
+
+        for event in data.events {
+            writeln!(out, "\n## {}\n", escape(&event.name)).unwrap();
+            if let Some(description) = &event.info.description {
+                writeln!(out, "{}\n", escape(description)).unwrap();
+            }
+            for slot in slots(event) {
+                write!(
+                    out,
+                    "- {} {}–{} ({})",
+                    slot_day(event, &slot),
+                    clock(slot.start),
+                    end_clock(&slot),
+                    event.timezone,
+                )
+                .unwrap();
+                if let Some(note) = slot.day.note {
+                    write!(out, ": {}", escape(note)).unwrap();
+                }
+                out.push('\n');
+            }
+            if let Some(web) = event.info.web {
+                writeln!(out, "\n<{web}>").unwrap();
+            }
+            for (label, link) in &event.info.links.0 {
+                writeln!(out, "\n[{}](<{link}>)", escape(label)).unwrap();
+            }
+        }
+        Ok(out.into_bytes())
+    }
+}
+
+/// Escapes characters that Markdown would treat as formatting.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
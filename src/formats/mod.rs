@@ -0,0 +1,135 @@
+use std::num::NonZeroU8;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use miette::Result;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    input::{in_interval, is_monthly_day},
+    output::{Data, Event, EventDay},
+};
+
+mod atom;
+mod html;
+mod ics;
+mod json;
+mod markdown;
+mod msgpack;
+pub mod preview;
+pub mod share;
+
+/// A file format the compiled calendar can be written in.
+pub trait OutputFormat {
+    /// The name of the file written to the output directory.
+    fn file_name(&self) -> &'static str;
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>>;
+}
+
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd, clap::ValueEnum)]
+pub enum Format {
+    /// data.json, which is loaded by the world.
+    Json,
+    /// calendar.ics, for calendar apps.
+    Ics,
+    /// index.html, a readable schedule.
+    Html,
+    /// events.md, a readable schedule.
+    Markdown,
+    /// data.msgpack, the same data as data.json in MessagePack.
+    Msgpack,
+    /// feed.xml, an Atom feed of the next week's occurrences.
+    Atom,
+}
+
+impl Format {
+    pub fn output_format(self) -> &'static dyn OutputFormat {
+        match self {
+            Format::Json => &json::Json,
+            Format::Ics => &ics::Ics,
+            Format::Html => &html::Html,
+            Format::Markdown => &markdown::Markdown,
+            Format::Msgpack => &msgpack::Msgpack,
+            Format::Atom => &atom::Atom,
+        }
+    }
+}
+
+/// One weekly occurrence of an event, in the event's time zone.
+pub struct Slot<'a> {
+    pub weekday: Weekday,
+    /// Minutes after midnight.
+    pub start: i32,
+    /// Minutes.
+    pub duration: i32,
+    pub day: &'a EventDay<'a>,
+}
+
+pub fn slots<'a>(event: &'a Event<'a>) -> impl Iterator<Item = Slot<'a>> {
+    // Monthly events are listed on every day of the week without overrides, so one slot has
+    // everything.
+    let count = if event.monthly.is_some() { 1 } else { 7 };
+    event.days.iter().take(count).map(|(weekday, day)| Slot {
+        weekday,
+        start: day.start.unwrap_or(event.start),
+        duration: day.duration.unwrap_or(event.duration),
+        day,
+    })
+}
+
+/// Formats minutes as `HH:MM`.
+pub fn clock(minutes: i32) -> String {
+    format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+}
+
+/// Formats the end of a slot as `HH:MM`, followed by how many days later it is if it's after
+/// midnight.
+pub fn end_clock(slot: &Slot) -> String {
+    let end = slot.start + slot.duration;
+    match end / (24 * 60) {
+        0 => clock(end),
+        days => format!("{} (+{days})", clock(end)),
+    }
+}
+
+/// When a slot repeats, for readable formats.
+pub fn slot_day(event: &Event, slot: &Slot) -> String {
+    match event.monthly {
+        Some(day) => format!("Day {day} of every month"),
+        None => weekday_name(slot.weekday).to_owned(),
+    }
+}
+
+pub fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// Whether the event is held on `date` by its schedule, in the slot on `weekday` with `weeks`.
+pub fn occurs_on(date: NaiveDate, event: &Event, weekday: Weekday, weeks: Option<&[u8]>) -> bool {
+    if let Some(day) = event.monthly.and_then(NonZeroU8::new) {
+        return is_monthly_day(date, day);
+    }
+    date.weekday() == weekday
+        && weeks.is_none_or(|weeks| weeks.contains(&((date.day0() / 7 + 1) as u8)))
+        && interval(event).is_none_or(|(every, anchor)| in_interval(date, anchor, every))
+}
+
+pub fn interval(event: &Event) -> Option<(NonZeroU8, NaiveDate)> {
+    NonZeroU8::new(event.every?).zip(event.anchor)
+}
+
+/// An id that stays the same across compiles while `name` does.
+pub fn uid(name: &str) -> String {
+    Sha256::digest(name.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
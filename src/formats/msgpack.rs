@@ -0,0 +1,69 @@
+use miette::{IntoDiagnostic, Result};
+
+use super::OutputFormat;
+use crate::output::Data;
+
+pub struct Msgpack;
+
+impl OutputFormat for Msgpack {
+    fn file_name(&self) -> &'static str {
+        "data.msgpack"
+    }
+
+    fn render(&self, data: &Data) -> Result<Vec<u8>> {
+        // Named fields keep the structure identical to data.json.
+        rmp_serde::to_vec_named(&data.written()).into_diagnostic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::*;
+    use crate::{
+        formats::json::Json,
+        output::{Keys, Meta, Rule, Zone},
+    };
+
+    #[test]
+    fn msgpack_has_the_same_structure_as_json() {
+        let meta = Meta {
+            title: "Events",
+            description: Some("Every event"),
+            link: None,
+            home_timezone: Some("Asia/Tokyo"),
+            compiled_time: 1_700_000_000,
+            languages: BTreeMap::new(),
+            fallbacks: BTreeMap::new(),
+            series: Vec::new(),
+            features: BTreeSet::new(),
+        };
+        let rules = vec![
+            Rule {
+                start: None,
+                offset: Some(-300),
+            },
+            Rule {
+                start: Some(1_710_054_000),
+                offset: Some(-240),
+            },
+        ];
+        let zones = BTreeMap::from([("America/New_York".to_owned(), Zone { offsets: rules })]);
+        for keys in [Keys::Short, Keys::Long] {
+            let data = Data {
+                meta: &meta,
+                events: &[],
+                zones: &zones,
+                redirects: BTreeMap::from([("old", "new")]),
+                keys,
+                compat_platforms: false,
+            };
+            let json: serde_json::Value =
+                serde_json::from_slice(&Json.render(&data).unwrap()).unwrap();
+            let msgpack: serde_json::Value =
+                rmp_serde::from_slice(&Msgpack.render(&data).unwrap()).unwrap();
+            assert_eq!(msgpack, json);
+        }
+    }
+}
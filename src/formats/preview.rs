@@ -0,0 +1,143 @@
+//! A single HTML file showing the compiled calendar, for reviewing changes before they are
+//! published. Everything is inlined, including the posters and the compiled data, so the file
+//! can be shared on its own.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base64::prelude::*;
+use miette::{IntoDiagnostic, Result};
+
+use super::{
+    clock, end_clock,
+    html::{self, escape},
+    slot_day, slots,
+};
+use crate::output::{Data, PosterInfo};
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: auto; }
+section { display: flex; gap: 1em; border-top: 1px solid #ccc; padding: 1em 0; }
+img { max-width: 8em; max-height: 8em; object-fit: contain; }
+.missing { color: #c00; }";
+
+/// Renders the preview. `posters` has the image for each poster slot used by the events.
+pub fn render(data: &Data, posters: &BTreeMap<u8, PathBuf>) -> Result<Vec<u8>> {
+    let images = posters
+        .iter()
+        .map(|(&number, path)| Ok((number, data_url(path)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    let image = |poster: Option<PosterInfo>| match poster.and_then(|p| images.get(&p.number)) {
+        Some(url) => format!("<img src=\"{url}\" alt=\"\">"),
+        None => String::new(),
+    };
+
+    let mut out = String::new();
+    let title = escape(data.meta.title);
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html>").unwrap();
+    writeln!(out, "<head>").unwrap();
+    writeln!(out, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>Preview of {title}</title>").unwrap();
+    writeln!(out, "<style>{STYLE}</style>").unwrap();
+    writeln!(out, "</head>").unwrap();
+    writeln!(out, "<body>").unwrap();
+    writeln!(out, "<h1>{title}</h1>").unwrap();
+    if let Some(description) = data.meta.description {
+        writeln!(out, "<p>{}</p>", escape(description)).unwrap();
+    }
+
+    for event in data.events {
+        match event.info.color {
+            Some(color) => writeln!(
+                out,
+                "<section style=\"border-left: 0.5em solid #{color:06x}; padding-left: 0.5em\">"
+            )
+            .unwrap(),
+            None => writeln!(out, "<section>").unwrap(),
+        }
+        match event.info.poster {
+            Some(_) => {
+                let credit = [event.info.poster_credit, event.info.poster_license]
+                    .into_iter()
+                    .flatten()
+                    .map(escape)
+                    .collect::<Vec<_>>();
+                write!(out, "<div>{}", image(event.info.poster)).unwrap();
+                if !credit.is_empty() {
+                    write!(out, "<br><small>{}</small>", credit.join(", ")).unwrap();
+                }
+                writeln!(out, "</div>").unwrap();
+            }
+            None => writeln!(out, "<div class=\"missing\">No poster</div>").unwrap(),
+        }
+        writeln!(out, "<div>").unwrap();
+        writeln!(out, "<h2>{}</h2>", escape(&event.name)).unwrap();
+        if let Some(description) = html::description(&event.info) {
+            writeln!(out, "<p>{description}</p>").unwrap();
+        }
+        writeln!(out, "<ul>").unwrap();
+        for slot in slots(event) {
+            write!(
+                out,
+                "<li>{} {}–{} ({})",
+                slot_day(event, &slot),
+                clock(slot.start),
+                end_clock(&slot),
+                escape(event.timezone),
+            )
+            .unwrap();
+            if let Some(name) = slot.day.name {
+                write!(out, " {}", escape(name)).unwrap();
+            }
+            if let Some(note) = slot.day.note {
+                write!(out, ": {}", escape(note)).unwrap();
+            }
+            write!(out, "{}", image(slot.day.info.poster)).unwrap();
+            writeln!(out, "</li>").unwrap();
+        }
+        writeln!(out, "</ul>").unwrap();
+        if let Some(web) = event.info.web {
+            let web = escape(web);
+            writeln!(out, "<p><a href=\"{web}\">{web}</a></p>").unwrap();
+        }
+        for (label, link) in &event.info.links.0 {
+            let (label, link) = (escape(label), escape(link));
+            writeln!(out, "<p><a href=\"{link}\">{label}</a></p>").unwrap();
+        }
+        writeln!(out, "</div>").unwrap();
+        writeln!(out, "</section>").unwrap();
+    }
+
+    // The compiled data, for checking details that aren't shown above.
+    let json = serde_json::to_string_pretty(data).into_diagnostic()?;
+    writeln!(out, "<details>").unwrap();
+    writeln!(out, "<summary>data.json</summary>").unwrap();
+    writeln!(out, "<pre>{}</pre>", escape(&json)).unwrap();
+    writeln!(out, "</details>").unwrap();
+
+    writeln!(out, "</body>").unwrap();
+    writeln!(out, "</html>").unwrap();
+    Ok(out.into_bytes())
+}
+
+fn data_url(path: &Path) -> Result<String> {
+    let media_type = match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("webp") => "image/webp",
+        Some("png") => "image/png",
+        _ => "image/jpeg",
+    };
+    let image = fs::read(path).into_diagnostic()?;
+    Ok(format!(
+        "data:{media_type};base64,{}",
+        BASE64_STANDARD.encode(image)
+    ))
+}
@@ -0,0 +1,138 @@
+//! A small page for each event, with OpenGraph and Twitter card tags, so that a link to an event
+//! shows its name, description and poster when it is shared in chat or on social media. The tags
+//! need full addresses, so the pages are only written when meta.toml sets `base_url`.
+
+use std::{collections::BTreeSet, fmt::Write, fs, path::Path};
+
+use miette::{IntoDiagnostic, Result};
+
+use super::html::{self, escape};
+use crate::{
+    output::{self, Meta},
+    Event,
+};
+
+/// The directory in the output that the pages are written to.
+pub const DIRECTORY: &str = "events";
+
+/// Whether `name`, a path in the output directory, is one of the pages.
+pub fn is_page(name: &str) -> bool {
+    name.strip_prefix(DIRECTORY)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Renders the page of each event, named by its path in the output directory. `sources` are the
+/// input events of `events`, in the same order.
+pub fn render(
+    base_url: &str,
+    meta: &Meta,
+    sources: &[&Event],
+    events: &[output::Event],
+) -> Vec<(String, Vec<u8>)> {
+    let base_url = base_url.trim_end_matches('/');
+    sources
+        .iter()
+        .zip(events)
+        .map(|(source, event)| {
+            let path = format!("{DIRECTORY}/{}.html", source.id());
+            let content = page(base_url, &path, meta, event);
+            (path, content.into_bytes())
+        })
+        .collect()
+}
+
+fn page(base_url: &str, path: &str, meta: &Meta, event: &output::Event) -> String {
+    let name = escape(&event.name);
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>").unwrap();
+    writeln!(out, "<html>").unwrap();
+    writeln!(out, "<head>").unwrap();
+    writeln!(out, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>{name} – {}</title>", escape(meta.title)).unwrap();
+    writeln!(out, "<link rel=\"canonical\" href=\"{base_url}/{path}\">").unwrap();
+    writeln!(out, "<meta property=\"og:type\" content=\"website\">").unwrap();
+    writeln!(
+        out,
+        "<meta property=\"og:site_name\" content=\"{}\">",
+        escape(meta.title),
+    )
+    .unwrap();
+    writeln!(out, "<meta property=\"og:title\" content=\"{name}\">").unwrap();
+    writeln!(
+        out,
+        "<meta property=\"og:url\" content=\"{base_url}/{path}\">"
+    )
+    .unwrap();
+    if let Some(description) = event.info.description.as_deref() {
+        let description = escape(description);
+        writeln!(
+            out,
+            "<meta property=\"og:description\" content=\"{description}\">"
+        )
+        .unwrap();
+        writeln!(out, "<meta name=\"description\" content=\"{description}\">").unwrap();
+    }
+    let card = match event.info.poster {
+        Some(poster) => {
+            writeln!(
+                out,
+                "<meta property=\"og:image\" content=\"{base_url}/posters/{:02x}\">",
+                poster.number,
+            )
+            .unwrap();
+            "summary_large_image"
+        }
+        None => "summary",
+    };
+    writeln!(out, "<meta name=\"twitter:card\" content=\"{card}\">").unwrap();
+    writeln!(out, "</head>").unwrap();
+    writeln!(out, "<body>").unwrap();
+    writeln!(out, "<h1>{name}</h1>").unwrap();
+    if let Some(poster) = event.info.poster {
+        writeln!(
+            out,
+            "<p><img src=\"../posters/{:02x}\" alt=\"\"></p>",
+            poster.number,
+        )
+        .unwrap();
+    }
+    if let Some(description) = html::description(&event.info) {
+        writeln!(out, "<p>{description}</p>").unwrap();
+    }
+    if let Some(web) = event.info.web {
+        let web = escape(web);
+        writeln!(out, "<p><a href=\"{web}\">{web}</a></p>").unwrap();
+    }
+    if let Some(link) = meta.link {
+        writeln!(
+            out,
+            "<p><a href=\"{}\">{}</a></p>",
+            escape(link),
+            escape(meta.title),
+        )
+        .unwrap();
+    }
+    writeln!(out, "</body>").unwrap();
+    writeln!(out, "</html>").unwrap();
+    out
+}
+
+/// Deletes the pages of events that were removed or renamed since the previous compile, so their
+/// links stop showing them.
+pub fn remove_stale(output: &Path, written: &BTreeSet<&str>) -> Result<()> {
+    let directory = output.join(DIRECTORY);
+    let Ok(entries) = fs::read_dir(&directory) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let entry = entry.into_diagnostic()?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str().filter(|name| name.ends_with(".html")) else {
+            continue;
+        };
+        if !written.contains(format!("{DIRECTORY}/{name}").as_str()) {
+            fs::remove_file(entry.path()).into_diagnostic()?;
+        }
+    }
+    Ok(())
+}
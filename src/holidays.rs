@@ -0,0 +1,136 @@
+//! Public holidays, for events that aren't held on them.
+//!
+//! The holidays are either a built-in set, which is calculated for the years around the compile,
+//! or an ICS file with one event for each holiday. Recurrence rules in ICS files are not expanded,
+//! so the file has to list every year's holidays, like the calendars published by governments do.
+
+use std::{collections::BTreeSet, fs, ops::RangeInclusive, path::Path};
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+/// The codes of the built-in sets.
+pub const BUILT_IN: &[&str] = &["JP"];
+
+/// Loads the holidays of a built-in set, or of an ICS file relative to `directory`.
+pub fn load(
+    source: &str,
+    directory: &Path,
+    years: RangeInclusive<i32>,
+) -> Result<BTreeSet<NaiveDate>, String> {
+    match source {
+        "JP" => Ok(years.flat_map(japan).collect()),
+        _ if source.ends_with(".ics") => {
+            let path = directory.join(source);
+            let content = fs::read_to_string(&path)
+                .map_err(|error| format!("{} can't be read: {error}", path.display()))?;
+            parse_ics(&content)
+        }
+        _ => Err(format!(
+            "{source:?} is not a built-in set, which are {}, or an ICS file",
+            BUILT_IN.join(", "),
+        )),
+    }
+}
+
+/// The national holidays of Japan in `year`, under the rules in effect since 2020.
+fn japan(year: i32) -> BTreeSet<NaiveDate> {
+    let date = |month, day| NaiveDate::from_ymd_opt(year, month, day);
+    let monday = |month, n| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n);
+    // The equinoxes by the approximation used for the official announcements, which holds until
+    // 2099.
+    let offset = 0.242194 * f64::from(year - 1980) - f64::from((year - 1980) / 4);
+    let vernal = (20.8431 + offset).floor() as u32;
+    let autumnal = (23.2488 + offset).floor() as u32;
+
+    let national: BTreeSet<_> = [
+        date(1, 1),
+        monday(1, 2),
+        date(2, 11),
+        date(2, 23),
+        date(3, vernal),
+        date(4, 29),
+        date(5, 3),
+        date(5, 4),
+        date(5, 5),
+        monday(7, 3),
+        date(8, 11),
+        monday(9, 3),
+        date(9, autumnal),
+        monday(10, 2),
+        date(11, 3),
+        date(11, 23),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    // A day between two holidays is a holiday too.
+    let mut holidays = national.clone();
+    for &holiday in &national {
+        let day = holiday + Days::new(1);
+        if national.contains(&(day + Days::new(1))) {
+            holidays.insert(day);
+        }
+    }
+
+    // A holiday on a Sunday moves the day off to the next day that isn't a holiday.
+    for &sunday in national.iter().filter(|day| day.weekday() == Weekday::Sun) {
+        let mut day = sunday + Days::new(1);
+        while holidays.contains(&day) {
+            day = day + Days::new(1);
+        }
+        holidays.insert(day);
+    }
+    holidays
+}
+
+/// Reads the dates of the events in an ICS file. An event that spans several days adds each of
+/// them.
+fn parse_ics(content: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    // Long lines are folded by starting their continuations with a space or a tab.
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    let mut holidays = BTreeSet::new();
+    let mut start = None;
+    let mut end = None;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let property = name.split(';').next().unwrap_or_default();
+        match property.to_ascii_uppercase().as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                start = None;
+                end = None;
+            }
+            "DTSTART" => start = Some(parse_ics_date(value)?),
+            "DTEND" => end = Some(parse_ics_date(value)?),
+            "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                let Some(start) = start else {
+                    return Err("an event in the ICS file has no DTSTART".to_owned());
+                };
+                // The end of a whole day event is the day after it.
+                let end = end
+                    .filter(|&end| start < end)
+                    .unwrap_or(start + Days::new(1));
+                holidays.extend(start.iter_days().take_while(|&day| day < end));
+            }
+            _ => {}
+        }
+    }
+    Ok(holidays)
+}
+
+/// Reads the date of a DTSTART or DTEND, which may also have a time.
+fn parse_ics_date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| format!("{value:?} in the ICS file is not a date"))
+}
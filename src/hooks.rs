@@ -0,0 +1,110 @@
+//! External commands run at stages of the compile, for checks and publishing that only one
+//! deployment needs.
+//!
+//! Each command gets a JSON object on stdin describing the stage. A command that fails stops the
+//! compile. The commands come from meta.toml, so anyone who can change the input could run
+//! anything with them, and they only run with `--run-hooks`.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use miette::{Context, IntoDiagnostic, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::HookFailed, output::Data};
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    pub stage: Stage,
+    /// The program and its arguments. It is run directly, without a shell.
+    pub command: Vec<String>,
+}
+
+#[derive(Clone, Copy, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// After the input files are parsed, with the paths of the input directories and event files.
+    AfterParse,
+    /// After the output is rendered, with the compiled data, before data.json and the other
+    /// files are written. This also runs with --dry-run and --verify when hooks are run.
+    BeforeWrite,
+    /// After the output is written, with the paths of the written files and posters.
+    AfterWrite,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::AfterParse => "after_parse",
+            Stage::BeforeWrite => "before_write",
+            Stage::AfterWrite => "after_write",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AfterParse<'a> {
+    pub inputs: &'a [PathBuf],
+    pub events: Vec<&'a Path>,
+}
+
+#[derive(Serialize)]
+pub struct BeforeWrite<'a> {
+    pub output: &'a Path,
+    pub data: &'a Data<'a>,
+}
+
+#[derive(Serialize)]
+pub struct AfterWrite<'a> {
+    pub output: &'a Path,
+    pub files: Vec<PathBuf>,
+    pub posters: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct HookInput<'a, T> {
+    stage: Stage,
+    #[serde(flatten)]
+    details: &'a T,
+}
+
+/// Runs the hooks for `stage` in order, giving each of them `details` on stdin.
+pub fn run(hooks: &[Hook], stage: Stage, details: &impl Serialize) -> Result<()> {
+    let mut hooks = hooks.iter().filter(|hook| hook.stage == stage).peekable();
+    if hooks.peek().is_none() {
+        return Ok(());
+    }
+    let input = serde_json::to_vec(&HookInput { stage, details }).into_diagnostic()?;
+    for hook in hooks {
+        let command = hook.command.join(" ");
+        let wrap = || format!("Running the {} hook {command:?} failed.", stage.name());
+        let Some((program, args)) = hook.command.split_first() else {
+            continue;
+        };
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+            .into_diagnostic()
+            .wrap_err_with(wrap)?;
+        let mut stdin = child.stdin.take().unwrap();
+        // A hook that doesn't read its input closes the pipe early, which is fine.
+        let _ = stdin.write_all(&input);
+        drop(stdin);
+        let status = child.wait().into_diagnostic().wrap_err_with(wrap)?;
+        if !status.success() {
+            return Err(HookFailed {
+                stage: stage.name(),
+                command,
+                status,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
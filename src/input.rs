@@ -1,42 +1,226 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    num::{NonZeroU16, NonZeroU8},
+};
 
-use chrono::{Duration, NaiveDate, NaiveTime};
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveTime, Weekday};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{
-    de::{Error, Visitor},
-    Deserialize, Deserializer,
+    de::{value::MapAccessDeserializer, Error, Visitor},
+    ser::{SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 use smallvec::{smallvec, SmallVec};
 use toml::Spanned;
 
-use crate::{Language, Platform, User, World};
+use crate::{hooks::Hook, InstanceType, JoinVia, Language, Organizer, Platform, User, World};
+
+/// `_defaults.toml` or the `[defaults]` of meta.toml, with fields for the events in its directory
+/// and its subdirectories that don't set them.
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Defaults<'a> {
+    #[serde(borrow)]
+    #[schemars(with = "Option<String>")]
+    pub timezone: Option<Spanned<Cow<'a, str>>>,
+    pub platforms: Option<SmallVec<[Platform; 2]>>,
+    pub duration: Option<Time<Duration>>,
+    /// Only for events held on weekdays, without an interval, a monthly day, or dates.
+    pub weeks: Option<SmallVec<[u8; 5]>>,
+    #[serde(borrow, default)]
+    pub join: Vec<User<'a>>,
+    #[serde(borrow)]
+    pub world: Option<World<'a>>,
+}
 
+/// The part of meta.toml that `parse_events` reads, without the rest of the meta file.
 #[derive(Deserialize)]
+pub struct MetaDefaults<'a> {
+    #[serde(borrow)]
+    pub defaults: Option<Defaults<'a>>,
+}
+
+// The field order is the canonical order used when formatting event files.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[schemars(description = "An event file, or an `[[event]]` table in meta.toml.")]
 #[serde(deny_unknown_fields)]
 pub struct Event<'a> {
-    #[serde(borrow, flatten)]
-    pub info: EventInfo<'a>,
+    /// The event file this one inherits every field it doesn't set from, relative to its
+    /// directory. `parse_events` fills in the fields and clears this.
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        with = "Option<String>",
+        description = "The event file this one inherits every field it doesn't set from, relative to its directory."
+    )]
+    pub extends: Option<Spanned<Cow<'a, str>>>,
+    /// Only events in meta.toml have an id, which `combined::find` reads. Other events are
+    /// identified by their file name.
+    #[serde(borrow, default, skip_serializing)]
+    #[schemars(
+        with = "Option<String>",
+        description = "The id of an event in meta.toml, which is the file name it would have on its own."
+    )]
+    pub id: Option<Spanned<Cow<'a, str>>>,
+    /// Required, but may come from a defaults file. `parse_events` reports events without one,
+    /// so this is always set after that.
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        with = "Option<String>",
+        description = "The IANA time zone name, like `America/New_York`. Required, but may come from a defaults file."
+    )]
+    pub timezone: Option<Spanned<Cow<'a, str>>>,
+    /// Required, but may come from the event this one extends. `parse_events` reports events
+    /// without one, so this is always set after that.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Required, but may come from the event this one extends.")]
+    pub start: Option<Time<NaiveTime>>,
+    /// Either this or `end` is required. `parse_events` turns an `end` into a duration, so
+    /// this is always set after that.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Either this or `end` is required.")]
+    pub duration: Option<Time<Duration>>,
+    /// The time the event ends, instead of `duration`. An end at or before the start is on the
+    /// next day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<Time<NaiveTime>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end_date: Option<NaiveDate>,
-    #[serde(borrow)]
-    pub timezone: Spanned<Cow<'a, str>>,
-    pub start: Time<NaiveTime>,
-    pub duration: Time<Duration>,
-    #[serde(default = "default_platforms")]
+    /// The event is held every this many weeks, counting from the week of `anchor`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<NonZeroU8>")]
+    pub every: Option<Spanned<NonZeroU8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<NaiveDate>")]
+    pub anchor: Option<Spanned<NaiveDate>>,
+    /// The event is held on this day of every month, or on the last day of shorter months.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<NonZeroU8>")]
+    pub monthly: Option<Spanned<NonZeroU8>>,
+    /// The only dates the event is held, for events that don't repeat every week.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Vec<NaiveDate>>")]
+    pub dates: Option<Vec<Spanned<NaiveDate>>>,
+    /// Empty if not given, which `parse_events` replaces with the defaults.
+    #[serde(default, skip_serializing_if = "is_default_platforms")]
+    #[schemars(
+        description = "The platforms the event is on. Defaults to the platforms in the defaults, or PC."
+    )]
     pub platforms: SmallVec<[Platform; 2]>,
-    #[serde(borrow, default = "default_days")]
-    pub days: EventDays<'a>,
-    #[serde(borrow, default)]
-    pub languages: HashMap<Language, EventLanguage<'a>>,
-    #[serde(default = "DateSet::all")]
+    #[serde(borrow, flatten)]
+    pub info: EventInfo<'a>,
+    /// Whether the event asks for one of the world's pickup slots, which `max_featured` in the
+    /// meta file limits.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub featured: bool,
+    /// The id of a series of events that are grouped together, like the afterparties of a
+    /// festival.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub series: Option<Spanned<Cow<'a, str>>>,
+    /// The ids of related events.
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(with = "Vec<String>")]
+    pub related: Vec<Spanned<Cow<'a, str>>>,
+    #[serde(default = "DateSet::all", skip_serializing_if = "DateSet::is_all")]
     pub confirmed: DateSet,
-    #[serde(default = "DateSet::none")]
+    /// Dates the organizer isn't sure about yet.
+    #[serde(default = "DateSet::none", skip_serializing_if = "DateSet::is_none")]
+    pub tentative: DateSet,
+    #[serde(default = "DateSet::none", skip_serializing_if = "DateSet::is_none")]
     pub canceled: DateSet,
+    /// Whether the event is canceled on the holidays set in the meta file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancel_on_holidays: bool,
+    /// Dates the event is not held, without showing them as canceled.
+    #[serde(default = "DateSet::none", skip_serializing_if = "DateSet::is_none")]
+    pub skipped: DateSet,
+    /// A break of several weeks or months, when the event is not held.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Hiatus<'static>>")]
+    pub hiatus: Option<Spanned<Hiatus<'a>>>,
+    /// Why the event is canceled on some of the canceled dates.
+    #[serde(borrow, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub canceled_reasons: BTreeMap<NaiveDate, Cow<'a, str>>,
+    /// Changes to single occurrences, like a special episode with another name or time.
+    #[serde(borrow, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<NaiveDate, Override<'a>>,
+    /// Short notes about single occurrences, like a special guest.
+    #[serde(borrow, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<NaiveDate, Cow<'a, str>>,
+    /// The days the event is held, as a list instead of `days`. These are moved into `days` when
+    /// the event is read, but formatting keeps them.
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<Session<'a>>,
+    #[serde(
+        borrow,
+        default = "default_days",
+        skip_serializing_if = "EventDays::is_daily"
+    )]
+    pub days: EventDays<'a>,
+    #[serde(
+        borrow,
+        default,
+        alias = "lang",
+        serialize_with = "serialize_sorted",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    // schemars writes defaults through `serialize_with`, which it can't do for a borrowed field.
+    #[schemars(skip_serializing)]
+    pub languages: HashMap<Language, EventLanguage<'a>>,
+    /// Fields that are being tried out, which are only compiled with `--unstable`. They are
+    /// copied to the output as they are, and may change or be removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<serde_json::Map<String, serde_json::Value>>")]
+    pub unstable: Option<Spanned<toml::Table>>,
+}
+
+impl<'a> Event<'a> {
+    pub fn timezone(&self) -> &Spanned<Cow<'a, str>> {
+        self.timezone
+            .as_ref()
+            .expect("events without a time zone are not parsed")
+    }
+
+    pub fn start(&self) -> Time<NaiveTime> {
+        self.start.expect("events without a start are not parsed")
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+            .map_or_else(Duration::zero, |duration| duration.0)
+    }
 }
 
-fn default_platforms() -> SmallVec<[Platform; 2]> {
+/// The time from `start` until `end`, which is on the next day if it isn't after `start`.
+pub fn duration_until(start: NaiveTime, end: NaiveTime) -> Duration {
+    let duration = end - start;
+    if duration <= Duration::zero() {
+        duration + Duration::days(1)
+    } else {
+        duration
+    }
+}
+
+pub fn default_platforms() -> SmallVec<[Platform; 2]> {
     smallvec![Platform::Pc]
 }
 
+fn is_default_platforms(platforms: &SmallVec<[Platform; 2]>) -> bool {
+    platforms.is_empty() || platforms[..] == [Platform::Pc]
+}
+
+fn serialize_sorted<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
 fn default_days() -> EventDays<'static> {
     EventDays {
         monday: Some(EventDay::default()),
@@ -49,65 +233,444 @@ fn default_days() -> EventDays<'static> {
     }
 }
 
-#[derive(Default, Deserialize)]
+#[derive(Default, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct EventInfo<'a> {
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub name: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub description: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub web: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub poster: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    /// Who made the poster, for calendars that credit the artist.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub poster_credit: Option<Cow<'a, str>>,
+    /// The license the poster is shared under, like "CC BY 4.0".
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub poster_license: Option<Cow<'a, str>>,
+    /// The accent color the world uses to highlight the event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub hashtag: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub twitter: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    /// A Bluesky handle, like "myevent.bsky.social".
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub bluesky: Option<Cow<'a, str>>,
+    /// A Misskey handle with its server, like "@myevent@misskey.io".
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub misskey: Option<Cow<'a, str>>,
+    /// A YouTube handle like "@myevent", or a channel id.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub youtube: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub twitch: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub group: Option<Cow<'a, str>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub discord: Option<Cow<'a, str>>,
-    #[serde(borrow, default)]
-    pub join: Vec<User<'a>>,
-    #[serde(borrow)]
-    pub world: Option<World<'a>>,
+    /// Other pages of the event, like stores and fan clubs, by their labels.
+    #[serde(default, skip_serializing_if = "Links::is_empty")]
+    pub links: Links<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub weeks: Option<SmallVec<[u8; 5]>>,
+    /// One world, or at the top of the file, a list of worlds the event rotates between.
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub world: Option<Worlds<'a>>,
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub join: Vec<User<'a>>,
+    /// The people who run the event and their roles, for crediting them.
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub organizers: Vec<Organizer<'a>>,
+    /// How many people the instance holds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<NonZeroU16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_type: Option<InstanceType>,
+    /// How people get into the event, which needs the field for that way to be set too.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join_via: Option<JoinVia>,
+    /// How long before the start the instance opens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_before: Option<Time<Duration>>,
+    /// Whether the instance is age-gated to adults. A day or language can set this to `false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adult: Option<bool>,
 }
 
-#[derive(Deserialize)]
+impl<'a> EventInfo<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.description.is_none()
+            && self.tags.is_empty()
+            && self.web.is_none()
+            && self.poster.is_none()
+            && self.poster_credit.is_none()
+            && self.poster_license.is_none()
+            && self.color.is_none()
+            && self.hashtag.is_none()
+            && self.twitter.is_none()
+            && self.bluesky.is_none()
+            && self.misskey.is_none()
+            && self.youtube.is_none()
+            && self.twitch.is_none()
+            && self.group.is_none()
+            && self.discord.is_none()
+            && self.links.is_empty()
+            && self.weeks.is_none()
+            && self.world.is_none()
+            && self.join.is_empty()
+            && self.organizers.is_empty()
+            && self.capacity.is_none()
+            && self.instance_type.is_none()
+            && self.join_via.is_none()
+            && self.open_before.is_none()
+            && self.adult.is_none()
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct EventDays<'a> {
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub monday: Option<EventDay<'a>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub tuesday: Option<EventDay<'a>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub wednesday: Option<EventDay<'a>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub thursday: Option<EventDay<'a>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub friday: Option<EventDay<'a>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub saturday: Option<EventDay<'a>>,
-    #[serde(borrow)]
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
     pub sunday: Option<EventDay<'a>>,
 }
 
-#[derive(Default, Deserialize)]
+/// Whether `date` is in a week when an event held every `every` weeks is held. Weeks start on
+/// Monday, so any day in the same week as `anchor` counts.
+pub fn in_interval(date: NaiveDate, anchor: NaiveDate, every: NonZeroU8) -> bool {
+    let monday = |d: NaiveDate| d - Duration::days(d.weekday().num_days_from_monday().into());
+    (monday(date) - monday(anchor))
+        .num_weeks()
+        .rem_euclid(every.get().into())
+        == 0
+}
+
+/// Whether `date` is the `day`th of its month, or the last day of a month with fewer days.
+pub fn is_monthly_day(date: NaiveDate, day: NonZeroU8) -> bool {
+    let last = date
+        .with_day(1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .and_then(|next| next.pred_opt())
+        .map_or(31, |last| last.day());
+    date.day() == u32::from(day.get()).min(last)
+}
+
+pub fn day_key(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+impl<'a> EventDays<'a> {
+    /// Whether every day is enabled without any overrides, which is the default.
+    pub fn is_daily(&self) -> bool {
+        self.iter().count() == 7 && self.iter().all(|(_, day)| day.is_empty())
+    }
+
+    pub fn get_mut(&mut self, weekday: Weekday) -> &mut Option<EventDay<'a>> {
+        match weekday {
+            Weekday::Mon => &mut self.monday,
+            Weekday::Tue => &mut self.tuesday,
+            Weekday::Wed => &mut self.wednesday,
+            Weekday::Thu => &mut self.thursday,
+            Weekday::Fri => &mut self.friday,
+            Weekday::Sat => &mut self.saturday,
+            Weekday::Sun => &mut self.sunday,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Weekday, &EventDay<'a>)> {
+        [
+            (Weekday::Mon, &self.monday),
+            (Weekday::Tue, &self.tuesday),
+            (Weekday::Wed, &self.wednesday),
+            (Weekday::Thu, &self.thursday),
+            (Weekday::Fri, &self.friday),
+            (Weekday::Sat, &self.saturday),
+            (Weekday::Sun, &self.sunday),
+        ]
+        .into_iter()
+        .filter_map(|(weekday, day)| day.as_ref().map(|day| (weekday, day)))
+    }
+}
+
+/// A day of the event in `[[sessions]]`. This has the same fields as a day section, which are
+/// repeated here because `note` can't be read through a flattened `EventDay`.
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Session<'a> {
+    #[schemars(with = "SessionDay")]
+    pub day: Spanned<SessionDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<Time<NaiveTime>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Time<Duration>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<Time<NaiveTime>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub note: Option<Note<'a>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub platforms: BTreeMap<Platform, PlatformOverride>,
+    #[serde(borrow, flatten)]
+    pub info: EventInfo<'a>,
+}
+
+impl<'a> Session<'a> {
+    pub fn into_day(self) -> EventDay<'a> {
+        EventDay {
+            start: self.start,
+            duration: self.duration,
+            end: self.end,
+            note: self.note,
+            platforms: self.platforms,
+            info: self.info,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionDay {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl SessionDay {
+    pub fn weekday(self) -> Weekday {
+        match self {
+            SessionDay::Monday => Weekday::Mon,
+            SessionDay::Tuesday => Weekday::Tue,
+            SessionDay::Wednesday => Weekday::Wed,
+            SessionDay::Thursday => Weekday::Thu,
+            SessionDay::Friday => Weekday::Fri,
+            SessionDay::Saturday => Weekday::Sat,
+            SessionDay::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+#[derive(Default, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct EventDay<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<Time<NaiveTime>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Time<Duration>>,
+    /// The time the day ends, instead of `duration`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<Time<NaiveTime>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub note: Option<Note<'a>>,
+    /// Different times for some platforms, like a Quest session after the PC session.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub platforms: BTreeMap<Platform, PlatformOverride>,
     #[serde(borrow, flatten)]
     pub info: EventInfo<'a>,
+}
+
+impl<'a> EventDay<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.start.is_none()
+            && self.duration.is_none()
+            && self.end.is_none()
+            && self.note.is_none()
+            && self.platforms.is_empty()
+            && self.info.is_empty()
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlatformOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start: Option<Time<NaiveTime>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<Time<Duration>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Override<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<Time<NaiveTime>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Time<Duration>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub world: Option<World<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub poster: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub poster_credit: Option<Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub poster_license: Option<Cow<'a, str>>,
+}
+
+#[derive(Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hiatus<'a> {
+    pub from: NaiveDate,
+    /// The last day of the break.
+    pub until: NaiveDate,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<Cow<'a, str>>,
+}
+
+impl Hiatus<'_> {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        (self.from..=self.until).contains(&date)
+    }
+}
+
+pub struct Note<'a> {
+    pub text: Cow<'a, str>,
+    /// The last day the note is shown.
+    pub expires: Option<Spanned<NaiveDate>>,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Note<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct NoteTable<'a> {
+            #[serde(borrow)]
+            text: Cow<'a, str>,
+            expires: Option<Spanned<NaiveDate>>,
+        }
+
+        struct NoteVisitor;
+        impl<'de> Visitor<'de> for NoteVisitor {
+            type Value = Note<'de>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "a string or a table with text and expires")
+            }
+
+            fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(Note {
+                    text: Cow::Borrowed(v),
+                    expires: None,
+                })
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(Note {
+                    text: Cow::Owned(v.to_owned()),
+                    expires: None,
+                })
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let table = NoteTable::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Note {
+                    text: table.text,
+                    expires: table.expires,
+                })
+            }
+        }
+
+        let note = deserializer.deserialize_any(NoteVisitor)?;
+        if note.text.trim().is_empty() {
+            return Err(D::Error::custom("Notes must not be empty"));
+        }
+        Ok(note)
+    }
+}
+
+impl Serialize for Note<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.expires {
+            None => serializer.serialize_str(&self.text),
+            Some(expires) => {
+                let mut table = serializer.serialize_struct("Note", 2)?;
+                table.serialize_field("text", &self.text)?;
+                table.serialize_field("expires", expires.as_ref())?;
+                table.end()
+            }
+        }
+    }
+}
+
+impl JsonSchema for Note<'_> {
+    fn schema_name() -> Cow<'static, str> {
+        "Note".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A note shown with the day, or a table with the note and the last day it is shown.",
+            "anyOf": [
+                { "type": "string", "minLength": 1 },
+                {
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string", "minLength": 1 },
+                        "expires": { "type": "string", "format": "date" },
+                    },
+                    "required": ["text"],
+                    "additionalProperties": false,
+                },
+            ],
+        })
+    }
+}
+
+#[derive(Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct EventLanguage<'a> {
     #[serde(borrow, flatten)]
     pub info: EventInfo<'a>,
+    #[serde(borrow, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub canceled_reasons: BTreeMap<NaiveDate, Cow<'a, str>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub hiatus_reason: Option<Cow<'a, str>>,
+    #[serde(borrow, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<NaiveDate, Cow<'a, str>>,
     #[serde(borrow, flatten)]
     pub days: EventDays<'a>,
 }
@@ -130,6 +693,25 @@ impl<'de> Deserialize<'de> for Time<NaiveTime> {
     }
 }
 
+impl Serialize for Time<NaiveTime> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&self.0.format("%H:%M"))
+    }
+}
+
+impl Serialize for Time<Duration> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let minutes = self.0.num_minutes();
+        serializer.collect_str(&format_args!("{}:{:02}", minutes / 60, minutes % 60))
+    }
+}
+
 impl<'de> Deserialize<'de> for Time<Duration> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -140,21 +722,13 @@ impl<'de> Deserialize<'de> for Time<Duration> {
         enum RawTime<'a> {
             #[serde(borrow)]
             String(Cow<'a, str>),
-            Minutes(u16),
+            Minutes(u32),
             Time(toml::value::Datetime),
         }
 
         let raw = RawTime::deserialize(deserializer)?;
         let minutes = match raw {
-            RawTime::String(v) => {
-                if let Some((hours, minutes)) = v.split_once(':') {
-                    let hours: u16 = hours.parse().map_err(D::Error::custom)?;
-                    let minutes: u16 = minutes.parse().map_err(D::Error::custom)?;
-                    hours * 60 + minutes
-                } else {
-                    v.parse().map_err(D::Error::custom)?
-                }
-            }
+            RawTime::String(v) => parse_minutes(&v).map_err(D::Error::custom)?,
             RawTime::Minutes(minutes) => minutes,
             RawTime::Time(time) => {
                 if time.date.is_some() {
@@ -169,13 +743,78 @@ impl<'de> Deserialize<'de> for Time<Duration> {
                 if time.second != 0 || time.nanosecond != 0 {
                     return Err(D::Error::custom("Time must contain whole minutes"));
                 }
-                time.hour as u16 * 60 + time.minute as u16
+                time.hour as u32 * 60 + time.minute as u32
             }
         };
         Ok(Time(Duration::minutes(minutes as i64)))
     }
 }
 
+impl JsonSchema for Time<NaiveTime> {
+    fn schema_name() -> Cow<'static, str> {
+        "Time".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A time of day like `22:00`, before 24:00.",
+            "anyOf": [time_string(), { "type": "integer", "minimum": 0, "maximum": 1439 }],
+        })
+    }
+}
+
+impl JsonSchema for Time<Duration> {
+    fn schema_name() -> Cow<'static, str> {
+        "Duration".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A length of time like `1:30`, `90`, `1h30m`, `90m` or `2h`.",
+            "anyOf": [time_string(), { "type": "integer", "minimum": 0 }],
+        })
+    }
+}
+
+/// The strings `parse_minutes` accepts, and local times, which editors show as strings.
+fn time_string() -> serde_json::Value {
+    serde_json::json!({
+        "type": "string",
+        "pattern": "^([0-9]+:[0-9]+(:00)?|[0-9]+|[0-9]+h([0-9]+m)?|[0-9]+m)$",
+    })
+}
+
+/// Parses `1:30`, `90`, `1h30m`, `90m` or `2h` as minutes.
+fn parse_minutes(v: &str) -> Result<u32, String> {
+    const EXPECTED: &str = "Expected a time like 1:30, 90, 1h30m, 90m or 2h";
+    let number = |digits: &str| {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!("{EXPECTED}, found {v:?}"));
+        }
+        digits.parse::<u32>().map_err(|e| e.to_string())
+    };
+    let (hours, minutes) = if let Some((hours, minutes)) = v.split_once(':') {
+        (number(hours)?, number(minutes)?)
+    } else if v.ends_with(['h', 'm']) {
+        let (hours, minutes) = match v.split_once('h') {
+            Some((hours, minutes)) => (number(hours)?, minutes),
+            None => (0, v),
+        };
+        let minutes = match minutes {
+            "" => 0,
+            minutes => number(minutes.strip_suffix('m').unwrap_or(minutes))?,
+        };
+        (hours, minutes)
+    } else {
+        (0, number(v)?)
+    };
+    // Durations may be 24 hours or longer, so the hours are not limited.
+    hours
+        .checked_mul(60)
+        .and_then(|hours| hours.checked_add(minutes))
+        .ok_or_else(|| "Time is too long".to_owned())
+}
+
 #[derive(Clone)]
 pub enum DateSet {
     All(bool),
@@ -190,6 +829,115 @@ impl DateSet {
     pub fn none() -> Self {
         DateSet::All(false)
     }
+
+    pub fn is_all(&self) -> bool {
+        matches!(self, DateSet::All(true))
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, DateSet::All(false))
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        match self {
+            DateSet::All(all) => *all,
+            DateSet::Dates(dates) => dates.iter().any(|d| *d.as_ref() == date),
+        }
+    }
+}
+
+impl Serialize for DateSet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            DateSet::All(all) => serializer.serialize_bool(*all),
+            DateSet::Dates(dates) => serializer.collect_seq(dates.iter().map(|d| d.as_ref())),
+        }
+    }
+}
+
+impl JsonSchema for DateSet {
+    fn schema_name() -> Cow<'static, str> {
+        "DateSet".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Every date, no date, or a list of dates.",
+            "anyOf": [
+                { "type": "boolean" },
+                { "type": "array", "items": { "type": "string", "format": "date" } },
+            ],
+        })
+    }
+}
+
+/// Labeled links, in the order they are written.
+#[derive(Clone, Default)]
+pub struct Links<'a>(pub Vec<(Cow<'a, str>, Cow<'a, str>)>);
+
+impl Links<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for Links<'_> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct LinksVisitor;
+        impl<'de> Visitor<'de> for LinksVisitor {
+            type Value = Links<'static>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "a table of links by their labels")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut links = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some((label, link)) = map.next_entry::<String, String>()? {
+                    links.push((label.into(), link.into()));
+                }
+                Ok(Links(links))
+            }
+        }
+
+        deserializer.deserialize_map(LinksVisitor)
+    }
+}
+
+impl JsonSchema for Links<'_> {
+    fn schema_name() -> Cow<'static, str> {
+        "Links".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "description": "Labeled links, in the order they are written.",
+            "additionalProperties": { "type": "string" },
+        })
+    }
+}
+
+impl Serialize for Links<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (label, link) in &self.0 {
+            map.serialize_entry(label, link)?;
+        }
+        map.end()
+    }
 }
 
 // Manually implement deserialize to avoid toml-rs/toml#535.
@@ -229,7 +977,230 @@ impl<'de> Deserialize<'de> for DateSet {
     }
 }
 
-#[derive(Deserialize)]
+/// A color written as `#rrggbb`, stored as `0xrrggbb`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Color(pub u32);
+
+impl Color {
+    /// The relative luminance of the color, as defined by WCAG.
+    pub fn luminance(self) -> f64 {
+        let linear = |shift: u32| {
+            let c = f64::from((self.0 >> shift) & 0xff) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(16) + 0.7152 * linear(8) + 0.0722 * linear(0)
+    }
+
+    /// The WCAG contrast ratio between the two colors.
+    pub fn contrast(self, other: Color) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = Cow::<str>::deserialize(deserializer)?;
+        match v.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                Ok(Color(u32::from_str_radix(hex, 16).unwrap()))
+            }
+            _ => Err(D::Error::custom("Expected a color like #ff66cc")),
+        }
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{:06x}", self.0)
+    }
+}
+
+impl JsonSchema for Color {
+    fn schema_name() -> Cow<'static, str> {
+        "Color".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A color like `#ff66cc`.",
+            "pattern": "^#[0-9A-Fa-f]{6}$",
+        })
+    }
+}
+
+/// The world of an event, which may change with the day of the week and the week of the month.
+#[derive(Clone)]
+pub enum Worlds<'a> {
+    One(World<'a>),
+    /// The first entry that matches a day is used.
+    Rotation(Vec<RotatingWorld<'a>>),
+}
+
+#[derive(Clone, Serialize)]
+pub struct RotatingWorld<'a> {
+    #[serde(flatten)]
+    pub world: World<'a>,
+    /// The days of the week the world is used on, or every day if this is empty.
+    #[serde(skip_serializing_if = "SmallVec::is_empty")]
+    pub days: SmallVec<[SessionDay; 7]>,
+    /// The weeks of the month the world is used on, or every week if this is empty.
+    #[serde(skip_serializing_if = "SmallVec::is_empty")]
+    pub weeks: SmallVec<[u8; 5]>,
+}
+
+impl<'a> Worlds<'a> {
+    /// The world, if it doesn't rotate.
+    pub fn single(&self) -> Option<&World<'a>> {
+        match self {
+            Worlds::One(world) => Some(world),
+            Worlds::Rotation(_) => None,
+        }
+    }
+
+    /// The world used on `weekday` in week `week` of the month.
+    pub fn on(&self, weekday: Weekday, week: u8) -> Option<&World<'a>> {
+        match self {
+            Worlds::One(world) => Some(world),
+            Worlds::Rotation(worlds) => worlds
+                .iter()
+                .find(|entry| {
+                    (entry.days.is_empty() || entry.days.iter().any(|d| d.weekday() == weekday))
+                        && (entry.weeks.is_empty() || entry.weeks.contains(&week))
+                })
+                .map(|entry| &entry.world),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Worlds<'_> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Entry {
+            id: String,
+            name: String,
+            #[serde(default)]
+            days: SmallVec<[SessionDay; 7]>,
+            #[serde(default)]
+            weeks: SmallVec<[u8; 5]>,
+        }
+
+        struct WorldsVisitor;
+        impl<'de> Visitor<'de> for WorldsVisitor {
+            type Value = Worlds<'static>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "a world, or a list of worlds")
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let world = World::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Worlds::One(World {
+                    id: world.id.into_owned().into(),
+                    name: world.name.into_owned().into(),
+                }))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut worlds = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+                while let Some(entry) = seq.next_element::<Entry>()? {
+                    if let Some(week) = entry.weeks.iter().find(|week| !(1..=5).contains(*week)) {
+                        return Err(A::Error::custom(format!(
+                            "{week} is not a week of the month"
+                        )));
+                    }
+                    worlds.push(RotatingWorld {
+                        world: World {
+                            id: entry.id.into(),
+                            name: entry.name.into(),
+                        },
+                        days: entry.days,
+                        weeks: entry.weeks,
+                    });
+                }
+                Ok(Worlds::Rotation(worlds))
+            }
+        }
+
+        deserializer.deserialize_any(WorldsVisitor)
+    }
+}
+
+impl Serialize for Worlds<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Worlds::One(world) => world.serialize(serializer),
+            Worlds::Rotation(worlds) => worlds.serialize(serializer),
+        }
+    }
+}
+
+impl JsonSchema for Worlds<'_> {
+    fn schema_name() -> Cow<'static, str> {
+        "Worlds".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let world = generator.subschema_for::<World>();
+        let day = generator.subschema_for::<SessionDay>();
+        json_schema!({
+            "description": "One world, or at the top of the file, a list of worlds the event rotates between. The first entry that matches a day is used.",
+            "anyOf": [
+                world,
+                {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "id": { "type": "string" },
+                            "name": { "type": "string" },
+                            "days": { "type": "array", "items": day },
+                            "weeks": {
+                                "type": "array",
+                                "items": { "type": "integer", "minimum": 1, "maximum": 5 },
+                            },
+                        },
+                        "required": ["id", "name"],
+                        "additionalProperties": false,
+                    },
+                },
+            ],
+        })
+    }
+}
+
+/// meta.toml, with the details of the whole calendar.
+#[derive(Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Meta<'a> {
     #[serde(borrow)]
@@ -237,18 +1208,188 @@ pub struct Meta<'a> {
     #[serde(borrow)]
     pub description: Option<Cow<'a, str>>,
     #[serde(borrow)]
-    pub link: Option<Cow<'a, str>>,
-    #[serde(borrow, default)]
+    #[schemars(with = "Option<String>")]
+    pub link: Option<Spanned<Cow<'a, str>>>,
+    /// Where the output directory is published, for the addresses in the event pages that are
+    /// shown when links are shared. The pages are only written when this is set.
+    #[serde(borrow)]
+    #[schemars(with = "Option<String>")]
+    pub base_url: Option<Spanned<Cow<'a, str>>>,
+    /// The calendar's primary time zone, which frontends can show times in by default.
+    #[serde(borrow)]
+    #[schemars(with = "Option<String>")]
+    pub home_timezone: Option<Spanned<Cow<'a, str>>>,
+    #[serde(borrow, default, alias = "lang")]
     pub languages: HashMap<Language, MetaLanguage<'a>>,
+    /// The language each language falls back to when an event has no section for it, like
+    /// `pt = "es"`. Languages without a fallback use the event's base details.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<Language, Language>")]
+    pub fallbacks: BTreeMap<Language, Spanned<Language>>,
+    #[serde(borrow, default)]
+    pub organizers: HashMap<Cow<'a, str>, MetaOrganizer<'a>>,
+    /// The tags events can use. Events can use any tag if this is empty.
+    #[serde(borrow, default)]
+    pub tags: Vec<Cow<'a, str>>,
+    /// How often loaders should check for a new calendar.
+    pub poll_interval: Option<Time<Duration>>,
+    /// Dates when every event is canceled, like holidays the whole calendar takes off.
+    #[serde(default)]
+    pub blackout: Vec<NaiveDate>,
+    /// A built-in holiday set like `JP`, or an ICS file relative to the meta file, whose dates
+    /// events with `cancel_on_holidays` are canceled on.
+    #[serde(borrow)]
+    #[schemars(with = "Option<String>")]
+    pub holidays: Option<Spanned<Cow<'a, str>>>,
+    /// How many events can be featured. Any number can be if this isn't set.
+    #[schemars(with = "Option<usize>")]
+    pub max_featured: Option<Spanned<usize>>,
+    /// How long the texts the world shows can be.
+    #[serde(default)]
+    pub max_length: MaxLength,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(default)]
+    pub guardrails: Guardrails,
+    /// Commands to run during the compile.
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    #[serde(borrow)]
+    pub rsvp: Option<RsvpConfig<'a>>,
+    /// Defaults for every event, which `parse_events` reads through `MetaDefaults`.
+    #[serde(borrow, default, rename = "defaults")]
+    #[schemars(description = "Defaults for every event.")]
+    _defaults: Option<Defaults<'a>>,
+    /// Events written in the meta file, which `read_event_files` reads as event files.
+    #[serde(default, rename = "event")]
+    #[schemars(
+        with = "Vec<Event<'static>>",
+        description = "Events written in the meta file, each with an `id`."
+    )]
+    _events: Vec<toml::Table>,
 }
 
-#[derive(Deserialize)]
+/// Where `--rsvp` fetches the RSVP counts from.
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RsvpConfig<'a> {
+    #[serde(borrow)]
+    #[schemars(with = "String")]
+    pub url: Spanned<Cow<'a, str>>,
+    /// How long the counts of the last fetch are used by compiles without `--rsvp`.
+    pub max_age: Option<Time<Duration>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct MetaLanguage<'a> {
     #[serde(borrow)]
     pub title: Option<Cow<'a, str>>,
     #[serde(borrow)]
     pub description: Option<Cow<'a, str>>,
+    /// Defaults to the base link.
     #[serde(borrow)]
-    pub link: Option<Cow<'a, str>>,
+    #[schemars(with = "Option<String>")]
+    pub link: Option<Spanned<Cow<'a, str>>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MetaOrganizer<'a> {
+    #[serde(borrow)]
+    #[schemars(with = "Option<String>")]
+    pub timezone: Option<Spanned<Cow<'a, str>>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub description_too_long: bool,
+    pub max_description_length: usize,
+    pub missing_poster: bool,
+    pub missing_hashtag: bool,
+    pub weeks_with_all_days: bool,
+    /// Every language needs its own title, so that translated pages don't show the base title.
+    pub language_titles: bool,
+    /// Posters that are too bright for the white event name to be read over them.
+    pub poster_contrast: bool,
+    /// Every poster needs a `poster_credit` next to it.
+    pub poster_credit: bool,
+    /// Every poster needs a `poster_license` next to it.
+    pub poster_license: bool,
+    /// The lowest contrast ratio between white text and the poster's average color.
+    pub min_poster_contrast: f64,
+    /// The background the world shows event colors on. Event colors are checked against it if
+    /// it is set.
+    pub color_background: Option<Color>,
+    /// The lowest contrast ratio between an event's color and `color_background`.
+    pub min_color_contrast: f64,
+    pub quiet_hours: Vec<QuietHours>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            description_too_long: true,
+            max_description_length: 300,
+            missing_poster: true,
+            missing_hashtag: false,
+            weeks_with_all_days: true,
+            language_titles: false,
+            poster_contrast: false,
+            poster_credit: false,
+            poster_license: false,
+            min_poster_contrast: 3.0,
+            color_background: None,
+            min_color_contrast: 3.0,
+            quiet_hours: Vec::new(),
+        }
+    }
+}
+
+/// Limits on how much a single compile may take away from the previous one, to catch mistakes like
+/// a wrong input path before they are published.
+#[derive(Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Guardrails {
+    /// The share of the previous events that may be removed or canceled entirely, in percent.
+    pub max_removed_percent: f64,
+    /// How many occurrences may be newly canceled.
+    pub max_canceled: usize,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self {
+            max_removed_percent: 25.0,
+            max_canceled: 10,
+        }
+    }
+}
+
+/// The most characters the world has room for in each text field. Fields without a limit can be
+/// any length.
+#[derive(Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaxLength {
+    pub name: Option<usize>,
+    /// The limit for descriptions, which are counted without their markup.
+    pub description: Option<usize>,
+    pub hashtag: Option<usize>,
+}
+
+/// Times when events for a region are unlikely to be intended, like the middle of the night.
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    /// The time zone the hours are in.
+    #[schemars(with = "String")]
+    pub timezone: Spanned<String>,
+    pub start: Time<NaiveTime>,
+    /// The end of the quiet hours. If this is before `start`, the quiet hours span midnight.
+    pub end: Time<NaiveTime>,
+    /// The rule also applies to events with sections for these languages, in addition to events
+    /// in `timezone`.
+    #[serde(default)]
+    pub languages: Vec<Language>,
 }
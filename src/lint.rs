@@ -0,0 +1,479 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+    sync::Arc,
+};
+
+use chrono::{Datelike, Days, NaiveTime, Utc};
+use chrono_tz::Tz;
+use miette::{Context, Diagnostic, IntoDiagnostic, NamedSource, Report, SourceSpan};
+
+use crate::{
+    check_meta_links, collect_files,
+    discover::FileFilter,
+    error::{
+        ColorLowContrast, DescriptionTooLong, MissingHashtag, MissingLanguageTitle, MissingPoster,
+        MissingPosterCredit, MissingTimeZone, PosterLowContrast, QuietHours, WeeksWithAllDays,
+    },
+    fallbacks, for_each_info, guess_poster,
+    input::{self, day_key, Color, LintConfig},
+    parse_events, parse_meta, read_event_files, read_meta_file,
+    report::Errors,
+    resolved,
+    span::SourceMap,
+    Event,
+};
+
+/// How far ahead to look for occurrences in the quiet hours.
+const QUIET_HOURS_DAYS: u64 = 366;
+
+#[derive(clap::Args)]
+pub struct LintArgs {
+    /// The directory containing meta.toml, the event files, and the posters.
+    input: PathBuf,
+    #[command(flatten)]
+    filter: FileFilter,
+}
+
+pub fn run(args: LintArgs, errors: Errors) -> ExitCode {
+    let files = collect_files(&args.input, &args.filter);
+    let meta_file = match read_meta_file(&files) {
+        Ok(meta_file) => meta_file,
+        Err(error) => {
+            eprintln!("{error:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let meta = match parse_meta(&meta_file) {
+        Ok(meta) => meta,
+        Err(error) => {
+            eprintln!("{error:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let event_files = read_event_files(&files, &errors);
+    let events = parse_events(&event_files, &errors);
+
+    let mut lints = Lints {
+        config: &meta.lint,
+        quiet_hours: Vec::new(),
+        contrasts: HashMap::new(),
+        count: 0,
+    };
+    for rule in &meta.lint.quiet_hours {
+        match Tz::from_str(rule.timezone.get_ref()) {
+            Ok(timezone) => lints.quiet_hours.push((timezone, rule)),
+            Err(_) => lints.report(MissingTimeZone::from_spanned(
+                &rule.timezone,
+                NamedSource::new("meta.toml", meta_file.clone()),
+            )),
+        }
+    }
+    check_meta_links(&meta, &meta_file);
+    fallbacks::check_loops(&meta, &meta_file);
+    if meta.lint.language_titles {
+        lints.check_language_titles(&meta, &meta_file);
+    }
+    for event in &events {
+        if errors.should_stop() {
+            break;
+        }
+        lints.check_event(event, &files);
+    }
+
+    if lints.count != 0 {
+        eprintln!("Found {} problems.", lints.count);
+    }
+    if lints.count != 0 || errors.count() != 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+struct Lints<'a> {
+    config: &'a LintConfig,
+    /// The quiet hours rules with valid time zones.
+    quiet_hours: Vec<(Tz, &'a input::QuietHours)>,
+    /// The contrast of each poster that has been checked, or `None` if it couldn't be read.
+    contrasts: HashMap<PathBuf, Option<f64>>,
+    count: usize,
+}
+
+impl Lints<'_> {
+    fn report(&mut self, diagnostic: impl Diagnostic + Send + Sync + 'static) {
+        self.count += 1;
+        eprintln!("{:?}", Report::new(diagnostic));
+    }
+
+    fn check_language_titles(&mut self, meta: &input::Meta, meta_file: &Arc<String>) {
+        let spans = SourceMap::parse(meta_file);
+        let mut languages: Vec<_> = meta.languages.iter().collect();
+        languages.sort_by_key(|&(id, _)| id);
+        for (id, language) in languages {
+            if language
+                .title
+                .as_ref()
+                .is_some_and(|title| *title != meta.title)
+            {
+                continue;
+            }
+            let code = id.code();
+            let location = spans.as_ref().and_then(|spans| {
+                ["languages", "lang"].into_iter().find_map(|key| {
+                    spans
+                        .span(&[key, code, "title"])
+                        .or_else(|| spans.span(&[key, code]))
+                })
+            });
+            self.report(MissingLanguageTitle {
+                language: code.to_owned(),
+                src: NamedSource::new("meta.toml", meta_file.clone()),
+                location,
+            });
+        }
+    }
+
+    fn check_event(&mut self, event: &Event, files: &BTreeSet<PathBuf>) {
+        let spans = event.source.source_map();
+
+        if self.config.description_too_long {
+            self.check_description(event, spans.as_ref(), &[], &event.event.info);
+            self.check_day_descriptions(event, spans.as_ref(), &["days"], &event.event.days);
+            for (language_id, language) in &event.event.languages {
+                let path = ["languages", language_id.code()];
+                self.check_description(event, spans.as_ref(), &path, &language.info);
+                self.check_day_descriptions(event, spans.as_ref(), &path, &language.days);
+            }
+        }
+
+        if self.config.missing_poster
+            && event.event.info.poster.is_none()
+            && guess_poster(event, files).is_none()
+        {
+            self.report(MissingPoster {
+                path: event.source.path.to_owned(),
+            });
+        }
+
+        if self.config.missing_hashtag && event.event.info.hashtag.is_none() {
+            self.report(MissingHashtag {
+                path: event.source.path.to_owned(),
+            });
+        }
+
+        if self.config.weeks_with_all_days
+            && event.event.info.weeks.is_some()
+            && event.event.days.iter().count() == 7
+        {
+            self.report(WeeksWithAllDays {
+                src: event.source.into(),
+                location: spans.as_ref().and_then(|s| s.span(&["weeks"])),
+            });
+        }
+
+        if self.config.poster_contrast || self.config.poster_credit || self.config.poster_license {
+            let posters = posters(event, files);
+            if self.config.poster_contrast {
+                self.check_poster_contrast(event, spans.as_ref(), &posters);
+            }
+            self.check_poster_credits(event, spans.as_ref(), &posters);
+        }
+
+        if let Some(background) = self.config.color_background {
+            self.check_color_contrast(event, spans.as_ref(), background);
+        }
+
+        self.check_quiet_hours(event, spans.as_ref());
+    }
+
+    fn check_color_contrast(
+        &mut self,
+        event: &Event,
+        spans: Option<&SourceMap>,
+        background: Color,
+    ) {
+        let mut low = Vec::new();
+        for_each_info(&event.event, |path, info| {
+            let Some(color) = info.color else {
+                return;
+            };
+            let contrast = color.contrast(background);
+            if contrast < self.config.min_color_contrast {
+                let mut path: Vec<_> = path.iter().map(|key| key.to_string()).collect();
+                path.push("color".to_owned());
+                low.push((color, contrast, path));
+            }
+        });
+        for (color, contrast, path) in low {
+            self.report(ColorLowContrast {
+                color: color.to_string(),
+                background: background.to_string(),
+                contrast,
+                min: self.config.min_color_contrast,
+                src: event.source.into(),
+                location: spans.and_then(|s| s.span(&path)),
+            });
+        }
+    }
+
+    fn check_poster_contrast(
+        &mut self,
+        event: &Event,
+        spans: Option<&SourceMap>,
+        posters: &[PosterUse],
+    ) {
+        for poster in posters {
+            let contrast = *self
+                .contrasts
+                .entry(poster.poster.to_path_buf())
+                .or_insert_with(|| match white_text_contrast(&poster.poster) {
+                    Ok(contrast) => Some(contrast),
+                    Err(error) => {
+                        eprintln!("{error:?}");
+                        None
+                    }
+                });
+            let Some(contrast) = contrast else {
+                continue;
+            };
+            if contrast < self.config.min_poster_contrast {
+                self.report(PosterLowContrast {
+                    path: poster.poster.to_path_buf(),
+                    contrast,
+                    min: self.config.min_poster_contrast,
+                    src: event.source.into(),
+                    location: poster.location(spans),
+                });
+            }
+        }
+    }
+
+    fn check_poster_credits(
+        &mut self,
+        event: &Event,
+        spans: Option<&SourceMap>,
+        posters: &[PosterUse],
+    ) {
+        for poster in posters {
+            let missing = [
+                ("poster_credit", self.config.poster_credit, poster.credit),
+                ("poster_license", self.config.poster_license, poster.license),
+            ];
+            for (field, required, value) in missing {
+                if required && value.is_none() {
+                    self.report(MissingPosterCredit {
+                        field,
+                        path: poster.poster.to_path_buf(),
+                        src: event.source.into(),
+                        location: poster.location(spans),
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_quiet_hours(&mut self, event: &Event, spans: Option<&SourceMap>) {
+        if self.quiet_hours.is_empty() {
+            return;
+        }
+        // An invalid time zone is reported when compiling. Canceled occurrences are still on the
+        // schedule, so the days off don't matter here.
+        let days_off = resolved::DaysOff::default();
+        let Ok(resolved) = resolved::Event::resolve(event, &days_off) else {
+            return;
+        };
+        let today = Utc::now().with_timezone(&resolved.timezone).date_naive();
+        let until = today + Days::new(QUIET_HOURS_DAYS);
+
+        for (timezone, rule) in self.quiet_hours.clone() {
+            let applies = timezone.name() == resolved.timezone.name()
+                || rule
+                    .languages
+                    .iter()
+                    .any(|language| event.event.languages.contains_key(language));
+            if !applies {
+                continue;
+            }
+            // Each day of the week is reported once, instead of every week it happens.
+            let mut reported = [false; 7];
+            for occurrence in resolved.occurrences(today, until) {
+                let weekday = occurrence.date.weekday();
+                if reported[weekday.num_days_from_monday() as usize] {
+                    continue;
+                }
+                let local = occurrence.start.with_timezone(&timezone);
+                if !in_range(local.time(), rule.start.0, rule.end.0) {
+                    continue;
+                }
+                reported[weekday.num_days_from_monday() as usize] = true;
+                let location = spans.and_then(|s| {
+                    s.span(&["days", day_key(weekday), "start"])
+                        .or_else(|| s.span(&["start"]))
+                });
+                self.report(QuietHours {
+                    local: local.format("%H:%M").to_string(),
+                    timezone: timezone.name().to_owned(),
+                    day: day_key(local.weekday()),
+                    src: event.source.into(),
+                    location,
+                });
+            }
+        }
+    }
+
+    fn check_day_descriptions(
+        &mut self,
+        event: &Event,
+        spans: Option<&SourceMap>,
+        path: &[&str],
+        days: &input::EventDays,
+    ) {
+        for (weekday, day) in days.iter() {
+            let mut path = path.to_vec();
+            path.push(day_key(weekday));
+            self.check_description(event, spans, &path, &day.info);
+        }
+    }
+
+    fn check_description(
+        &mut self,
+        event: &Event,
+        spans: Option<&SourceMap>,
+        path: &[&str],
+        info: &input::EventInfo,
+    ) {
+        let Some(description) = &info.description else {
+            return;
+        };
+        let length = description.chars().count();
+        if length > self.config.max_description_length {
+            let mut path = path.to_vec();
+            path.push("description");
+            self.report(DescriptionTooLong {
+                length,
+                max: self.config.max_description_length,
+                src: event.source.into(),
+                location: spans.and_then(|s| s.span(&path)),
+            });
+        }
+    }
+}
+
+/// A poster an event uses, along with its credit.
+struct PosterUse<'e> {
+    poster: Cow<'e, Path>,
+    /// The table the poster is set in, which is empty for the event's own poster.
+    path: Vec<String>,
+    /// Whether the poster was found by the event's file name instead of set in the file.
+    guessed: bool,
+    credit: Option<&'e str>,
+    license: Option<&'e str>,
+}
+
+impl PosterUse<'_> {
+    fn location(&self, spans: Option<&SourceMap>) -> Option<SourceSpan> {
+        // Guessed posters have no location in the file.
+        if self.guessed {
+            return None;
+        }
+        let mut path = self.path.clone();
+        path.push("poster".to_owned());
+        spans.and_then(|s| s.span(&path))
+    }
+}
+
+/// Every poster the event uses, wherever it is set.
+fn posters<'e>(event: &'e Event<'e>, files: &BTreeSet<PathBuf>) -> Vec<PosterUse<'e>> {
+    let input = &event.event;
+    let mut posters = Vec::new();
+    let mut add = |path: Vec<String>, info: &'e input::EventInfo<'e>| {
+        if let Some(poster) = info.poster.as_deref() {
+            posters.push(PosterUse {
+                poster: Cow::Borrowed(Path::new(poster)),
+                path,
+                guessed: false,
+                credit: info.poster_credit.as_deref(),
+                license: info.poster_license.as_deref(),
+            });
+        }
+    };
+    add(vec![], &input.info);
+    for (weekday, day) in input.days.iter() {
+        add(
+            vec!["days".to_owned(), day_key(weekday).to_owned()],
+            &day.info,
+        );
+    }
+    for (language_id, language) in &input.languages {
+        let code = language_id.code().to_owned();
+        add(vec!["languages".to_owned(), code.clone()], &language.info);
+        for (weekday, day) in language.days.iter() {
+            let path = vec![
+                "languages".to_owned(),
+                code.clone(),
+                day_key(weekday).to_owned(),
+            ];
+            add(path, &day.info);
+        }
+    }
+    for (date, change) in &input.overrides {
+        if let Some(poster) = change.poster.as_deref() {
+            posters.push(PosterUse {
+                poster: Cow::Borrowed(Path::new(poster)),
+                path: vec!["overrides".to_owned(), date.to_string()],
+                guessed: false,
+                credit: change.poster_credit.as_deref(),
+                license: change.poster_license.as_deref(),
+            });
+        }
+    }
+    if input.info.poster.is_none() {
+        if let Some(guessed) = guess_poster(event, files) {
+            posters.push(PosterUse {
+                poster: Cow::Owned(guessed),
+                path: vec![],
+                guessed: true,
+                credit: input.info.poster_credit.as_deref(),
+                license: input.info.poster_license.as_deref(),
+            });
+        }
+    }
+    posters
+}
+
+/// The WCAG contrast ratio between white and the average luminance of the image.
+fn white_text_contrast(path: &Path) -> miette::Result<f64> {
+    let image = image::open(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not read {}", path.display()))?;
+    // The average doesn't need every pixel.
+    let image = image.thumbnail(64, 64).to_rgb8();
+    let linear = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let total: f64 = image
+        .pixels()
+        .map(|&image::Rgb([r, g, b])| 0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b))
+        .sum();
+    let luminance = total / f64::from((image.width() * image.height()).max(1));
+    Ok(1.05 / (luminance + 0.05))
+}
+
+/// Whether `time` is in `[start, end)`, which spans midnight if `end` is before `start`.
+fn in_range(time: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        start <= time || time < end
+    }
+}
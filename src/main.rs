@@ -1,23 +1,21 @@
 use std::{
     borrow::Cow,
+    cell::OnceCell,
     collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap},
     ffi::OsStr,
     fmt,
     fs::{self, File},
-    hash::{Hash, Hasher},
+    hash::Hash,
     io::{self, BufReader, BufWriter, Seek, SeekFrom, Write},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     process::ExitCode,
-    str::FromStr,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
+    ptr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc};
-use chrono_tz::Tz;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use error::StateParseError;
 use iso639_enum::IsoCompat;
 use miette::{
@@ -25,46 +23,278 @@ use miette::{
     Result, Severity,
 };
 
+use discover::{FileFilter, Files};
+use event_format::EventFormat;
+use formats::Format;
+use hooks::Stage;
+use image::imageops::FilterType;
 use output::{Hashtag, Zone};
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use report::{Errors, ReportArgs};
+use retry::Retry;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{de::Visitor, Deserialize, Serialize};
 use sha2::{digest::Output, Digest, Sha256};
+use social::Service;
+use span::SourceMap;
 use state::State;
 use tempfile::NamedTempFile;
+use timings::Timings;
+use unicode_normalization::UnicodeNormalization;
 
 use crate::error::{
-    CanceledOutOfRange, ConfirmedOutOfRange, ImageTooLarge, MissingTimeZone, MultiplePosters,
+    BrokenTimeZone, CanceledOutOfRange, ConfirmedOutOfRange, DateOutOfRange, DuplicateEventId,
+    DuplicateSession, DurationWithEnd, EventConversionError, ExtendsChain, ExtendsNotFound,
+    FileNameCollision, HiatusEndsBeforeStart, HolidaysNotSet, HolidaysUnavailable, ImageTooLarge,
+    IntervalWithWeeks, IntervalWithoutAnchor, InvalidAccount, InvalidEventId, InvalidLink,
+    InvalidMarkup, InvisibleCharacter, JoinViaWithoutField, LanguageDayNotHeld, LonelySeries,
+    ManyEventsRemoved, ManyOccurrencesCanceled, MissingDuration, MissingTimeZone,
+    MissingTimeZoneField, MonthlyOutOfRange, MonthlyWithWeeklySchedule, MultiplePosters,
+    NonUnicodeFileName, NoteExpiresOnOtherDay, NoteOutOfRange, OrganizerTimeZoneMismatch,
+    OutputInInput, OutputOutOfDate, OverrideOutOfRange, ReasonWithoutCancellation,
+    RotationInSection, SessionsWithDays, SkippedOutOfRange, TentativeOutOfRange, TextTooLong,
+    TimeZoneChanged, TooManyFeatured, TzdbChanged, UnknownRelatedEvent, UnknownTag,
+    UnstableWithoutFlag, UnsupportedPlatformOverride,
 };
 
+mod combined;
+mod compress;
+mod deprecated;
+mod diff;
+#[cfg(feature = "discord")]
+mod discord;
+mod discover;
 mod error;
+mod event_format;
+mod explain;
+mod export;
+mod fallbacks;
+mod format;
+mod formats;
+mod holidays;
+mod hooks;
 mod input;
+mod lint;
+mod markup;
+mod migrate;
+mod mirror;
+mod new;
+mod notes;
 mod output;
+mod preview_date;
+mod rename;
+mod report;
+mod resolved;
+mod retry;
+#[cfg(feature = "rsvp")]
+mod rsvp;
+mod schema;
+mod selftest;
+mod single;
+mod social;
+mod span;
 mod state;
+mod stats;
 mod time;
+mod timings;
+mod upcoming;
+mod usage;
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    input: PathBuf,
-    output: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    compile: CompileArgs,
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a new event file.
+    New(new::NewArgs),
+    /// Rewrite event files in the canonical format.
+    Fmt(format::FmtArgs),
+    /// Check event files for common mistakes.
+    Lint(lint::LintArgs),
+    /// Write every event in the canonical event file format.
+    ExportToml(export::ExportTomlArgs),
+    /// Summarize the events in the calendar.
+    Stats(stats::StatsArgs),
+    /// List the next occurrences of each event.
+    Upcoming(upcoming::UpcomingArgs),
+    /// Show what the calendar shows on a date, and why.
+    PreviewDate(preview_date::PreviewDateArgs),
+    /// Explain a diagnostic code.
+    Explain(explain::ExplainArgs),
+    /// Compile one event read from stdin, and print its output JSON.
+    CompileEvent(single::CompileEventArgs),
+    /// Rename events, keeping their state and redirecting their old ids.
+    Migrate(migrate::MigrateArgs),
+    /// Check the time zone and recurrence handling against generated edge cases.
+    Selftest(selftest::SelftestArgs),
+    /// Write JSON Schemas of event files, meta.toml and _defaults.toml, for editors to check them
+    /// against.
+    Schema(schema::SchemaArgs),
+    /// Add confirmations posted to a Discord channel to the event files.
+    #[cfg(feature = "discord")]
+    ConfirmDiscord(discord::ConfirmDiscordArgs),
+}
+
+#[derive(clap::Args)]
+struct CompileArgs {
+    /// The directories or archives containing the event files and the posters. meta.toml is read
+    /// from the first one.
+    #[arg(required = true, num_args = 1..)]
+    inputs: Vec<PathBuf>,
+    /// The directory to save data.json and the posters in.
+    #[arg(required = true)]
+    output: Option<PathBuf>,
+    /// Read the input as a compiled data.json instead of event files, and only write the
+    /// formats.
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "verify", "preview", "notes"]
+    )]
+    from_data: bool,
+    /// Show what would change in the output directory without writing anything.
+    #[arg(long)]
+    dry_run: bool,
+    /// Fail if data.json is not what compiling the input would produce, without writing anything.
+    #[arg(long, conflicts_with = "dry_run")]
+    verify: bool,
+    /// Print which event files use each poster slot.
+    #[arg(long)]
+    verbose: bool,
+    /// Print how long each phase of the compile took.
+    #[arg(long)]
+    timings: bool,
+    /// Keep confirmations and cancellations for occurrences that ended up to this many minutes
+    /// ago, so a compile that runs late doesn't drop them as the event ends.
+    #[arg(long, value_name = "MINUTES", default_value_t = DEFAULT_GRACE_MINUTES)]
+    grace: u32,
+    /// The formats to write the calendar in.
+    #[arg(
+        long = "format",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "json"
+    )]
+    formats: Vec<Format>,
+    /// Include the fields from the [unstable] sections of events, which may change in any
+    /// version.
+    #[arg(long)]
+    unstable: bool,
+    /// How to write the keys of data.json and data.msgpack. The world needs short keys.
+    #[arg(long, value_enum, default_value = "short")]
+    keys: output::Keys,
+    /// Write Android and iOS as Quest in data.json and data.msgpack, for worlds that only know PC
+    /// and Quest. All three run the same builds of worlds.
+    #[arg(long)]
+    compat_platforms: bool,
+    /// Also write data.schema.json, a JSON Schema of data.json and data.msgpack with the keys
+    /// chosen by --keys, for checking frontends against.
+    #[arg(long)]
+    emit_schema: bool,
+    /// Run the commands in the [[hooks]] of meta.toml. Only give this for input that is trusted,
+    /// since the hooks can run anything.
+    #[arg(long)]
+    run_hooks: bool,
+    /// Also write data.json.gz and data.json.br, compressed copies of data.json for static hosts
+    /// to serve as they are.
+    #[arg(long)]
+    precompress: bool,
+    /// Also write a single HTML file showing the compiled calendar with its posters, for
+    /// reviewing changes. This is written even with --dry-run or --verify.
+    #[arg(long, value_name = "FILE")]
+    preview: Option<PathBuf>,
+    /// Also write notes.json, listing the notes, special occurrences, cancellations and breaks
+    /// of the next this many days.
+    #[arg(long, value_name = "DAYS")]
+    notes: Option<u32>,
+    /// Let posters that look the same share a slot even if their files differ, like the same
+    /// artwork saved as PNG by one organizer and as JPEG by another. This decodes every poster,
+    /// so it is slower.
+    #[arg(long)]
+    similar_posters: bool,
+    /// Fetch the RSVP counts of upcoming occurrences from the source in meta.toml. Without this,
+    /// the counts from the last fetch are used until they are older than `max_age` in [rsvp].
+    #[cfg(feature = "rsvp")]
+    #[arg(long, conflicts_with = "verify")]
+    rsvp: bool,
+    #[command(flatten)]
+    filter: FileFilter,
+    #[command(flatten)]
+    retry: Retry,
 }
 
 fn main() -> ExitCode {
     let args = Args::parse();
 
-    let errors = Arc::new(AtomicUsize::new(0));
+    let errors = args.report.errors();
+    let json = match args.report.open() {
+        Ok(json) => json.map(Arc::new),
+        Err(error) => {
+            eprintln!("Could not open the error file: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
     miette::set_hook({
         let errors = errors.clone();
         Box::new(move |_| {
             Box::new(Handler {
                 inner: MietteHandler::new(),
                 errors: errors.clone(),
+                strict: args.report.strict,
+                json: json.clone(),
+                json_on_stderr: args.report.json_on_stderr(),
             })
         })
     })
     .unwrap();
 
-    if !args.output.exists() {
-        if let Err(err) = fs::create_dir_all(&args.output)
+    match args.command {
+        Some(Command::New(args)) => new::run(args),
+        Some(Command::Fmt(args)) => format::run(args),
+        Some(Command::Lint(args)) => lint::run(args, errors),
+        Some(Command::ExportToml(args)) => export::run(args, errors),
+        Some(Command::Stats(args)) => stats::run(args, errors),
+        Some(Command::Upcoming(args)) => upcoming::run(args, errors),
+        Some(Command::PreviewDate(args)) => preview_date::run(args, errors),
+        Some(Command::Explain(args)) => explain::run(args),
+        Some(Command::CompileEvent(args)) => single::run(args, errors),
+        Some(Command::Migrate(args)) => migrate::run(args, errors),
+        Some(Command::Selftest(args)) => selftest::run(args, errors),
+        Some(Command::Schema(args)) => schema::run(args),
+        #[cfg(feature = "discord")]
+        Some(Command::ConfirmDiscord(args)) => discord::run(args, errors),
+        None => compile(args.compile, errors),
+    }
+}
+
+fn compile(args: CompileArgs, errors: Errors) -> ExitCode {
+    // clap enforces these when no subcommand is given.
+    let Some(output) = args.output.clone() else {
+        unreachable!();
+    };
+    if args.from_data {
+        let [data] = &args.inputs[..] else {
+            eprintln!("--from-data reads a single data.json.");
+            return ExitCode::FAILURE;
+        };
+        return mirror::render(data, &output, &args);
+    }
+    let mut timings = Timings::new(args.timings);
+
+    if let Err(error) = check_output_location(&args.inputs, &output, &args.filter) {
+        eprintln!("{error:?}");
+        return ExitCode::FAILURE;
+    }
+
+    if !args.dry_run && !args.verify && !output.exists() {
+        if let Err(err) = fs::create_dir_all(&output)
             .into_diagnostic()
             .wrap_err("Could not create output directory")
         {
@@ -73,486 +303,2611 @@ fn main() -> ExitCode {
         }
     }
 
-    let now = Utc::now();
+    let now = if args.verify {
+        // Compile as of the previous compile so that unchanged input gives identical output.
+        match diff::compiled_time(&output) {
+            Ok(now) => now,
+            Err(error) => {
+                eprintln!("{error:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        Utc::now()
+    };
+    let read_only = args.dry_run || args.verify;
 
-    let mut state = match load_state(&args.output) {
+    let mut state = match load_state(&output) {
         Ok(state) => state,
         Err(error) => {
             eprintln!("{error:?}");
             return ExitCode::FAILURE;
         }
     };
-    let mut posters = Posters::load(args.output.join("posters"), &state, now);
+    let mut posters = Posters::load(
+        output.join("posters"),
+        &state,
+        now,
+        read_only,
+        args.similar_posters,
+        args.retry,
+    );
+    timings.phase("Loading state");
 
-    let mut files = BTreeSet::<PathBuf>::new();
-    match fs::read_dir(&args.input)
-        .into_diagnostic()
-        .wrap_err("Collecting input failed.")
-    {
-        Ok(dir) => {
-            for file in dir {
-                match file.into_diagnostic().wrap_err("Collecting input failed.") {
-                    Ok(file) => {
-                        files.insert(file.path());
-                    }
-                    Err(error) => {
-                        eprintln!("{error:?}");
-                    }
-                }
-            }
-        }
+    let files = collect_inputs(&args.inputs, &args.filter);
+    timings.phase("Scanning input");
+
+    let meta_file = match read_meta_file(&files) {
+        Ok(meta_file) => meta_file,
         Err(error) => {
             eprintln!("{error:?}");
+            return ExitCode::FAILURE;
         }
-    }
-
-    let meta_file = if let Some(meta_file) = files
-        .iter()
-        .find(|f| f.file_name() == Some(OsStr::new("meta.toml")))
-    {
-        match fs::read_to_string(meta_file)
-            .into_diagnostic()
-            .wrap_err_with(|| format!("Reading {} failed.", meta_file.display()))
-        {
-            Ok(content) => Arc::new(content),
-            Err(error) => {
-                eprintln!("{error:?}");
-                return ExitCode::FAILURE;
-            }
-        }
-    } else {
-        eprintln!("{:?}", miette!("meta.toml not found."));
-        return ExitCode::FAILURE;
     };
-
-    let meta = match input::Meta::deserialize(toml::Deserializer::new(&meta_file))
-        .map_err(|error| error::EventParseError {
-            src: NamedSource::new("meta.toml", meta_file.clone()),
-            location: error.span().map(|s| s.into()),
-            error,
-        })
-        .wrap_err("Parsing meta.toml failed.")
-    {
+    let meta = match parse_meta(&meta_file) {
         Ok(meta) => meta,
         Err(error) => {
             eprintln!("{error:?}");
             return ExitCode::FAILURE;
         }
     };
+    // The hooks come from the input, so they only run when whoever runs the compile trusts it.
+    let hooks: &[hooks::Hook] = if args.run_hooks {
+        &meta.hooks
+    } else {
+        if !meta.hooks.is_empty() {
+            eprintln!("Skipping the hooks in meta.toml, which only run with --run-hooks.");
+        }
+        &[]
+    };
 
-    let output_meta = output::Meta {
+    let mut output_meta = output::Meta {
         title: &meta.title,
         description: meta.description.as_deref(),
-        link: meta.link.as_deref(),
+        link: meta.link.as_ref().map(|link| link.as_ref().as_ref()),
+        home_timezone: meta.home_timezone.as_ref().map(|tz| tz.as_ref().as_ref()),
         compiled_time: now.timestamp(),
         languages: meta
             .languages
             .iter()
-            .map(|(&id, language)| {
+            .map(|(id, language)| {
                 (
-                    id,
+                    id.clone(),
                     output::MetaLanguage {
                         title: language.title.as_deref(),
                         description: language.description.as_deref(),
-                        link: language.link.as_deref(),
+                        link: language
+                            .link
+                            .as_ref()
+                            .or(meta.link.as_ref())
+                            .map(|link| link.as_ref().as_ref()),
                     },
                 )
             })
             .collect(),
+        fallbacks: fallbacks::resolve(&meta),
+        series: Vec::new(),
+        features: BTreeSet::new(),
     };
 
-    let mut event_files = Vec::new();
-    for file in files.iter().filter(|f| {
-        f.file_name() != Some(OsStr::new("meta.toml")) && f.extension() == Some(OsStr::new("toml"))
-    }) {
-        match fs::read_to_string(file)
-            .into_diagnostic()
-            .wrap_err_with(|| format!("Reading {} failed.", file.display()))
-        {
-            Ok(content) => {
-                event_files.push(EventFile {
-                    path: file,
-                    content: Arc::new(content),
-                });
-            }
-            Err(error) => {
-                eprintln!("{error:?}");
-            }
-        };
+    let event_files = read_event_files(&files, &errors);
+    let input_events = parse_events(&event_files, &errors);
+    timings.phase("Parsing TOML");
+
+    let hook_input = hooks::AfterParse {
+        inputs: &args.inputs,
+        events: input_events.iter().map(|e| e.source.path).collect(),
+    };
+    if let Err(e) = hooks::run(hooks, Stage::AfterParse, &hook_input) {
+        eprintln!("{e:?}");
+        return ExitCode::FAILURE;
     }
 
-    let mut input_events = Vec::with_capacity(event_files.len());
-    for file in event_files.iter() {
-        match input::Event::deserialize(toml::Deserializer::new(&file.content))
-            .map_err(|error| error::EventParseError::new(error, file))
-            .wrap_err_with(|| format!("Parsing {} failed.", file.path.display()))
-        {
-            Ok(input) => {
-                input_events.push(Event {
-                    source: file,
-                    event: input,
-                });
-            }
-            Err(error) => {
-                eprintln!("{error:?}");
+    let (zones, broken_zones) = time::collect_zones(now);
+    let grace = chrono::Duration::minutes(args.grace.into());
+    timings.phase("Building zones");
+
+    let meta_timezones = meta
+        .home_timezone
+        .iter()
+        .chain(meta.organizers.values().filter_map(|o| o.timezone.as_ref()));
+    for timezone in meta_timezones {
+        if broken_zones.contains(timezone.as_ref().as_ref()) {
+            eprintln!(
+                "{:?}",
+                Report::new(BrokenTimeZone::from_spanned(
+                    timezone,
+                    NamedSource::new("meta.toml", meta_file.clone()),
+                )),
+            );
+        } else if !zones.contains_key(timezone.as_ref().as_ref()) {
+            eprintln!(
+                "{:?}",
+                Report::new(MissingTimeZone::from_spanned(
+                    timezone,
+                    NamedSource::new("meta.toml", meta_file.clone()),
+                )),
+            );
+        }
+    }
+    check_meta_links(&meta, &meta_file);
+    fallbacks::check_loops(&meta, &meta_file);
+
+    #[cfg(feature = "rsvp")]
+    if args.rsvp {
+        match &meta.rsvp {
+            Some(config) => {
+                let url = config.url.as_ref().as_ref();
+                match rsvp::fetch(url) {
+                    Ok(counts) => {
+                        state.rsvps = counts;
+                        state.rsvps_fetched = Some(now);
+                    }
+                    Err(error) => eprintln!(
+                        "{:?}",
+                        Report::new(error::RsvpFetchFailed {
+                            url: url.to_owned(),
+                            reason: error.to_string(),
+                        }),
+                    ),
+                }
             }
+            None => eprintln!(
+                "{:?}",
+                miette!("--rsvp needs an [rsvp] section in meta.toml.")
+            ),
+        }
+        timings.phase("Fetching RSVP counts");
+    }
+    // Old counts would show sign-ups that may have been withdrawn since, so they expire.
+    let max_age = meta
+        .rsvp
+        .as_ref()
+        .and_then(|config| config.max_age)
+        .map_or(chrono::Duration::hours(DEFAULT_RSVP_MAX_AGE_HOURS), |age| {
+            age.0
+        });
+    if state
+        .rsvps_fetched
+        .is_none_or(|fetched| fetched + max_age < now)
+    {
+        if let Some(fetched) = state.rsvps_fetched {
+            eprintln!(
+                "Not using the RSVP counts fetched at {fetched}, which are older than the max_age of [rsvp]."
+            );
         }
+        state.rsvps.clear();
+        state.rsvps_fetched = None;
     }
 
-    let zones = time::collect_zones(now);
+    let meta_directory = files
+        .iter()
+        .find(|f| f.file_name() == Some(OsStr::new("meta.toml")))
+        .and_then(|f| f.parent())
+        .unwrap_or(Path::new(""));
+    let days_off = resolved::DaysOff {
+        blackout: meta.blackout.clone(),
+        holidays: load_holidays(&meta, &meta_file, meta_directory, now),
+    };
 
     let mut output_events = Vec::with_capacity(input_events.len());
+    let mut output_sources = Vec::with_capacity(input_events.len());
+    let mut timezones = BTreeMap::new();
     for event in input_events.iter() {
-        match prepare_event(event, &files, &zones, now, &mut posters).wrap_err_with(|| {
-            format!(
-                "File {} could not be processed.",
-                event.source.path.display(),
-            )
-        }) {
-            Ok(event) => output_events.push(event),
+        if errors.should_stop() {
+            break;
+        }
+        if broken_zones.contains(event.event.timezone().as_ref().as_ref()) {
+            eprintln!("{:?}", Report::new(BrokenTimeZone::new(event)));
+            continue;
+        }
+        let rsvps = state.rsvps.get(event.id().as_ref());
+        match prepare_event(event, &files, &zones, now, grace, &mut posters, rsvps).wrap_err_with(
+            || {
+                format!(
+                    "File {} could not be processed.",
+                    event.source.path.display(),
+                )
+            },
+        ) {
+            Ok(mut output_event) => {
+                cancel_days_off(&mut output_event, event, &days_off, now, grace);
+                check_holidays(event, &meta);
+                check_time_zone(event, &meta, &state, &zones);
+                check_unstable(event, args.unstable);
+                check_tags(event, &meta);
+                check_lengths(event, &meta);
+                check_characters(event);
+                fallbacks::check_event(event, &meta);
+                check_links(event);
+                check_descriptions(event);
+                check_accounts(event);
+                check_worlds(event);
+                check_join_via(event);
+                check_language_days(event);
+                timezones.insert(
+                    event.id().into_owned(),
+                    event.event.timezone().as_ref().clone().into_owned(),
+                );
+                output_events.push(output_event);
+                output_sources.push(event);
+            }
             Err(error) => eprintln!("{error:?}"),
         }
     }
+    resolve_related(&output_sources, &mut output_events);
+    check_featured(&output_sources, &meta, &meta_file);
+    check_series(&output_sources);
 
-    if errors.load(Ordering::SeqCst) == 0 {
-        posters.save(&mut state);
-        if let Err(e) = safely_save(&args.output, "state.json", |mut t| {
-            serde_json::to_writer_pretty(&mut t, &state).into_diagnostic()?;
-            t.write_all(b"\n").into_diagnostic()
-        }) {
+    output_meta.series = output_events
+        .iter()
+        .filter_map(|event| event.series)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    for event in &output_events {
+        event.collect_features(&mut output_meta.features);
+    }
+    timings.phase_with_part("Converting events", "Hashing posters", posters.hashing);
+
+    let ids: Vec<_> = output_sources.iter().map(|event| event.id()).collect();
+    check_losses(
+        &output,
+        &args.formats,
+        &ids,
+        &output_events,
+        &meta.guardrails,
+    );
+
+    if errors.count() != 0 {
+        return ExitCode::FAILURE;
+    }
+
+    let poster_sources = std::mem::take(&mut posters.sources);
+    let written_posters = posters.save(&mut state);
+    state.tzdb = Some(check_tzdb(&state, &zones, &timezones, now));
+    state.timezones = timezones;
+    state.events = ids.into_iter().map(Cow::into_owned).collect();
+    let state_json = match serde_json::to_vec_pretty(&state).into_diagnostic() {
+        Ok(mut json) => {
+            json.push(b'\n');
+            json
+        }
+        Err(e) => {
+            eprintln!("{e:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // Redirects stop once the old id is used by an event again.
+    let redirects: BTreeMap<_, _> = state
+        .redirects
+        .iter()
+        .filter(|(old, new)| {
+            state.timezones.contains_key(*new) && !state.timezones.contains_key(*old)
+        })
+        .map(|(old, new)| (old.as_str(), new.as_str()))
+        .collect();
+    if !redirects.is_empty() {
+        output_meta.features.insert(output::Feature::Redirects);
+    }
+    if args.keys == output::Keys::Long {
+        output_meta.features.insert(output::Feature::LongKeys);
+    }
+    if args.compat_platforms {
+        output_meta
+            .features
+            .remove(&output::Feature::MobilePlatforms);
+    }
+    let data = output::Data {
+        meta: &output_meta,
+        events: &output_events,
+        zones: &zones,
+        redirects,
+        keys: args.keys,
+        compat_platforms: args.compat_platforms,
+    };
+    let mut rendered = Vec::with_capacity(args.formats.len());
+    for format in args.formats.iter().collect::<BTreeSet<_>>() {
+        let format = format.output_format();
+        match format
+            .render(&data)
+            .wrap_err_with(|| format!("Rendering {} failed.", format.file_name()))
+        {
+            Ok(content) => rendered.push((format.file_name().to_owned(), content)),
+            Err(e) => {
+                eprintln!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if let Some((_, json)) = rendered.iter().find(|(name, _)| *name == "data.json") {
+        let loader = output::Loader {
+            version: output::FORMAT_VERSION,
+            bytes: json.len(),
+            posters: state.posters.len(),
+            poll: meta
+                .poll_interval
+                .map_or(DEFAULT_POLL_SECONDS, |interval| interval.0.num_seconds()),
+        };
+        match serde_json::to_vec(&loader).into_diagnostic() {
+            Ok(content) => rendered.push(("loader.json".to_owned(), content)),
+            Err(e) => {
+                eprintln!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if args.precompress {
+        if let Err(e) = compress::add_copies("data.json", &mut rendered) {
             eprintln!("{e:?}");
             return ExitCode::FAILURE;
         }
+    }
+    if let Some(days) = args.notes {
+        match notes::collect(&output_sources, &days_off, now, days)
+            .and_then(|feed| serde_json::to_vec(&feed).into_diagnostic())
+            .wrap_err("Rendering notes.json failed.")
+        {
+            Ok(content) => rendered.push(("notes.json".to_owned(), content)),
+            Err(e) => {
+                eprintln!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if args.emit_schema {
+        match output::schema(args.keys).into_diagnostic() {
+            Ok(content) => rendered.push((output::SCHEMA_FILE_NAME.to_owned(), content)),
+            Err(e) => {
+                eprintln!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if let Some(base_url) = &meta.base_url {
+        rendered.extend(formats::share::render(
+            base_url.as_ref(),
+            &output_meta,
+            &output_sources,
+            &output_events,
+        ));
+    }
+    timings.phase("Serializing");
 
-        if let Err(e) = safely_save(&args.output, "data.json", |mut t| {
-            serde_json::to_writer(
-                &mut t,
-                &output::Data {
-                    meta: &output_meta,
-                    events: &output_events,
-                    zones: &zones,
-                },
-            )
-            .into_diagnostic()?;
-            t.write_all(b"\n").into_diagnostic()
-        }) {
+    if let Some(path) = &args.preview {
+        let saved = formats::preview::render(&data, &poster_sources).and_then(|content| {
+            let directory = match path.parent() {
+                Some(parent) if parent != Path::new("") => parent,
+                _ => Path::new("."),
+            };
+            let name = path
+                .file_name()
+                .ok_or_else(|| miette!("{} is not a file.", path.display()))?;
+            safely_save(directory, name, &args.retry, |t| {
+                t.write_all(&content).into_diagnostic()
+            })
+        });
+        if let Err(e) = saved.wrap_err("Writing the preview failed.") {
             eprintln!("{e:?}");
             return ExitCode::FAILURE;
         }
-        ExitCode::SUCCESS
-    } else {
-        ExitCode::FAILURE
     }
-}
 
-fn load_state(output_path: &Path) -> miette::Result<State> {
-    let state_path = output_path.join("state.json");
-    let state = match fs::read(&state_path) {
-        Ok(state) => state,
-        Err(e) if e.kind() == io::ErrorKind::NotFound => {
-            eprintln!("Initializing new state");
-            return Ok(Default::default());
+    let hook_input = hooks::BeforeWrite {
+        output: &output,
+        data: &data,
+    };
+    if let Err(e) = hooks::run(hooks, Stage::BeforeWrite, &hook_input) {
+        eprintln!("{e:?}");
+        return ExitCode::FAILURE;
+    }
+
+    if args.verbose {
+        usage::report(&output_sources, &output_events);
+    }
+    if args.dry_run {
+        diff::report(&output, &rendered, &state_json, &written_posters);
+        return ExitCode::SUCCESS;
+    }
+    if args.verify {
+        let out_of_date: Vec<_> = rendered
+            .iter()
+            .filter(|(name, content)| {
+                fs::read(output.join(name)).ok().as_deref() != Some(&content[..])
+            })
+            .collect();
+        if out_of_date.is_empty() {
+            return ExitCode::SUCCESS;
         }
-        Err(e) => {
-            return Err(e)
-                .into_diagnostic()
-                .wrap_err_with(|| format!("Could not read {}", state_path.display()))
+        diff::report(&output, &rendered, &state_json, &written_posters);
+        for (name, _) in out_of_date {
+            eprintln!(
+                "{:?}",
+                Report::new(OutputOutOfDate {
+                    path: output.join(name),
+                }),
+            );
+        }
+        return ExitCode::FAILURE;
+    }
+
+    let share_pages: BTreeSet<_> = rendered
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| formats::share::is_page(name))
+        .collect();
+    if !share_pages.is_empty() {
+        let directory = output.join(formats::share::DIRECTORY);
+        if let Err(e) = fs::create_dir_all(&directory)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Could not create {}", directory.display()))
+        {
+            eprintln!("{e:?}");
+            return ExitCode::FAILURE;
+        }
+    }
+    let files = [("state.json", &state_json)].into_iter().chain(
+        rendered
+            .iter()
+            .map(|(name, content)| (name.as_str(), content)),
+    );
+    for (name, content) in files {
+        if let Err(e) = safely_save(&output, name, &args.retry, |t| {
+            t.write_all(content).into_diagnostic()
+        }) {
+            eprintln!("{e:?}");
+            return ExitCode::FAILURE;
         }
+    }
+    if let Err(e) = formats::share::remove_stale(&output, &share_pages)
+        .wrap_err("Removing the pages of removed events failed.")
+    {
+        eprintln!("{e:?}");
+        return ExitCode::FAILURE;
+    }
+    timings.phase("Writing");
+    if let Some(summary) = compress::summary("data.json", &rendered) {
+        eprintln!("{summary}");
+    }
+
+    let hook_input = hooks::AfterWrite {
+        output: &output,
+        files: ["state.json"]
+            .into_iter()
+            .chain(rendered.iter().map(|(name, _)| name.as_str()))
+            .map(|name| output.join(name))
+            .collect(),
+        posters: written_posters
+            .iter()
+            .map(|(index, _)| output.join("posters").join(format!("{index:02x}")))
+            .collect(),
     };
-    match serde_json::from_slice(&state) {
-        Ok(state) => Ok(state),
-        Err(e) => Err(StateParseError::new(e, &output_path.to_string_lossy(), state).into()),
+    if let Err(e) = hooks::run(hooks, Stage::AfterWrite, &hook_input) {
+        eprintln!("{e:?}");
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
 
-fn safely_save(
-    output_path: &Path,
-    name: &str,
-    save: impl FnOnce(&mut BufWriter<&mut NamedTempFile>) -> miette::Result<()>,
-) -> miette::Result<()> {
-    let save_path = output_path.join(name);
-    tempfile::Builder::new()
-        .tempfile_in(output_path)
-        .into_diagnostic()
-        .and_then(|mut t| {
-            {
-                let mut t = BufWriter::new(&mut t);
-                save(&mut t)?;
-                t.flush().into_diagnostic()?;
-            }
-            t.persist(&save_path).into_diagnostic()?;
-            Ok(())
-        })
-        .wrap_err_with(|| format!("Could not save {}", save_path.display()))
+/// Reports more featured events than the meta file allows.
+fn check_featured(events: &[&Event], meta: &input::Meta, meta_file: &Arc<String>) {
+    let Some(max) = &meta.max_featured else {
+        return;
+    };
+    let featured: Vec<_> = events
+        .iter()
+        .filter(|event| event.event.featured)
+        .map(|event| event.id())
+        .collect();
+    if featured.len() > *max.get_ref() {
+        eprintln!(
+            "{:?}",
+            Report::new(TooManyFeatured {
+                count: featured.len(),
+                max: *max.get_ref(),
+                events: featured.join(", "),
+                src: NamedSource::new("meta.toml", meta_file.clone()),
+                location: max.span().into(),
+            }),
+        );
+    }
 }
 
-struct Handler {
-    inner: MietteHandler,
-    errors: Arc<AtomicUsize>,
+/// Reports series that only one event belongs to, which are usually typos.
+fn check_series(events: &[&Event]) {
+    let mut series = BTreeMap::<_, Vec<_>>::new();
+    for event in events {
+        if let Some(id) = &event.event.series {
+            series
+                .entry(id.as_ref())
+                .or_default()
+                .push((event, id.span()));
+        }
+    }
+    for (id, members) in series {
+        if let [(event, span)] = &members[..] {
+            eprintln!(
+                "{:?}",
+                Report::new(LonelySeries {
+                    id: id.as_ref().to_owned(),
+                    src: event.file_of("series").into(),
+                    location: span.clone().into(),
+                }),
+            );
+        }
+    }
 }
 
-impl ReportHandler for Handler {
-    fn debug(
-        &self,
-        error: &(dyn Diagnostic),
-        f: &mut core::fmt::Formatter<'_>,
-    ) -> core::fmt::Result {
-        let severity = error.severity().unwrap_or(miette::Severity::Error);
-        if severity == Severity::Error {
-            self.errors.fetch_add(1, Ordering::SeqCst);
+/// Reports compiles that would take away much more than usual from the previous output.
+fn check_losses(
+    output: &Path,
+    formats: &[Format],
+    ids: &[Cow<str>],
+    events: &[output::Event],
+    guardrails: &input::Guardrails,
+) {
+    let Some(losses) = diff::losses(output, formats, ids, events) else {
+        return;
+    };
+    let percent = losses.removed.len() as f64 * 100.0 / losses.total as f64;
+    if percent > guardrails.max_removed_percent {
+        let mut names = losses.removed.iter().take(5).cloned().collect::<Vec<_>>();
+        if losses.removed.len() > names.len() {
+            names.push(format!("and {} more", losses.removed.len() - names.len()));
         }
-        self.inner.debug(error, f)
+        eprintln!(
+            "{:?}",
+            Report::new(ManyEventsRemoved {
+                removed: losses.removed.len(),
+                total: losses.total,
+                max: guardrails.max_removed_percent,
+                events: names.join(", "),
+            }),
+        );
+    }
+    if losses.canceled > guardrails.max_canceled {
+        eprintln!(
+            "{:?}",
+            Report::new(ManyOccurrencesCanceled {
+                canceled: losses.canceled,
+                max: guardrails.max_canceled,
+            }),
+        );
     }
 }
 
-pub struct EventFile<'a> {
-    path: &'a Path,
-    content: Arc<String>,
-}
+/// How long after an occurrence ends its confirmation or cancellation is kept by default.
+const DEFAULT_GRACE_MINUTES: u32 = 30;
 
-pub struct Event<'a> {
-    source: &'a EventFile<'a>,
-    event: input::Event<'a>,
-}
+/// The polling interval recommended to loaders if meta.toml doesn't set one.
+const DEFAULT_POLL_SECONDS: i64 = 60 * 60;
 
-impl<'a> Event<'a> {
-    pub fn get_time_for_day(
-        &self,
-        date: NaiveDate,
-        timezone: Tz,
-        force: bool,
-    ) -> Result<Option<DateTime<Tz>>> {
-        if let Some(start_date) = self.event.start_date {
-            if date < start_date {
-                return Ok(None);
-            }
-        }
-        if let Some(end_date) = self.event.end_date {
-            if end_date < date {
-                return Ok(None);
-            }
-        }
-        let day = match date.weekday() {
-            chrono::Weekday::Mon => self.event.days.monday.as_ref(),
-            chrono::Weekday::Tue => self.event.days.tuesday.as_ref(),
-            chrono::Weekday::Wed => self.event.days.wednesday.as_ref(),
-            chrono::Weekday::Thu => self.event.days.thursday.as_ref(),
-            chrono::Weekday::Fri => self.event.days.friday.as_ref(),
-            chrono::Weekday::Sat => self.event.days.saturday.as_ref(),
-            chrono::Weekday::Sun => self.event.days.sunday.as_ref(),
-        };
-        if !force && day.is_none() {
-            return Ok(None);
-        }
-        let time = day.and_then(|d| d.start).unwrap_or(self.event.start).0;
-        Ok(date.and_time(time).and_local_timezone(timezone).earliest())
-    }
+/// How long RSVP counts are used after they were fetched if meta.toml doesn't set `max_age`.
+const DEFAULT_RSVP_MAX_AGE_HOURS: i64 = 24;
+
+fn collect_files(input: &Path, filter: &FileFilter) -> Files {
+    collect_inputs(&[input.to_owned()], filter)
 }
 
-#[derive(Deserialize, Serialize)]
-#[serde(rename_all = "lowercase")]
-pub enum Platform {
-    Pc,
-    Quest,
+/// Collects the files from several input directories or archives. Only the first input's
+/// meta.toml is kept.
+fn collect_inputs(inputs: &[PathBuf], filter: &FileFilter) -> Files {
+    let mut files = BTreeSet::new();
+    let mut extracted = Vec::new();
+    let mut ids = HashMap::<&OsStr, PathBuf>::new();
+    for (index, input) in inputs.iter().enumerate() {
+        let input = match discover::extract(input) {
+            Ok((input, directory)) => {
+                extracted.extend(directory);
+                input
+            }
+            Err(error) => {
+                eprintln!("{error:?}");
+                continue;
+            }
+        };
+        let found = filter.walk(&input);
+        check_name_collisions(&found);
+        for file in found {
+            if file.file_name() == Some(OsStr::new("meta.toml")) {
+                if index == 0 {
+                    files.insert(file);
+                }
+                continue;
+            }
+            files.insert(file);
+        }
+    }
+    let meta = files
+        .iter()
+        .find(|f| f.file_name() == Some(OsStr::new("meta.toml")));
+    let entries = meta.map(|meta| combined::find(meta)).unwrap_or_default();
+    let event_files = files
+        .iter()
+        .filter(|file| !is_defaults_file(file) && EventFormat::of(file).is_some())
+        .map(|file| (file, file));
+    // Events in meta.toml are reported as meta.toml.
+    let entry_files = meta
+        .into_iter()
+        .flat_map(|meta| entries.iter().map(move |(_, path)| (path, meta)));
+    for (path, file) in event_files.chain(entry_files) {
+        let Some(id) = EventFormat::id(path) else {
+            continue;
+        };
+        match ids.entry(id) {
+            Entry::Occupied(e) => eprintln!(
+                "{:?}",
+                Report::new(DuplicateEventId {
+                    first: e.get().clone(),
+                    second: file.clone(),
+                }),
+            ),
+            Entry::Vacant(e) => {
+                e.insert(file.clone());
+            }
+        }
+    }
+    Files::new(files, entries, extracted)
 }
 
-#[derive(Clone, Copy, Eq, PartialEq)]
-pub struct Language(iso639_enum::Language);
+/// Reports files in the same directory whose names differ only by case or Unicode normalization,
+/// which are the same file on some filesystems.
+fn check_name_collisions(files: &BTreeSet<PathBuf>) {
+    let mut seen = HashMap::<(Option<&Path>, String), &PathBuf>::new();
+    for file in files {
+        // Names that aren't Unicode can't be normalized, and any exact duplicates were already
+        // merged by the set.
+        let Some(name) = file.file_name().and_then(OsStr::to_str) else {
+            continue;
+        };
+        let folded = name.nfc().collect::<String>().to_lowercase();
+        match seen.entry((file.parent(), folded)) {
+            Entry::Occupied(e) => eprintln!(
+                "{:?}",
+                Report::new(FileNameCollision {
+                    first: e.get().to_path_buf(),
+                    second: file.clone(),
+                }),
+            ),
+            Entry::Vacant(e) => {
+                e.insert(file);
+            }
+        }
+    }
+}
 
-impl<'de> Deserialize<'de> for Language {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
+fn read_meta_file(files: &BTreeSet<PathBuf>) -> Result<Arc<String>> {
+    let Some(meta_file) = files
+        .iter()
+        .find(|f| f.file_name() == Some(OsStr::new("meta.toml")))
+    else {
+        return Err(miette!("meta.toml not found."));
+    };
+    fs::read_to_string(meta_file)
+        .map(|content| Arc::new(normalize(content.into()).into_owned()))
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Reading {} failed.", meta_file.display()))
+}
+
+/// Reports links in the meta file that aren't web addresses.
+fn check_meta_links(meta: &input::Meta, meta_file: &Arc<String>) {
+    let links = meta
+        .link
+        .iter()
+        .chain(meta.languages.values().filter_map(|l| l.link.as_ref()))
+        .chain(&meta.base_url)
+        .chain(meta.rsvp.as_ref().map(|rsvp| &rsvp.url));
+    for link in links {
+        if !is_web_address(link.as_ref()) {
+            eprintln!(
+                "{:?}",
+                Report::new(InvalidLink {
+                    link: link.as_ref().as_ref().to_owned(),
+                    src: NamedSource::new("meta.toml", meta_file.clone()),
+                    location: Some(link.span().into()),
+                }),
+            );
+        }
+    }
+}
+
+fn is_web_address(link: &str) -> bool {
+    url::Url::parse(link)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+}
+
+fn parse_meta(meta_file: &Arc<String>) -> Result<input::Meta<'_>> {
+    deprecated::check(deprecated::META, SourceMap::parse(meta_file), || {
+        NamedSource::new("meta.toml", meta_file.clone())
+    });
+    input::Meta::deserialize(toml::Deserializer::new(meta_file))
+        .map_err(|error| error::EventParseError {
+            src: NamedSource::new("meta.toml", meta_file.clone()),
+            location: error.span().map(|s| s.into()),
+            error,
+        })
+        .wrap_err("Parsing meta.toml failed.")
+}
+
+/// Writes text in NFC, so that the same text always has the same characters in the output. Files
+/// are normalized as they are read, and locations in diagnostics point into the normalized text.
+fn normalize(text: Cow<str>) -> Cow<str> {
+    if unicode_normalization::is_nfc(&text) {
+        text
+    } else {
+        Cow::Owned(text.nfc().collect())
+    }
+}
+
+fn read_event_files<'a>(files: &'a Files, errors: &Errors) -> Vec<EventFile<'a>> {
+    let mut event_files = Vec::new();
+    // meta.toml is read along with the event files for its defaults.
+    for (file, format) in files.iter().filter_map(|f| Some((f, EventFormat::of(f)?))) {
+        if errors.should_stop() {
+            break;
+        }
+        // The file stem is used as the event's id in the output and the state.
+        if EventFormat::id(file).and_then(OsStr::to_str).is_none() {
+            eprintln!(
+                "{:?}",
+                Report::new(NonUnicodeFileName {
+                    path: file.to_owned(),
+                })
+            );
+            continue;
+        }
+        let content = fs::read_to_string(file)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Reading {} failed.", file.display()))
+            .and_then(|content| {
+                let content = normalize(content.into()).into_owned();
+                if format == EventFormat::Toml {
+                    return Ok((content, None));
+                }
+                event_format::convert(format, file.to_string_lossy().into_owned(), content.clone())
+                    .map(|(toml, original)| (toml, Some(original)))
+                    .map_err(|error| {
+                        Report::new(EventConversionError {
+                            format: format.name(),
+                            reason: error.reason,
+                            src: NamedSource::new(file.to_string_lossy(), content),
+                            location: error.location,
+                        })
+                    })
+            });
+        match content {
+            Ok((content, original)) => {
+                event_files.push(EventFile {
+                    path: file,
+                    format,
+                    entry: None,
+                    content: Arc::new(content),
+                    original,
+                });
+            }
+            Err(error) => {
+                eprintln!("{error:?}");
+            }
+        };
+    }
+    // The events in meta.toml are read from its content.
+    let meta = event_files
+        .iter()
+        .find(|file| file.path.file_name() == Some(OsStr::new("meta.toml")))
+        .map(|file| (file.path, file.content.clone()));
+    if let Some((meta, content)) = meta {
+        event_files.extend(files.entries.iter().map(|(index, path)| EventFile {
+            path,
+            format: EventFormat::Toml,
+            entry: Some((meta, *index)),
+            content: content.clone(),
+            original: None,
+        }));
+    }
+    event_files
+}
+
+/// The name of the file with defaults for the events in its directory and its subdirectories.
+const DEFAULTS_FILE: &str = "_defaults.toml";
+
+/// Whether the file has defaults instead of an event, which includes meta.toml and its
+/// `[defaults]`.
+fn is_defaults_file(path: &Path) -> bool {
+    let name = path.file_name();
+    name == Some(OsStr::new(DEFAULTS_FILE)) || name == Some(OsStr::new("meta.toml"))
+}
+
+fn parse_events<'a>(event_files: &'a [EventFile<'a>], errors: &Errors) -> Vec<Event<'a>> {
+    let defaults = parse_defaults(event_files);
+    let mut input_events = Vec::with_capacity(event_files.len());
+    for file in event_files
+        .iter()
+        .filter(|file| !is_defaults_file(file.path))
     {
-        struct LanguageVisitor;
+        if errors.should_stop() {
+            break;
+        }
+        deprecated::check(deprecated::EVENT, file.source_map(), || file.into());
+        match parse_event(file, event_files, &defaults)
+            .wrap_err_with(|| format!("Parsing {} failed.", file.describe()))
+        {
+            Ok(event) => input_events.push(event),
+            Err(error) => {
+                eprintln!("{error:?}");
+            }
+        }
+    }
+    input_events
+}
 
-        impl<'de> Visitor<'de> for LanguageVisitor {
-            type Value = Language;
+/// Parses the defaults files, with the deepest directories first. The defaults of meta.toml
+/// apply to every event, after all of the defaults files.
+fn parse_defaults<'a>(
+    event_files: &'a [EventFile<'a>],
+) -> Vec<(&'a Path, &'a EventFile<'a>, input::Defaults<'a>)> {
+    let mut defaults = Vec::new();
+    for file in event_files
+        .iter()
+        .filter(|file| is_defaults_file(file.path))
+    {
+        let is_meta = file.path.file_name() == Some(OsStr::new("meta.toml"));
+        let deserializer = toml::Deserializer::new(&file.content);
+        let parsed = if is_meta {
+            input::MetaDefaults::deserialize(deserializer).map(|meta| meta.defaults)
+        } else {
+            input::Defaults::deserialize(deserializer).map(Some)
+        };
+        match parsed
+            .map_err(|error| Report::new(error::EventParseError::new(error, file)))
+            .wrap_err_with(|| format!("Parsing {} failed.", file.path.display()))
+        {
+            Ok(Some(parsed)) => {
+                let directory = match file.path.parent() {
+                    Some(directory) if !is_meta => directory,
+                    _ => Path::new(""),
+                };
+                defaults.push((directory, file, parsed));
+            }
+            Ok(None) => {}
+            Err(error) => eprintln!("{error:?}"),
+        }
+    }
+    defaults.sort_by_key(|(directory, _, _)| std::cmp::Reverse(directory.components().count()));
+    defaults
+}
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "an ISO 639-1 language code")
+/// Parses an event file, filling in what it inherits.
+fn parse_event<'a>(
+    file: &'a EventFile<'a>,
+    event_files: &'a [EventFile<'a>],
+    defaults: &[(&'a Path, &'a EventFile<'a>, input::Defaults<'a>)],
+) -> Result<Event<'a>> {
+    let mut input = file
+        .deserialize::<input::Event>()
+        .map_err(|error| Report::new(error::EventParseError::new(error, file)))?;
+    if let (Some(id), None) = (&input.id, file.entry) {
+        return Err(InvalidEventId {
+            problem: "can only be set in meta.toml, since an event file's id is its name",
+            src: file.into(),
+            location: Some(id.span().into()),
+        }
+        .into());
+    }
+    let base = apply_extends(&mut input, file, event_files, defaults)?;
+    let mut timezone_file = finish_event(&mut input, file, defaults)?;
+    if let Some(base) = base.as_ref().filter(|base| base.inherits("timezone")) {
+        timezone_file = base.timezone_file;
+    }
+    Ok(Event {
+        source: file,
+        timezone_file,
+        base,
+        event: input,
+    })
+}
+
+/// Applies the defaults, sessions, and end times. Returns the file the time zone is set in.
+fn finish_event<'a>(
+    input: &mut input::Event<'a>,
+    file: &'a EventFile<'a>,
+    defaults: &[(&'a Path, &'a EventFile<'a>, input::Defaults<'a>)],
+) -> Result<&'a EventFile<'a>> {
+    if input.start.is_none() {
+        let error = <toml::de::Error as serde::de::Error>::missing_field("start");
+        return Err(error::EventParseError::new(error, file).into());
+    }
+    let timezone_file = apply_defaults(input, file, defaults)?;
+    apply_sessions(input, file)?;
+    apply_end_times(input, file)?;
+    Ok(timezone_file)
+}
+
+/// Fills in the fields the event doesn't set from the event file it extends, as that event is
+/// compiled. Returns what the event inherits.
+fn apply_extends<'a>(
+    input: &mut input::Event<'a>,
+    file: &'a EventFile<'a>,
+    event_files: &'a [EventFile<'a>],
+    defaults: &[(&'a Path, &'a EventFile<'a>, input::Defaults<'a>)],
+) -> Result<Option<Base<'a>>> {
+    let Some(extends) = input.extends.take() else {
+        return Ok(None);
+    };
+    let directory = file.path.parent().unwrap_or(Path::new(""));
+    let path = normalize_path(&directory.join(extends.as_ref().as_ref()));
+    let Some(base_file) = event_files
+        .iter()
+        .find(|f| !is_defaults_file(f.path) && normalize_path(f.path) == path)
+    else {
+        return Err(ExtendsNotFound {
+            src: file.into(),
+            location: extends.span().into(),
+            path: extends.into_inner().into_owned(),
+        }
+        .into());
+    };
+    let mut base = base_file
+        .deserialize::<input::Event>()
+        .map_err(|error| Report::new(error::EventParseError::new(error, base_file)))
+        .wrap_err_with(|| format!("Parsing {} failed.", base_file.describe()))?;
+    if let Some(chained) = &base.extends {
+        return Err(ExtendsChain {
+            src: base_file.into(),
+            location: chained.span().into(),
+        }
+        .into());
+    }
+    let base_timezone_file = finish_event(&mut base, base_file, defaults)
+        .wrap_err_with(|| format!("Parsing {} failed.", base_file.describe()))?;
+
+    // The file already parsed as an event, so it is a table.
+    let keys = file.deserialize::<toml::Table>().unwrap_or_default();
+    let keys = inherit(input, base, |key| keys.contains_key(key));
+    Ok(Some(Base {
+        file: base_file,
+        timezone_file: base_timezone_file,
+        keys,
+    }))
+}
+
+/// The event file an event extends.
+struct Base<'a> {
+    file: &'a EventFile<'a>,
+    /// The file the time zone of the base is set in.
+    timezone_file: &'a EventFile<'a>,
+    /// The keys the event inherits, since it doesn't set them itself.
+    keys: Vec<&'static str>,
+}
+
+impl Base<'_> {
+    fn inherits(&self, key: &str) -> bool {
+        self.keys.contains(&key)
+    }
+}
+
+macro_rules! inherit {
+    ($($($key:literal)|+ => $($field:ident).+,)*) => {
+        /// The keys an event can inherit from the event it extends.
+        #[cfg(test)]
+        const INHERITABLE_KEYS: &[&str] = &[$($($key),+),*];
+
+        /// Moves the fields `set` says the event doesn't set from `base`. Returns the keys of the
+        /// fields it inherits.
+        fn inherit<'a>(
+            input: &mut input::Event<'a>,
+            base: input::Event<'a>,
+            set: impl Fn(&str) -> bool,
+        ) -> Vec<&'static str> {
+            let mut inherited = Vec::new();
+            $(
+                if !($(set($key))||+) {
+                    input.$($field).+ = base.$($field).+;
+                    inherited.extend([$($key),+]);
+                }
+            )*
+            inherited
+        }
+    };
+}
+
+// The base's end times and sessions are already turned into durations and days.
+inherit! {
+    "timezone" => timezone,
+    "start" => start,
+    "duration" | "end" => duration,
+    "start_date" => start_date,
+    "end_date" => end_date,
+    "every" => every,
+    "anchor" => anchor,
+    "monthly" => monthly,
+    "dates" => dates,
+    "platforms" => platforms,
+    "name" => info.name,
+    "description" => info.description,
+    "tags" => info.tags,
+    "web" => info.web,
+    "poster" => info.poster,
+    "poster_credit" => info.poster_credit,
+    "poster_license" => info.poster_license,
+    "color" => info.color,
+    "hashtag" => info.hashtag,
+    "twitter" => info.twitter,
+    "bluesky" => info.bluesky,
+    "misskey" => info.misskey,
+    "youtube" => info.youtube,
+    "twitch" => info.twitch,
+    "group" => info.group,
+    "discord" => info.discord,
+    "links" => info.links,
+    "weeks" => info.weeks,
+    "world" => info.world,
+    "join" => info.join,
+    "organizers" => info.organizers,
+    "capacity" => info.capacity,
+    "instance_type" => info.instance_type,
+    "join_via" => info.join_via,
+    "open_before" => info.open_before,
+    "adult" => info.adult,
+    "featured" => featured,
+    "series" => series,
+    "related" => related,
+    "confirmed" => confirmed,
+    "tentative" => tentative,
+    "canceled" => canceled,
+    "cancel_on_holidays" => cancel_on_holidays,
+    "skipped" => skipped,
+    "hiatus" => hiatus,
+    "canceled_reasons" => canceled_reasons,
+    "overrides" => overrides,
+    "notes" => notes,
+    "days" | "sessions" => days,
+    "languages" | "lang" => languages,
+    "unstable" => unstable,
+}
+
+/// Fails if the output directory would be read as input.
+fn check_output_location(inputs: &[PathBuf], output: &Path, filter: &FileFilter) -> Result<()> {
+    let output = resolve_path(output);
+    for input in inputs.iter().filter(|input| input.is_dir()) {
+        let input = resolve_path(input);
+        if output
+            .strip_prefix(&input)
+            .is_ok_and(|relative| filter.enters(relative))
+        {
+            return Err(OutputInInput { output, input }.into());
+        }
+    }
+    Ok(())
+}
+
+/// `path` as an absolute path, with symbolic links resolved as far as it exists.
+fn resolve_path(path: &Path) -> PathBuf {
+    let path = normalize_path(&std::path::absolute(path).unwrap_or_else(|_| path.to_owned()));
+    let mut missing = Vec::new();
+    for existing in path.ancestors() {
+        // canonicalize returns `\\?\` paths on Windows. dunce only keeps the prefix when a path is
+        // too long to be written without it, so diagnostics show paths in the usual form.
+        if let Ok(resolved) = dunce::canonicalize(existing) {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |path, name| path.join(name));
+        }
+        missing.extend(existing.file_name());
+    }
+    path
+}
+
+/// Resolves `.` and `..` in `path` without touching the file system.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
             }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
 
-            fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
-            where
-                E: serde::de::Error,
-            {
-                iso639_enum::Language::from_iso639_1(v)
-                    .map(Language)
-                    .map_err(E::custom)
+/// Fills in the fields the event doesn't set from the defaults files above it, nearest first.
+/// Returns the file the time zone is set in.
+fn apply_defaults<'a>(
+    input: &mut input::Event<'a>,
+    file: &'a EventFile<'a>,
+    defaults: &[(&'a Path, &'a EventFile<'a>, input::Defaults<'a>)],
+) -> Result<&'a EventFile<'a>> {
+    let mut timezone_file = file;
+    let applicable = defaults
+        .iter()
+        .filter(|(directory, _, _)| file.path.starts_with(directory));
+    for (_, defaults_file, defaults) in applicable {
+        if input.timezone.is_none() && defaults.timezone.is_some() {
+            input.timezone = defaults.timezone.clone();
+            timezone_file = defaults_file;
+        }
+        if input.platforms.is_empty() {
+            input.platforms = defaults.platforms.clone().unwrap_or_default();
+        }
+        // An end time is also a duration.
+        if input.duration.is_none() && input.end.is_none() {
+            input.duration = defaults.duration;
+        }
+        if input.info.join.is_empty() {
+            input.info.join = defaults.join.clone();
+        }
+        if input.info.world.is_none() {
+            input.info.world = defaults.world.clone().map(input::Worlds::One);
+        }
+        let weekly = input.every.is_none() && input.monthly.is_none() && input.dates.is_none();
+        if input.info.weeks.is_none() && weekly {
+            input.info.weeks = defaults.weeks.clone();
+        }
+    }
+    if input.platforms.is_empty() {
+        input.platforms = input::default_platforms();
+    }
+    if input.timezone.is_none() {
+        return Err(MissingTimeZoneField { src: file.into() }.into());
+    }
+    Ok(timezone_file)
+}
+
+/// Moves `[[sessions]]` into `days`, so the rest of the compiler only sees one form.
+fn apply_sessions(input: &mut input::Event, file: &EventFile) -> Result<()> {
+    let sessions = std::mem::take(&mut input.sessions);
+    let Some(first) = sessions.first() else {
+        return Ok(());
+    };
+    if !input.days.is_daily() {
+        return Err(SessionsWithDays {
+            src: file.into(),
+            location: first.day.span().into(),
+        }
+        .into());
+    }
+
+    let mut spans = BTreeMap::new();
+    for session in &sessions {
+        let weekday = session.day.as_ref().weekday();
+        if let Some(first) = spans.insert(weekday.num_days_from_monday(), session.day.span()) {
+            return Err(DuplicateSession {
+                src: file.into(),
+                first: first.into(),
+                location: session.day.span().into(),
+            }
+            .into());
+        }
+    }
+    input.days = input::EventDays {
+        monday: None,
+        tuesday: None,
+        wednesday: None,
+        thursday: None,
+        friday: None,
+        saturday: None,
+        sunday: None,
+    };
+    for session in sessions {
+        let weekday = session.day.as_ref().weekday();
+        *input.days.get_mut(weekday) = Some(session.into_day());
+    }
+    Ok(())
+}
+
+/// Turns `end` times into durations, so the rest of the compiler only deals with durations.
+fn apply_end_times(input: &mut input::Event, file: &EventFile) -> Result<()> {
+    let mut spans = None;
+    let mut span = |path: &[&str]| {
+        spans
+            .get_or_insert_with(|| file.source_map())
+            .as_ref()
+            .and_then(|s| s.span(path))
+    };
+    match (input.duration, input.end.take()) {
+        (Some(_), Some(_)) => {
+            return Err(DurationWithEnd {
+                src: file.into(),
+                location: span(&["end"]),
+            }
+            .into())
+        }
+        (None, None) => {
+            return Err(MissingDuration {
+                src: file.into(),
+                location: span(&["start"]),
             }
+            .into())
+        }
+        (None, Some(end)) => {
+            input.duration = Some(input::Time(input::duration_until(input.start().0, end.0)))
         }
+        (Some(_), None) => {}
+    }
 
-        deserializer.deserialize_str(LanguageVisitor)
+    let event_start = input.start();
+    let mut days = vec![(vec!["days"], &mut input.days)];
+    let mut starts = [None; 7];
+    for (weekday, day) in days[0].1.iter() {
+        starts[weekday.num_days_from_monday() as usize] = day.start;
+    }
+    for (language_id, language) in &mut input.languages {
+        days.push((vec!["languages", language_id.code()], &mut language.days));
+    }
+    for (path, days) in days {
+        for (index, base_start) in starts.into_iter().enumerate() {
+            let weekday = chrono::Weekday::try_from(index as u8).unwrap();
+            let Some(day) = days.get_mut(weekday).as_mut() else {
+                continue;
+            };
+            match (day.duration, day.end.take()) {
+                (Some(_), Some(_)) => {
+                    let mut path = path.clone();
+                    path.extend([input::day_key(weekday), "end"]);
+                    return Err(DurationWithEnd {
+                        src: file.into(),
+                        location: span(&path),
+                    }
+                    .into());
+                }
+                (None, Some(end)) => {
+                    let start = day.start.or(base_start).unwrap_or(event_start);
+                    day.duration = Some(input::Time(input::duration_until(start.0, end.0)));
+                }
+                _ => {}
+            }
+        }
     }
+    Ok(())
 }
 
-impl Ord for Language {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.0
-            .iso639_1()
-            .cmp(&other.0.iso639_1())
-            .then_with(|| (self.0 as usize).cmp(&(other.0 as usize)))
+fn load_state(output_path: &Path) -> miette::Result<State> {
+    let state_path = output_path.join("state.json");
+    let state = match fs::read(&state_path) {
+        Ok(state) => state,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("Initializing new state");
+            return Ok(Default::default());
+        }
+        Err(e) => {
+            return Err(e)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Could not read {}", state_path.display()))
+        }
+    };
+    match serde_json::from_slice(&state) {
+        Ok(state) => Ok(state),
+        Err(e) => Err(StateParseError::new(e, &output_path.to_string_lossy(), state).into()),
     }
 }
 
-impl PartialOrd for Language {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+fn safely_save(
+    output_path: &Path,
+    name: impl AsRef<Path>,
+    retry: &Retry,
+    save: impl FnOnce(&mut BufWriter<&mut NamedTempFile>) -> miette::Result<()>,
+) -> miette::Result<()> {
+    let save_path = output_path.join(name);
+    retry
+        .run(&save_path, || {
+            tempfile::Builder::new().tempfile_in(output_path)
+        })
+        .into_diagnostic()
+        .and_then(|mut t| {
+            {
+                let mut t = BufWriter::new(&mut t);
+                save(&mut t)?;
+                t.flush().into_diagnostic()?;
+            }
+            let mut t = Some(t);
+            retry
+                .run(&save_path, || match t.take().unwrap().persist(&save_path) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        t = Some(e.file);
+                        Err(e.error)
+                    }
+                })
+                .into_diagnostic()
+        })
+        .wrap_err_with(|| format!("Could not save {}", save_path.display()))
+}
+
+struct Handler {
+    inner: MietteHandler,
+    errors: Errors,
+    /// Counts warnings as errors.
+    strict: bool,
+    /// Receives a copy of every diagnostic as JSON when `--error-format json` is used with
+    /// `--error-file`.
+    json: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    /// Reports diagnostics as JSON instead of the human readable reports.
+    json_on_stderr: bool,
+}
+
+impl ReportHandler for Handler {
+    fn debug(&self, error: &dyn Diagnostic, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let severity = error.severity().unwrap_or(miette::Severity::Error);
+        if severity == Severity::Error || (self.strict && severity == Severity::Warning) {
+            self.errors.add();
+        }
+        // Locations in converted event files are shown in the files as they are written.
+        let error = event_format::Relocated::new(error);
+        if let Some(json) = &self.json {
+            let mut writer = json.lock().unwrap();
+            if let Err(error) = report::write_json(&error, &mut **writer) {
+                eprintln!("Could not write the JSON diagnostic: {error}");
+            }
+        }
+        if self.json_on_stderr {
+            let mut line = Vec::new();
+            report::write_json(&error, &mut line).map_err(|_| core::fmt::Error)?;
+            return f.write_str(String::from_utf8_lossy(&line).trim_end());
+        }
+        self.inner.debug(&error, f)
     }
 }
 
-impl Serialize for Language {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(self.0.iso639_1().unwrap())
+pub struct EventFile<'a> {
+    path: &'a Path,
+    /// The format the file is written in. `content` is always TOML, converted from the file if it
+    /// is written in another format.
+    format: EventFormat,
+    /// For an event in meta.toml, the meta file and the event's position in its `[[event]]`
+    /// tables. `path` is then where the event would be as a file of its own, and `content` is the
+    /// whole meta file.
+    entry: Option<(&'a Path, usize)>,
+    content: Arc<String>,
+    /// The file as it is written, if it was converted to TOML.
+    original: Option<Arc<String>>,
+}
+
+impl EventFile<'_> {
+    /// Deserializes the event, which is only part of the file for an event in meta.toml.
+    fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T, toml::de::Error> {
+        match self.entry {
+            None => T::deserialize(toml::Deserializer::new(&self.content)),
+            Some((_, index)) => combined::deserialize(&self.content, index),
+        }
+    }
+
+    /// The locations of the event's keys.
+    fn source_map(&self) -> Option<SourceMap> {
+        let spans = SourceMap::parse(&self.content)?;
+        match self.entry {
+            None => Some(spans),
+            Some((_, index)) => spans.entry(combined::KEY, index),
+        }
+    }
+
+    /// Whether the file can be edited in place, which only TOML files of their own can.
+    fn is_editable(&self) -> bool {
+        self.format == EventFormat::Toml && self.entry.is_none()
+    }
+
+    /// The file for messages, which is meta.toml for the events in it.
+    fn describe(&self) -> String {
+        match self.entry {
+            None => self.path.display().to_string(),
+            Some((meta, _)) => format!("{} in {}", self.path.display(), meta.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl<'a> EventFile<'a> {
+    /// A TOML event file that is only in memory.
+    fn toml(path: &'a Path, content: &str) -> Self {
+        EventFile {
+            path,
+            format: EventFormat::Toml,
+            entry: None,
+            content: Arc::new(content.to_owned()),
+            original: None,
+        }
+    }
+
+    /// Parses the event in the file on its own, without any defaults files.
+    fn parse(&'a self) -> Event<'a> {
+        parse_event(self, std::slice::from_ref(self), &[]).unwrap()
+    }
+}
+
+pub struct Event<'a> {
+    source: &'a EventFile<'a>,
+    /// The file the time zone is set in, which is a defaults file if the event doesn't set one.
+    timezone_file: &'a EventFile<'a>,
+    /// The event file this one extends, if it does.
+    base: Option<Base<'a>>,
+    event: input::Event<'a>,
+}
+
+impl<'a> Event<'a> {
+    /// The file `key` is set in, which is the file the event extends if it inherits the key. The
+    /// locations in the value are in this file.
+    fn file_of(&self, key: &str) -> &'a EventFile<'a> {
+        match &self.base {
+            Some(base) if base.inherits(key) => base.file,
+            _ => self.source,
+        }
+    }
+
+    /// Whether the event inherits `key` from the event it extends.
+    fn inherits(&self, key: &str) -> bool {
+        self.base.as_ref().is_some_and(|base| base.inherits(key))
+    }
+
+    pub fn id(&self) -> Cow<'a, str> {
+        EventFormat::id(self.source.path).unwrap().to_string_lossy()
+    }
+
+    /// Where the poster found by the file name would be with `extension`, like `party.webp` next
+    /// to `party.toml`.
+    pub fn poster_path(&self, extension: &str) -> PathBuf {
+        self.source
+            .path
+            .with_file_name(format!("{}.{extension}", self.id()))
+    }
+
+    pub fn name(&self) -> Cow<'a, str> {
+        // File names aren't read like the content, and some filesystems decompose them.
+        self.event
+            .info
+            .name
+            .clone()
+            .unwrap_or_else(|| normalize(self.id()))
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Pc,
+    Quest,
+    #[serde(alias = "Android")]
+    Android,
+    #[serde(alias = "iOS", alias = "iphone")]
+    Ios,
+}
+
+impl JsonSchema for Platform {
+    fn schema_name() -> Cow<'static, str> {
+        "Platform".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut names = vec!["pc", "quest", "android", "ios"];
+        // The aliases above, which are read but never written.
+        if generator.contract().is_deserialize() {
+            names.extend(["Android", "iOS", "iphone"]);
+        }
+        json_schema!({
+            "type": "string",
+            "enum": names,
+        })
+    }
+}
+
+/// Who can join the event's instance.
+#[derive(Clone, Copy, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum InstanceType {
+    #[serde(rename = "public")]
+    Public,
+    #[serde(rename = "group")]
+    Group,
+    #[serde(rename = "group+")]
+    GroupPlus,
+    #[serde(rename = "friends+")]
+    FriendsPlus,
+    #[serde(rename = "invite")]
+    Invite,
+}
+
+/// How people get into the event, so calendars can show the matching instructions.
+#[derive(Clone, Copy, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinVia {
+    /// Joining the VRChat group, which is set by `group`.
+    Group,
+    /// Sending a friend request to one of the people in `join`.
+    FriendRequest,
+    /// Opening a public instance of the `world`.
+    OpenInstance,
+    /// Joining the Discord server, which is set by `discord`.
+    Discord,
+}
+
+impl JoinVia {
+    pub fn name(self) -> &'static str {
+        match self {
+            JoinVia::Group => "group",
+            JoinVia::FriendRequest => "friend_request",
+            JoinVia::OpenInstance => "open_instance",
+            JoinVia::Discord => "discord",
+        }
+    }
+
+    /// The field with what people need to join this way.
+    pub fn field(self) -> &'static str {
+        match self {
+            JoinVia::Group => "group",
+            JoinVia::FriendRequest => "join",
+            JoinVia::OpenInstance => "world",
+            JoinVia::Discord => "discord",
+        }
+    }
+
+    fn is_set(self, info: &input::EventInfo) -> bool {
+        match self {
+            JoinVia::Group => info.group.is_some(),
+            JoinVia::FriendRequest => !info.join.is_empty(),
+            JoinVia::OpenInstance => info.world.is_some(),
+            JoinVia::Discord => info.discord.is_some(),
+        }
+    }
+}
+
+impl Platform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Pc => "pc",
+            Platform::Quest => "quest",
+            Platform::Android => "android",
+            Platform::Ios => "ios",
+        }
+    }
+}
+
+/// A language, as an ISO 639-1 or ISO 639-3 code, or a BCP 47 tag starting with one of them like
+/// `zh-Hant` or `pt-BR`.
+///
+/// Tags are kept in their canonical form, so a language is always written the same way in the
+/// output: the shortest code for the language, then the script in title case, the region in
+/// upper case, and anything else in lower case. Ordering is by that form.
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Language(Arc<str>);
+
+impl Language {
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+
+    /// Reads a code or tag, or returns why it isn't one.
+    fn parse(tag: &str) -> std::result::Result<Self, String> {
+        let mut subtags = tag.split('-');
+        let primary = subtags.next().unwrap_or_default().to_ascii_lowercase();
+        let primary = match primary.len() {
+            2 => iso639_enum::Language::from_iso639_1(&primary)
+                .ok()
+                .map(|_| primary),
+            // ISO 639-3 keeps adding languages, like Toki Pona in 2022, so codes that aren't in the
+            // table are accepted as long as they could be one.
+            3 => match iso639_enum::Language::from_iso639_3(&primary) {
+                Ok(language) => Some(language.iso639_1().unwrap_or(&primary).to_owned()),
+                Err(_) => primary
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase())
+                    .then_some(primary),
+            },
+            _ => None,
+        }
+        .ok_or_else(|| format!("{tag:?} does not start with a known language code"))?;
+
+        let mut canonical = primary;
+        // Subtags after an extension or private use singleton, like `x`, are in lower case.
+        let mut extension = false;
+        for subtag in subtags {
+            if subtag.is_empty()
+                || subtag.len() > 8
+                || !subtag.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(format!("{tag:?} is not a BCP 47 language tag"));
+            }
+            let alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            canonical.push('-');
+            match subtag.len() {
+                1 => {
+                    extension = true;
+                    canonical.push_str(&subtag.to_ascii_lowercase());
+                }
+                2 if alphabetic && !extension => canonical.push_str(&subtag.to_ascii_uppercase()),
+                4 if alphabetic && !extension => {
+                    canonical.push_str(&subtag[..1].to_ascii_uppercase());
+                    canonical.push_str(&subtag[1..].to_ascii_lowercase());
+                }
+                _ => canonical.push_str(&subtag.to_ascii_lowercase()),
+            }
+        }
+        // The primary language is longer, so a last subtag of one letter is a singleton.
+        let last = canonical.rsplit('-').next().unwrap_or_default();
+        if last.len() == 1 {
+            return Err(format!(
+                "{tag:?} ends with {last:?}, which needs subtags after it"
+            ));
+        }
+        Ok(Language(canonical.into()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct LanguageVisitor;
+
+        impl<'de> Visitor<'de> for LanguageVisitor {
+            type Value = Language;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "an ISO 639 language code or a BCP 47 tag")
+            }
+
+            fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Language::parse(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(LanguageVisitor)
+    }
+}
+
+impl Serialize for Language {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl JsonSchema for Language {
+    fn schema_name() -> Cow<'static, str> {
+        "Language".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A BCP 47 language tag, like `ja` or `zh-Hant`.",
+        })
+    }
+}
+
+/// Replaces the ids of related events with their indexes in the output.
+fn resolve_related(sources: &[&Event], output_events: &mut [output::Event]) {
+    let indexes: HashMap<_, _> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, event)| (event.id(), index))
+        .collect();
+    for (event, output_event) in sources.iter().zip(output_events) {
+        for id in &event.event.related {
+            match indexes.get(id.as_ref()) {
+                Some(&index) => output_event.related.push(index),
+                None => eprintln!(
+                    "{:?}",
+                    Report::new(UnknownRelatedEvent {
+                        id: id.as_ref().as_ref().to_owned(),
+                        src: event.file_of("related").into(),
+                        location: id.span().into(),
+                    })
+                ),
+            }
+        }
+    }
+}
+
+fn prepare_event<'a, 'b>(
+    event: &'a Event<'a>,
+    files: &'b BTreeSet<PathBuf>,
+    zones: &'b BTreeMap<String, Zone>,
+    now: DateTime<Utc>,
+    grace: chrono::Duration,
+    posters: &'b mut Posters,
+    rsvps: Option<&BTreeMap<NaiveDate, u16>>,
+) -> Result<output::Event<'a>> {
+    if !zones.contains_key(event.event.timezone().as_ref().as_ref()) {
+        return Err(MissingTimeZone::new(event).into());
+    }
+    // The days off are canceled in the output by `cancel_days_off`, with the grace period.
+    let days_off = resolved::DaysOff::default();
+    let resolved = resolved::Event::resolve(event, &days_off)?;
+    let tz = resolved.timezone;
+
+    let poster = event
+        .event
+        .info
+        .poster
+        .as_deref()
+        .map(Path::new)
+        .map(Cow::Borrowed)
+        .or_else(|| guess_poster(event, files).map(Cow::Owned));
+    let poster = poster.and_then(|p| posters.try_load(p));
+
+    let name = event.name();
+
+    let today = now.with_timezone(&tz).date_naive();
+    check_note_expiry(event.file_of("days"), &event.event.days);
+    for language in event.event.languages.values() {
+        check_note_expiry(event.file_of("languages"), &language.days);
+    }
+    check_platform_overrides(event)?;
+    check_interval(event)?;
+    check_monthly(event)?;
+    check_hiatus(event)?;
+    check_canceled_reasons(event);
+
+    let confirmed = upcoming_dates(&resolved, &event.event.confirmed, now, grace, |date| {
+        Report::new(ConfirmedOutOfRange {
+            date: *date.as_ref(),
+            src: event.file_of("confirmed").into(),
+            location: date.span().into(),
+        })
+    });
+    let tentative = upcoming_dates(&resolved, &event.event.tentative, now, grace, |date| {
+        Report::new(TentativeOutOfRange {
+            date: *date.as_ref(),
+            src: event.file_of("tentative").into(),
+            location: date.span().into(),
+        })
+    });
+
+    let canceled = match &event.event.canceled {
+        input::DateSet::All(b) => output::DateSet::All(*b),
+        input::DateSet::Dates(canceled) => {
+            let mut future = Vec::with_capacity(canceled.len());
+            for date in canceled {
+                let Some(occurrence) = resolved.occurrence_on(*date.as_ref()) else {
+                    eprintln!(
+                        "{:?}",
+                        Report::new(CanceledOutOfRange {
+                            date: *date.as_ref(),
+                            src: event.file_of("canceled").into(),
+                            location: date.span().into(),
+                        }),
+                    );
+                    continue;
+                };
+                if now - grace < occurrence.end {
+                    future.push(*date.as_ref());
+                }
+            }
+            if future.is_empty() {
+                output::DateSet::All(false)
+            } else {
+                output::DateSet::Dates(future)
+            }
+        }
+    };
+    let skipped = match &event.event.skipped {
+        input::DateSet::All(b) => output::DateSet::All(*b),
+        input::DateSet::Dates(skipped) => {
+            let mut future = Vec::with_capacity(skipped.len());
+            for date in skipped {
+                if resolved.scheduled_on(*date.as_ref()).is_none() {
+                    eprintln!(
+                        "{:?}",
+                        Report::new(SkippedOutOfRange {
+                            date: *date.as_ref(),
+                            src: event.file_of("skipped").into(),
+                            location: date.span().into(),
+                        }),
+                    );
+                    continue;
+                }
+                // Kept until the occurrence would have ended, like the dates in `dates`.
+                let end = resolved.scheduled_end(*date.as_ref());
+                if end.is_some_and(|end| now - grace < end) {
+                    future.push(*date.as_ref());
+                }
+            }
+            if future.is_empty() {
+                output::DateSet::All(false)
+            } else {
+                output::DateSet::Dates(future)
+            }
+        }
+    };
+    let dates = event.event.dates.as_ref().map(|dates| {
+        let mut future = Vec::with_capacity(dates.len());
+        for date in dates {
+            // Dates that are skipped or on a break are still part of the schedule.
+            if resolved.scheduled_on(*date.as_ref()).is_none() {
+                eprintln!(
+                    "{:?}",
+                    Report::new(DateOutOfRange {
+                        date: *date.as_ref(),
+                        src: event.file_of("dates").into(),
+                        location: date.span().into(),
+                    }),
+                );
+                continue;
+            }
+            let end = resolved.scheduled_end(*date.as_ref());
+            if end.is_some_and(|end| now - grace < end) {
+                future.push(*date.as_ref());
+            }
+        }
+        future.sort();
+        future.dedup();
+        future
+    });
+
+    let mut overrides = BTreeMap::new();
+    let mut spans = None;
+    for (&date, change) in &event.event.overrides {
+        let Some(occurrence) = resolved.occurrence_on(date) else {
+            let spans = spans.get_or_insert_with(|| event.source.source_map());
+            eprintln!(
+                "{:?}",
+                Report::new(OverrideOutOfRange {
+                    date,
+                    src: event.source.into(),
+                    location: spans
+                        .as_ref()
+                        .and_then(|s| s.span(&["overrides".to_owned(), date.to_string()])),
+                }),
+            );
+            continue;
+        };
+        if now - grace < occurrence.end {
+            overrides.insert(
+                date,
+                output::Override {
+                    name: change.name.as_deref(),
+                    start: change
+                        .start
+                        .map(|start| (start.0 - NaiveTime::default()).num_minutes() as i32),
+                    duration: change
+                        .duration
+                        .map(|duration| duration.0.num_minutes() as i32),
+                    world: change.world.as_ref(),
+                    poster: change
+                        .poster
+                        .as_deref()
+                        .and_then(|p| posters.try_load(Cow::Borrowed(Path::new(p))))
+                        .and_then(|p| posters.try_get_output(&p)),
+                    poster_credit: change.poster_credit.as_deref(),
+                    poster_license: change.poster_license.as_deref(),
+                },
+            );
+        }
+    }
+
+    let rsvps = rsvps
+        .into_iter()
+        .flatten()
+        .filter(|(&date, _)| {
+            resolved
+                .occurrence_on(date)
+                .is_some_and(|occurrence| now - grace < occurrence.end)
+        })
+        .map(|(&date, &count)| (date, count))
+        .collect();
+
+    // Reasons for past cancellations are dropped along with the dates.
+    let canceled_reasons = |reasons: &'a BTreeMap<NaiveDate, Cow<'a, str>>| {
+        let output::DateSet::Dates(dates) = &canceled else {
+            return BTreeMap::new();
+        };
+        reasons
+            .iter()
+            .filter(|(date, _)| dates.contains(date))
+            .map(|(date, reason)| (*date, reason.as_ref()))
+            .collect()
+    };
+
+    // Shown until the last day of the break.
+    let hiatus = event
+        .event
+        .hiatus
+        .as_ref()
+        .map(|hiatus| hiatus.as_ref())
+        .filter(|hiatus| today <= hiatus.until);
+
+    // Notes for occurrences that have passed are dropped.
+    let mut occurrence_notes = |path: &[&str], notes: &'a BTreeMap<NaiveDate, Cow<'a, str>>| {
+        let mut upcoming = BTreeMap::new();
+        for (&date, note) in notes {
+            let Some(occurrence) = resolved.occurrence_on(date) else {
+                let spans = spans.get_or_insert_with(|| event.source.source_map());
+                let mut path: Vec<_> = path.iter().map(|key| key.to_string()).collect();
+                path.extend(["notes".to_owned(), date.to_string()]);
+                eprintln!(
+                    "{:?}",
+                    Report::new(NoteOutOfRange {
+                        date,
+                        src: event.source.into(),
+                        location: spans.as_ref().and_then(|s| s.span(&path)),
+                    }),
+                );
+                continue;
+            };
+            if now - grace < occurrence.end {
+                upcoming.insert(date, note.as_ref());
+            }
+        }
+        upcoming
+    };
+    let notes = occurrence_notes(&[], &event.event.notes);
+
+    let mut languages = BTreeMap::new();
+    for (language_id, language) in &event.event.languages {
+        languages.insert(
+            language_id.clone(),
+            output::EventLanguage {
+                name: language.info.name.as_deref(),
+                info: convert_event_info(&language.info, posters),
+                canceled_reasons: canceled_reasons(&language.canceled_reasons),
+                hiatus_reason: hiatus.and(language.hiatus_reason.as_deref()),
+                notes: occurrence_notes(&["languages", language_id.code()], &language.notes),
+                days: convert_event_days(&language.days, today, posters),
+            },
+        );
+    }
+
+    Ok(output::Event {
+        name,
+        start_date: event
+            .event
+            .start_date
+            .map(|d| {
+                d.and_time(NaiveTime::MIN)
+                    .and_local_timezone(tz)
+                    .earliest()
+                    .ok_or_else(|| miette!("Midnight of start date does not exist"))
+                    .map(|t| t.timestamp())
+            })
+            .transpose()?,
+        end_date: event
+            .event
+            .end_date
+            .map(|d| {
+                d.checked_add_days(Days::new(1))
+                    .and_then(|d| d.and_time(NaiveTime::MIN).and_local_timezone(tz).earliest())
+                    .ok_or_else(|| miette!("Midnight of day after end date does not exist"))
+                    .map(|t| t.timestamp())
+            })
+            .transpose()?,
+        every: event.event.every.as_ref().map(|every| every.as_ref().get()),
+        anchor: event.event.anchor.as_ref().map(|anchor| *anchor.as_ref()),
+        monthly: event.event.monthly.as_ref().map(|day| day.as_ref().get()),
+        dates,
+        info: output::EventInfo {
+            poster: poster.as_ref().and_then(|p| posters.try_get_output(p)),
+            ..convert_event_info(&event.event.info, posters)
+        },
+        timezone: event.event.timezone().as_ref().as_ref(),
+        start: (event.event.start().0 - NaiveTime::default()).num_minutes() as i32,
+        duration: event.event.duration().num_minutes() as i32,
+        platforms: &event.event.platforms,
+        days: rotate_worlds(
+            convert_event_days(&event.event.days, today, posters),
+            &event.event.info,
+        ),
+        languages,
+        confirmed,
+        tentative,
+        skipped,
+        hiatus: hiatus.map(|hiatus| output::Hiatus {
+            from: hiatus.from,
+            until: hiatus.until,
+            reason: hiatus.reason.as_deref(),
+        }),
+        overrides,
+        notes,
+        rsvps,
+        featured: event.event.featured,
+        series: event
+            .event
+            .series
+            .as_ref()
+            .map(|series| series.as_ref().as_ref()),
+        canceled_reasons: canceled_reasons(&event.event.canceled_reasons),
+        canceled,
+        related: Vec::new(),
+        unstable: event
+            .event
+            .unstable
+            .as_ref()
+            .map(|unstable| toml_to_json(&toml::Value::Table(unstable.as_ref().clone()))),
+    })
+}
+
+/// Loads the holidays the meta file refers to, which are empty if it doesn't or they can't be
+/// loaded.
+fn load_holidays(
+    meta: &input::Meta,
+    meta_file: &Arc<String>,
+    directory: &Path,
+    now: DateTime<Utc>,
+) -> Vec<NaiveDate> {
+    let Some(source) = &meta.holidays else {
+        return Vec::new();
+    };
+    let years = now.year()..=now.year() + 1;
+    match holidays::load(source.as_ref(), directory, years) {
+        Ok(holidays) => holidays.into_iter().collect(),
+        Err(reason) => {
+            eprintln!(
+                "{:?}",
+                Report::new(HolidaysUnavailable {
+                    reason,
+                    src: NamedSource::new("meta.toml", meta_file.clone()),
+                    location: source.span().into(),
+                }),
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Reads the blackout dates and holidays of the meta file among `files`, for the commands that
+/// don't use the rest of it. Without a meta file, there are none.
+fn read_days_off(files: &BTreeSet<PathBuf>, now: DateTime<Utc>) -> resolved::DaysOff {
+    let Some(directory) = files
+        .iter()
+        .find(|f| f.file_name() == Some(OsStr::new("meta.toml")))
+        .map(|f| f.parent().unwrap_or(Path::new("")))
+    else {
+        return resolved::DaysOff::default();
+    };
+    let meta_file = match read_meta_file(files) {
+        Ok(meta_file) => meta_file,
+        Err(error) => {
+            eprintln!("{error:?}");
+            return resolved::DaysOff::default();
+        }
+    };
+    match parse_meta(&meta_file) {
+        Ok(meta) => resolved::DaysOff {
+            holidays: load_holidays(&meta, &meta_file, directory, now),
+            blackout: meta.blackout,
+        },
+        Err(error) => {
+            eprintln!("{error:?}");
+            resolved::DaysOff::default()
+        }
+    }
+}
+
+/// Reports events that are canceled on holidays when the meta file doesn't set any.
+fn check_holidays(event: &Event, meta: &input::Meta) {
+    if !event.event.cancel_on_holidays || meta.holidays.is_some() {
+        return;
+    }
+    let spans = event.source.source_map();
+    eprintln!(
+        "{:?}",
+        Report::new(HolidaysNotSet {
+            src: event.source.into(),
+            location: spans.and_then(|s| s.span(&["cancel_on_holidays"])),
+        }),
+    );
+}
+
+/// Cancels the occurrences on the blackout dates and the holidays of the meta file. Events that
+/// aren't held on a date are left as they are.
+fn cancel_days_off(
+    output_event: &mut output::Event,
+    event: &Event,
+    days_off: &resolved::DaysOff,
+    now: DateTime<Utc>,
+    grace: chrono::Duration,
+) {
+    // `prepare_event` already reported an event that can't be resolved.
+    let Ok(resolved) = resolved::Event::resolve(event, days_off) else {
+        return;
+    };
+    let held: Vec<_> = days_off
+        .of(&event.event)
+        .filter(|&date| {
+            resolved
+                .occurrence_on(date)
+                .is_some_and(|occurrence| now - grace < occurrence.end)
+        })
+        .collect();
+    if held.is_empty() {
+        return;
+    }
+    let mut canceled = match &mut output_event.canceled {
+        output::DateSet::All(true) => return,
+        output::DateSet::All(false) => Vec::new(),
+        output::DateSet::Dates(dates) => std::mem::take(dates),
+    };
+    canceled.extend(held);
+    canceled.sort();
+    canceled.dedup();
+    output_event.canceled = output::DateSet::Dates(canceled);
+}
+
+/// Converts a TOML value to JSON, with dates and times as strings.
+fn toml_to_json(value: &toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => s.as_str().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(datetime) => datetime.to_string().into(),
+        toml::Value::Array(values) => values.iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(key, value)| (key.clone(), toml_to_json(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
+
+/// Reports unstable fields unless the calendar opted in to them.
+fn check_unstable(event: &Event, allowed: bool) {
+    if let Some(unstable) = &event.event.unstable {
+        if !allowed {
+            eprintln!(
+                "{:?}",
+                Report::new(UnstableWithoutFlag {
+                    src: event.file_of("unstable").into(),
+                    location: unstable.span().into(),
+                }),
+            );
+        }
+    }
+}
+
+/// Reports names, descriptions and hashtags that are longer than the meta file allows.
+fn check_lengths(event: &Event, meta: &input::Meta) {
+    let limits = &meta.max_length;
+    if limits.name.is_none() && limits.description.is_none() && limits.hashtag.is_none() {
+        return;
+    }
+    let spans = event.source.source_map();
+    let check = |path: &[&str], field: &'static str, length: usize, max: Option<usize>| {
+        let Some(max) = max.filter(|&max| max < length) else {
+            return;
+        };
+        let mut path = path.to_vec();
+        path.push(field);
+        eprintln!(
+            "{:?}",
+            Report::new(TextTooLong {
+                event: event.id().into_owned(),
+                field,
+                length,
+                max,
+                src: event.source.into(),
+                location: spans.as_ref().and_then(|s| s.span(&path)),
+            }),
+        );
+    };
+    let length = |text: &str| text.chars().count();
+    // An event without a name is called by its file name, which has no location.
+    if event.event.info.name.is_none() {
+        check(&[], "name", length(&event.id()), limits.name);
+    }
+    for_each_info(&event.event, |path, info| {
+        if let Some(name) = &info.name {
+            check(path, "name", length(name), limits.name);
+        }
+        if let Some(description) = &info.description {
+            // Descriptions with broken markup are reported by check_descriptions.
+            let shown =
+                markup::parse(description).map_or(Cow::Borrowed(&**description), |d| d.text);
+            check(path, "description", length(&shown), limits.description);
+        }
+        if let Some(hashtag) = &info.hashtag {
+            check(path, "hashtag", length(hashtag), limits.hashtag);
+        }
+    });
+    for (date, change) in &event.event.overrides {
+        if let Some(name) = &change.name {
+            let date = date.to_string();
+            check(&["overrides", &date], "name", length(name), limits.name);
+        }
+    }
+}
+
+/// What kind of character `c` is, if it is one that the world doesn't show or that changes how the
+/// text around it is shown.
+fn invisible_kind(c: char) -> Option<&'static str> {
+    match c {
+        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => Some("a zero-width character"),
+        '\u{061C}'
+        | '\u{200E}'
+        | '\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2066}'..='\u{2069}' => Some("a bidi control character"),
+        c if c.is_control() => Some("a control character"),
+        _ => None,
+    }
+}
+
+/// Reports names and hashtags with characters that break the text in the world, or that could be
+/// used to make them look like something else.
+fn check_characters(event: &Event) {
+    let spans = OnceCell::new();
+    let check = |path: &[&str], field: &'static str, text: &str| {
+        let Some((kind, character)) = text.chars().find_map(|c| Some((invisible_kind(c)?, c)))
+        else {
+            return;
+        };
+        let mut path = path.to_vec();
+        path.push(field);
+        let spans = spans.get_or_init(|| event.source.source_map());
+        eprintln!(
+            "{:?}",
+            Report::new(InvisibleCharacter {
+                event: event.id().into_owned(),
+                field,
+                kind,
+                code: format!("U+{:04X}", u32::from(character)),
+                src: event.source.into(),
+                location: spans.as_ref().and_then(|s| s.span(&path)),
+            }),
+        );
+    };
+    // An event without a name is called by its file name, which has no location.
+    if event.event.info.name.is_none() {
+        check(&[], "name", &event.id());
+    }
+    for_each_info(&event.event, |path, info| {
+        if let Some(name) = &info.name {
+            check(path, "name", name);
+        }
+        if let Some(hashtag) = &info.hashtag {
+            check(path, "hashtag", hashtag);
+        }
+    });
+    for (date, change) in &event.event.overrides {
+        if let Some(name) = &change.name {
+            check(&["overrides", &date.to_string()], "name", name);
+        }
+    }
+}
+
+/// Reports tags that are not in the meta file's list of tags, if it has one.
+fn check_tags(event: &Event, meta: &input::Meta) {
+    if meta.tags.is_empty() {
+        return;
+    }
+    let spans = event.source.source_map();
+    for_each_info(&event.event, |path, info| {
+        for (index, tag) in info.tags.iter().enumerate() {
+            if meta.tags.contains(tag) {
+                continue;
+            }
+            let index = index.to_string();
+            let mut path = path.to_vec();
+            path.extend(["tags", &index]);
+            eprintln!(
+                "{:?}",
+                Report::new(UnknownTag {
+                    tag: tag.clone().into_owned(),
+                    known: meta.tags.join(", "),
+                    src: event.source.into(),
+                    location: spans.as_ref().and_then(|s| s.span(&path)),
+                }),
+            );
+        }
+    });
+}
+
+/// Calls `f` with the path and details of the event itself, and of each of its day and language
+/// sections.
+fn for_each_info<'e>(input: &'e input::Event, mut f: impl FnMut(&[&str], &'e input::EventInfo)) {
+    f(&[], &input.info);
+    for (weekday, day) in input.days.iter() {
+        f(&["days", input::day_key(weekday)], &day.info);
+    }
+    for (language_id, language) in &input.languages {
+        let code = language_id.code();
+        f(&["languages", code], &language.info);
+        for (weekday, day) in language.days.iter() {
+            f(&["languages", code, input::day_key(weekday)], &day.info);
+        }
+    }
+}
+
+/// Reports entries of `links` that aren't web addresses.
+fn check_links(event: &Event) {
+    let mut spans = None;
+    for_each_info(&event.event, |path, info| {
+        for (label, link) in &info.links.0 {
+            if is_web_address(link) {
+                continue;
+            }
+            let spans = spans.get_or_insert_with(|| event.source.source_map());
+            let mut path = path.to_vec();
+            path.extend(["links", label]);
+            eprintln!(
+                "{:?}",
+                Report::new(InvalidLink {
+                    link: link.clone().into_owned(),
+                    src: event.source.into(),
+                    location: spans.as_ref().and_then(|s| s.span(&path)),
+                }),
+            );
+        }
+    });
+}
+
+/// Reports descriptions whose markup can't be turned into styled text.
+fn check_descriptions(event: &Event) {
+    let mut spans = None;
+    for_each_info(&event.event, |path, info| {
+        let Some(Err(problem)) = info.description.as_deref().map(markup::parse) else {
+            return;
+        };
+        let spans = spans.get_or_insert_with(|| event.source.source_map());
+        let mut path = path.to_vec();
+        path.push("description");
+        eprintln!(
+            "{:?}",
+            Report::new(InvalidMarkup {
+                problem,
+                src: event.source.into(),
+                location: spans.as_ref().and_then(|s| s.span(&path)),
+            }),
+        );
+    });
+}
+
+/// Reports social accounts whose handles don't have the form the platform uses.
+fn check_accounts(event: &Event) {
+    let mut spans = None;
+    for_each_info(&event.event, |path, info| {
+        for service in Service::ALL {
+            let Some(handle) = service.handle(info) else {
+                continue;
+            };
+            if service.account(handle).is_some() {
+                continue;
+            }
+            let spans = spans.get_or_insert_with(|| event.source.source_map());
+            let mut path = path.to_vec();
+            path.push(service.key());
+            eprintln!(
+                "{:?}",
+                Report::new(InvalidAccount {
+                    service: service.name(),
+                    handle: handle.to_owned(),
+                    expected: service.expected(),
+                    src: event.source.into(),
+                    location: spans.as_ref().and_then(|s| s.span(&path)),
+                }),
+            );
+        }
+    });
+}
+
+/// Reports lists of worlds in day and language sections, which can only set one world.
+fn check_worlds(event: &Event) {
+    let mut spans = None;
+    for_each_info(&event.event, |path, info| {
+        if path.is_empty() || !matches!(info.world, Some(input::Worlds::Rotation(_))) {
+            return;
+        }
+        let spans = spans.get_or_insert_with(|| event.source.source_map());
+        let mut path = path.to_vec();
+        path.push("world");
+        eprintln!(
+            "{:?}",
+            Report::new(RotationInSection {
+                src: event.source.into(),
+                location: spans.as_ref().and_then(|s| s.span(&path)),
+            }),
+        );
+    });
+}
+
+/// Reports `join_via` in sections where neither the section nor the sections it overrides set the
+/// field that joining that way needs.
+fn check_join_via(event: &Event) {
+    let input = &event.event;
+    let mut spans = None;
+    let mut check = |path: &[&str], sections: &[&input::EventInfo]| {
+        let Some(join_via) = sections[0].join_via else {
+            return;
+        };
+        if sections.iter().any(|info| join_via.is_set(info)) {
+            return;
+        }
+        let spans = spans.get_or_insert_with(|| event.source.source_map());
+        let mut path = path.to_vec();
+        path.push("join_via");
+        eprintln!(
+            "{:?}",
+            Report::new(JoinViaWithoutField {
+                join_via: join_via.name(),
+                field: join_via.field(),
+                src: event.source.into(),
+                location: spans.as_ref().and_then(|s| s.span(&path)),
+            }),
+        );
+    };
+    check(&[], &[&input.info]);
+    for (weekday, day) in input.days.iter() {
+        check(
+            &["days", input::day_key(weekday)],
+            &[&day.info, &input.info],
+        );
+    }
+    for (language_id, language) in &input.languages {
+        let code = language_id.code();
+        check(&["languages", code], &[&language.info, &input.info]);
+        for (weekday, day) in language.days.iter() {
+            let mut sections = vec![&day.info, &language.info, &input.info];
+            sections.extend(
+                input
+                    .days
+                    .iter()
+                    .filter(|&(held, _)| held == weekday)
+                    .map(|(_, day)| &day.info),
+            );
+            check(&["languages", code, input::day_key(weekday)], &sections);
+        }
+    }
+}
+
+/// Warns about language sections with days the event isn't held on, which are usually copied
+/// from another event by mistake.
+fn check_language_days(event: &Event) {
+    let input = &event.event;
+    let mut spans = None;
+    for (language_id, language) in &input.languages {
+        for (weekday, _) in language.days.iter() {
+            if input.days.iter().any(|(held, _)| held == weekday) {
+                continue;
+            }
+            let spans = spans.get_or_insert_with(|| event.source.source_map());
+            let code = language_id.code();
+            let day = input::day_key(weekday);
+            let location = spans.as_ref().and_then(|s| {
+                s.span(&["languages", code, day])
+                    .or_else(|| s.span(&["lang", code, day]))
+            });
+            let days = spans.as_ref().and_then(|s| {
+                s.find(&["days"])
+                    .into_iter()
+                    .chain(s.find(&["sessions"]))
+                    .next()
+                    .flatten()
+            });
+            eprintln!(
+                "{:?}",
+                Report::new(LanguageDayNotHeld {
+                    language: code.to_owned(),
+                    day: day.to_owned(),
+                    src: event.source.into(),
+                    days,
+                    location,
+                }),
+            );
+        }
     }
 }
 
-impl Hash for Language {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        (self.0 as usize).hash(state);
+/// Drops the dates that have passed, and warns about dates when the event is not held.
+fn upcoming_dates(
+    resolved: &resolved::Event,
+    dates: &input::DateSet,
+    now: DateTime<Utc>,
+    grace: chrono::Duration,
+    out_of_range: impl Fn(&toml::Spanned<NaiveDate>) -> Report,
+) -> output::DateSet {
+    let dates = match dates {
+        input::DateSet::All(b) => return output::DateSet::All(*b),
+        input::DateSet::Dates(dates) => dates,
+    };
+    let mut future = Vec::with_capacity(dates.len());
+    for date in dates {
+        if resolved.scheduled_on(*date.as_ref()).is_none() {
+            eprintln!("{:?}", out_of_range(date));
+            continue;
+        }
+        // Kept while the occurrence is going, which can be into the next days.
+        let end = resolved.scheduled_end(*date.as_ref());
+        if end.is_some_and(|end| now - grace < end) {
+            future.push(*date.as_ref());
+        }
+    }
+    if future.is_empty() {
+        output::DateSet::All(false)
+    } else {
+        output::DateSet::Dates(future)
     }
 }
 
-fn prepare_event<'a, 'b>(
-    event: &'a Event<'a>,
-    files: &'b BTreeSet<PathBuf>,
-    zones: &'b BTreeMap<String, Zone>,
+/// Warns when the time zone data changed since the last compile, and returns the new state.
+fn check_tzdb(
+    state: &State,
+    zones: &BTreeMap<String, Zone>,
+    timezones: &BTreeMap<String, String>,
     now: DateTime<Utc>,
-    posters: &'b mut Posters,
-) -> Result<output::Event<'a>> {
-    if !zones.contains_key(event.event.timezone.as_ref().as_ref()) {
-        return Err(MissingTimeZone::new(event).into());
-    }
-    let Ok(tz) = Tz::from_str(event.event.timezone.as_ref().as_ref()) else {
-        return Err(MissingTimeZone::new(event).into());
+) -> state::Tzdb {
+    let tzdb = state::Tzdb {
+        sha256: time::checksum(),
+        until: time::limit(now).timestamp(),
+        zones: timezones
+            .values()
+            .filter_map(|name| Some((name.clone(), zones.get(name)?.offsets.clone())))
+            .collect(),
     };
-
-    let poster = event
-        .event
-        .info
-        .poster
-        .as_deref()
-        .map(Path::new)
-        .map(Cow::Borrowed)
-        .or_else(|| guess_poster(event, files).map(Cow::Owned));
-    let poster = poster.and_then(try_load_poster);
-
-    let name = event
-        .event
-        .info
-        .name
-        .as_deref()
-        .map(Cow::Borrowed)
-        .unwrap_or_else(|| event.source.path.file_stem().unwrap().to_string_lossy());
-
-    let mut languages = BTreeMap::new();
-    for (&language_id, language) in &event.event.languages {
-        languages.insert(
-            language_id,
-            output::EventLanguage {
-                name: language.info.name.as_deref(),
-                info: convert_event_info(&language.info, posters),
-                days: convert_event_days(&language.days, posters),
-            },
-        );
+    if let Some(previous) = &state.tzdb {
+        if previous.sha256 != tzdb.sha256 {
+            let changed = time::changed_zones(previous, zones, now)
+                .into_iter()
+                .filter(|name| tzdb.zones.contains_key(name))
+                .collect();
+            eprintln!("{:?}", Report::new(TzdbChanged { zones: changed }));
+        }
     }
+    tzdb
+}
 
-    let confirmed = match &event.event.confirmed {
-        input::DateSet::All(b) => output::DateSet::All(*b),
-        input::DateSet::Dates(confirmed) => {
-            let mut future = Vec::with_capacity(confirmed.len());
-            for date in confirmed {
-                let Some(time) = event.get_time_for_day(*date.as_ref(), tz, true)? else {
-                    eprintln!(
-                        "{:?}",
-                        Report::new(ConfirmedOutOfRange {
-                            date: *date.as_ref(),
-                            src: event.source.into(),
-                            location: date.span().into(),
-                        }),
-                    );
-                    continue;
-                };
-                if now < time {
-                    future.push(*date.as_ref());
-                }
-            }
-            if future.is_empty() {
-                output::DateSet::All(false)
-            } else {
-                output::DateSet::Dates(future)
-            }
-        }
+fn check_time_zone(
+    event: &Event,
+    meta: &input::Meta,
+    state: &State,
+    zones: &BTreeMap<String, Zone>,
+) {
+    let name = event.event.timezone().as_ref().as_ref();
+    // Linked zones such as US/Eastern and America/New_York have the same rules.
+    let same_zone = |other: &str| {
+        other == name || matches!((zones.get(name), zones.get(other)), (Some(a), Some(b)) if a == b)
     };
 
-    let canceled = match &event.event.canceled {
-        input::DateSet::All(b) => output::DateSet::All(*b),
-        input::DateSet::Dates(canceled) => {
-            let mut future = Vec::with_capacity(canceled.len());
-            for date in canceled {
-                let Some(time) = event.get_time_for_day(*date.as_ref(), tz, false)? else {
-                    eprintln!(
-                        "{:?}",
-                        Report::new(CanceledOutOfRange {
-                            date: *date.as_ref(),
-                            src: event.source.into(),
-                            location: date.span().into(),
-                        }),
-                    );
-                    continue;
-                };
-                if now < time {
-                    future.push(*date.as_ref());
-                }
-            }
-            if future.is_empty() {
-                output::DateSet::All(false)
-            } else {
-                output::DateSet::Dates(future)
-            }
+    let info = &event.event.info;
+    let people = info
+        .join
+        .iter()
+        .map(|user| (&user.id, &user.name))
+        .chain(info.organizers.iter().map(|o| (&o.id, &o.name)));
+    let mut checked = BTreeSet::new();
+    for (id, organizer) in people {
+        if !checked.insert(id) {
+            continue;
         }
-    };
+        let Some(expected) = meta
+            .organizers
+            .get(id.as_ref())
+            .and_then(|o| o.timezone.as_ref())
+        else {
+            continue;
+        };
+        if !same_zone(expected.as_ref()) {
+            eprintln!(
+                "{:?}",
+                Report::new(OrganizerTimeZoneMismatch {
+                    name: name.to_owned(),
+                    organizer: organizer.clone().into_owned(),
+                    expected: expected.as_ref().clone().into_owned(),
+                    src: event.timezone_file.into(),
+                    location: event.event.timezone().span().into(),
+                }),
+            );
+        }
+    }
 
-    Ok(output::Event {
-        name,
-        start_date: event
-            .event
-            .start_date
-            .map(|d| {
-                d.and_time(NaiveTime::MIN)
-                    .and_local_timezone(tz)
-                    .earliest()
-                    .ok_or_else(|| miette!("Midnight of start date does not exist"))
-                    .map(|t| t.timestamp())
-            })
-            .transpose()?,
-        end_date: event
-            .event
-            .end_date
-            .map(|d| {
-                d.checked_add_days(Days::new(1))
-                    .and_then(|d| d.and_time(NaiveTime::MIN).and_local_timezone(tz).earliest())
-                    .ok_or_else(|| miette!("Midnight of day after end date does not exist"))
-                    .map(|t| t.timestamp())
-            })
-            .transpose()?,
-        info: output::EventInfo {
-            poster: poster.as_ref().and_then(|p| posters.try_get_output(p)),
-            ..convert_event_info(&event.event.info, posters)
-        },
-        timezone: event.event.timezone.as_ref().as_ref(),
-        start: (event.event.start.0 - NaiveTime::default()).num_minutes() as i32,
-        duration: event.event.duration.0.num_minutes() as i32,
-        platforms: &event.event.platforms,
-        days: convert_event_days(&event.event.days, posters),
-        languages,
-        confirmed,
-        canceled,
-    })
+    if let Some(previous) = state.timezones.get(event.id().as_ref()) {
+        if !same_zone(previous) {
+            eprintln!(
+                "{:?}",
+                Report::new(TimeZoneChanged {
+                    name: name.to_owned(),
+                    previous: previous.clone(),
+                    src: event.timezone_file.into(),
+                    location: event.event.timezone().span().into(),
+                }),
+            );
+        }
+    }
 }
 
 struct PosterInfo<'a> {
@@ -560,6 +2915,59 @@ struct PosterInfo<'a> {
     pub width: u16,
     pub height: u16,
     pub hash: Output<Sha256>,
+    /// Only loaded with `--similar-posters`.
+    pub appearance: Option<Appearance>,
+}
+
+/// What a poster looks like, for finding other files with the same image.
+struct Appearance {
+    hash: state::ImageHash,
+    thumbnail: image::RgbImage,
+}
+
+/// The size of the thumbnails compared to tell whether two images are the same.
+const THUMBNAIL_SIZE: u32 = 64;
+
+impl Appearance {
+    fn load(path: &Path, width: u16, height: u16) -> Result<Self> {
+        let image = image::open(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Could not read {}", path.display()))?;
+        let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+        let mut dhash = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+                dhash = dhash << 1 | u64::from(brighter);
+            }
+        }
+        Ok(Appearance {
+            hash: state::ImageHash {
+                dhash,
+                width,
+                height,
+            },
+            thumbnail: image
+                .resize_exact(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+                .to_rgb8(),
+        })
+    }
+
+    /// Whether the image at `path` looks the same. Compression changes each part of a thumbnail
+    /// a little, while a change like a different date changes some parts a lot.
+    fn matches(&self, path: &Path) -> bool {
+        let Ok(image) = image::open(path) else {
+            return false;
+        };
+        let thumbnail = image
+            .resize_exact(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+            .to_rgb8();
+        self.thumbnail
+            .as_raw()
+            .iter()
+            .zip(thumbnail.as_raw())
+            .all(|(a, b)| a.abs_diff(*b) <= 12)
+    }
 }
 
 struct Posters {
@@ -567,17 +2975,34 @@ struct Posters {
     posters: Vec<state::Poster>,
     by_sha256: HashMap<Output<Sha256>, u8>,
     now: DateTime<Utc>,
+    dry_run: bool,
+    /// Whether posters that look the same share a slot.
+    similar: bool,
+    retry: Retry,
+    /// The poster slots that were written, and the images written to them.
+    written: Vec<(u8, PathBuf)>,
+    /// The time spent reading and hashing poster images.
+    hashing: Duration,
+    /// The image used for each poster slot in this compile.
+    sources: BTreeMap<u8, PathBuf>,
 }
 
 impl Posters {
-    fn load(directory: PathBuf, state: &State, now: DateTime<Utc>) -> Self {
+    fn load(
+        directory: PathBuf,
+        state: &State,
+        now: DateTime<Utc>,
+        dry_run: bool,
+        similar: bool,
+        retry: Retry,
+    ) -> Self {
         let posters = state.posters.clone();
         let mut by_sha256 = HashMap::with_capacity(posters.len());
         for (i, poster) in posters.iter().enumerate() {
             by_sha256.insert(poster.sha256, i as u8);
         }
 
-        if !directory.exists() {
+        if !dry_run && !directory.exists() {
             if let Err(err) = fs::create_dir(&directory) {
                 eprintln!("{err:?}");
             }
@@ -588,26 +3013,55 @@ impl Posters {
             posters,
             by_sha256,
             now,
+            dry_run,
+            similar,
+            retry,
+            written: Vec::new(),
+            hashing: Duration::ZERO,
+            sources: BTreeMap::new(),
+        }
+    }
+
+    fn try_load<'p>(&mut self, image_path: Cow<'p, Path>) -> Option<PosterInfo<'p>> {
+        let start = Instant::now();
+        let mut poster = try_load_poster(image_path);
+        if let Some(poster) = poster.as_mut().filter(|_| self.similar) {
+            match Appearance::load(&poster.source, poster.width, poster.height) {
+                Ok(appearance) => poster.appearance = Some(appearance),
+                Err(error) => eprintln!("{error:?}"),
+            }
         }
+        self.hashing += start.elapsed();
+        poster
     }
 
-    fn save(self, state: &mut State) {
+    fn save(self, state: &mut State) -> Vec<(u8, PathBuf)> {
         state.posters = self.posters;
+        self.written
     }
 
     fn try_get_output(&mut self, poster: &PosterInfo<'_>) -> Option<output::PosterInfo> {
-        let index = match self.by_sha256.entry(poster.hash) {
-            Entry::Occupied(e) => {
-                let index = *e.get();
+        let similar = self.find_similar(poster);
+        let image = poster.appearance.as_ref().map(|a| a.hash);
+        let index = match (self.by_sha256.entry(poster.hash), similar) {
+            (_, Some(index)) => {
                 self.posters[index as usize].last_used = self.now;
                 index
             }
-            Entry::Vacant(e) => {
+            (Entry::Occupied(e), None) => {
+                let index = *e.get();
+                let slot = &mut self.posters[index as usize];
+                slot.last_used = self.now;
+                slot.image = slot.image.or(image);
+                index
+            }
+            (Entry::Vacant(e), None) => {
                 let index = if self.posters.len() < 255 {
                     let index = self.posters.len() as u8;
                     self.posters.push(state::Poster {
                         last_used: self.now,
                         sha256: poster.hash,
+                        image,
                     });
                     e.insert(index);
                     index
@@ -624,24 +3078,60 @@ impl Posters {
                     self.posters[index as usize] = state::Poster {
                         last_used: self.now,
                         sha256: poster.hash,
+                        image,
                     };
                     index
                 };
-                if let Err(err) =
-                    fs::copy(&poster.source, self.directory.join(format!("{index:02x}")))
-                {
-                    eprintln!("{err:?}");
-                    return None;
+                if !self.dry_run {
+                    let destination = self.directory.join(format!("{index:02x}"));
+                    if let Err(err) = self
+                        .retry
+                        .run(&destination, || fs::copy(&poster.source, &destination))
+                    {
+                        eprintln!("{err:?}");
+                        return None;
+                    }
                 }
+                self.written.push((index, poster.source.to_path_buf()));
                 index
             }
         };
+        self.sources
+            .entry(index)
+            .or_insert_with(|| poster.source.to_path_buf());
         Some(output::PosterInfo {
             number: index,
             width: poster.width,
             height: poster.height,
         })
     }
+
+    /// The first slot with a different file that looks the same as `poster`, if it comes before
+    /// the poster's own slot. Earlier compiles may have given the same image several slots.
+    fn find_similar(&self, poster: &PosterInfo<'_>) -> Option<u8> {
+        let appearance = poster.appearance.as_ref()?;
+        let own = self
+            .by_sha256
+            .get(&poster.hash)
+            .map_or(256, |&i| usize::from(i));
+        self.posters
+            .iter()
+            .take(own)
+            .enumerate()
+            .find_map(|(index, slot)| {
+                let index = index as u8;
+                if !slot.image?.is_close(&appearance.hash) {
+                    return None;
+                }
+                // The slot's image from this compile, or from an earlier one.
+                let path = self
+                    .sources
+                    .get(&index)
+                    .cloned()
+                    .unwrap_or_else(|| self.directory.join(format!("{index:02x}")));
+                appearance.matches(&path).then_some(index)
+            })
+    }
 }
 
 fn try_load_poster(image_path: Cow<'_, Path>) -> Option<PosterInfo<'_>> {
@@ -684,6 +3174,7 @@ fn try_load_poster(image_path: Cow<'_, Path>) -> Option<PosterInfo<'_>> {
                         width: size.width as u16,
                         height: size.height as u16,
                         hash: hasher.finalize(),
+                        appearance: None,
                     }),
                     Err(e) => {
                         eprintln!("{:?}", e);
@@ -699,105 +3190,360 @@ fn try_load_poster(image_path: Cow<'_, Path>) -> Option<PosterInfo<'_>> {
     }
 }
 
+fn check_note_expiry(file: &EventFile, days: &input::EventDays) {
+    for (weekday, day) in days.iter() {
+        let Some(expires) = day.note.as_ref().and_then(|n| n.expires.as_ref()) else {
+            continue;
+        };
+        let date = *expires.as_ref();
+        if date.weekday() != weekday {
+            eprintln!(
+                "{:?}",
+                Report::new(NoteExpiresOnOtherDay {
+                    date,
+                    actual: date.weekday(),
+                    weekday,
+                    src: file.into(),
+                    location: expires.span().into(),
+                }),
+            );
+        }
+    }
+}
+
+fn check_interval(event: &Event) -> Result<()> {
+    let input = &event.event;
+    match (&input.every, &input.anchor) {
+        (Some(every), None) => Err(IntervalWithoutAnchor {
+            src: event.file_of("every").into(),
+            location: every.span().into(),
+        }
+        .into()),
+        (None, Some(anchor)) => Err(IntervalWithoutAnchor {
+            src: event.file_of("anchor").into(),
+            location: anchor.span().into(),
+        }
+        .into()),
+        (Some(every), Some(_)) => {
+            let has_weeks = input.info.weeks.is_some()
+                || input.days.iter().any(|(_, day)| day.info.weeks.is_some())
+                || input
+                    .languages
+                    .values()
+                    .any(|language| language.days.iter().any(|(_, d)| d.info.weeks.is_some()));
+            if has_weeks {
+                Err(IntervalWithWeeks {
+                    src: event.file_of("every").into(),
+                    location: every.span().into(),
+                }
+                .into())
+            } else {
+                Ok(())
+            }
+        }
+        (None, None) => Ok(()),
+    }
+}
+
+fn check_monthly(event: &Event) -> Result<()> {
+    let input = &event.event;
+    let Some(monthly) = &input.monthly else {
+        return Ok(());
+    };
+    if monthly.as_ref().get() > 31 {
+        return Err(MonthlyOutOfRange {
+            src: event.file_of("monthly").into(),
+            location: monthly.span().into(),
+        }
+        .into());
+    }
+    let has_weeks = input.info.weeks.is_some()
+        || input
+            .languages
+            .values()
+            .any(|language| language.days.iter().any(|(_, d)| d.info.weeks.is_some()));
+    if !input.days.is_daily() || has_weeks || input.every.is_some() || input.dates.is_some() {
+        return Err(MonthlyWithWeeklySchedule {
+            src: event.file_of("monthly").into(),
+            location: monthly.span().into(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn check_hiatus(event: &Event) -> Result<()> {
+    let Some(hiatus) = &event.event.hiatus else {
+        return Ok(());
+    };
+    if hiatus.as_ref().until < hiatus.as_ref().from {
+        return Err(HiatusEndsBeforeStart {
+            src: event.file_of("hiatus").into(),
+            location: hiatus.span().into(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Reports reasons given for dates that are not canceled.
+fn check_canceled_reasons(event: &Event) {
+    let mut reasons = vec![(vec![], &event.event.canceled_reasons)];
+    for (language_id, language) in &event.event.languages {
+        reasons.push((
+            vec!["languages".to_owned(), language_id.code().to_owned()],
+            &language.canceled_reasons,
+        ));
+    }
+    let mut spans = None;
+    for (path, reasons) in reasons {
+        for &date in reasons.keys() {
+            let canceled = match &event.event.canceled {
+                input::DateSet::All(all) => *all,
+                input::DateSet::Dates(dates) => dates.iter().any(|d| *d.as_ref() == date),
+            };
+            if canceled {
+                continue;
+            }
+            let spans = spans.get_or_insert_with(|| event.source.source_map());
+            let mut path = path.clone();
+            path.extend(["canceled_reasons".to_owned(), date.to_string()]);
+            eprintln!(
+                "{:?}",
+                Report::new(ReasonWithoutCancellation {
+                    date,
+                    src: event.source.into(),
+                    location: spans.as_ref().and_then(|s| s.span(&path)),
+                }),
+            );
+        }
+    }
+}
+
+fn check_platform_overrides(event: &Event) -> Result<()> {
+    let mut days = vec![(vec!["days"], &event.event.days)];
+    for (language_id, language) in &event.event.languages {
+        days.push((vec!["languages", language_id.code()], &language.days));
+    }
+    let mut spans = None;
+    for (path, days) in days {
+        for (weekday, day) in days.iter() {
+            for platform in day.platforms.keys() {
+                if !event.event.platforms.contains(platform) {
+                    let spans = spans.get_or_insert_with(|| event.source.source_map());
+                    let mut path = path.clone();
+                    path.extend([input::day_key(weekday), "platforms", platform.name()]);
+                    return Err(UnsupportedPlatformOverride {
+                        platform: platform.name(),
+                        src: event.source.into(),
+                        location: spans.as_ref().and_then(|s| s.span(&path)),
+                    }
+                    .into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn convert_event_days<'a>(
     value: &'a input::EventDays<'a>,
+    today: NaiveDate,
     posters: &mut Posters,
 ) -> output::EventDays<'a> {
     output::EventDays {
         monday: value
             .monday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
         tuesday: value
             .tuesday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
         wednesday: value
             .wednesday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
         thursday: value
             .thursday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
         friday: value
             .friday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
         saturday: value
             .saturday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
         sunday: value
             .sunday
             .as_ref()
-            .map(|day| convert_event_day(day, posters)),
+            .map(|day| convert_event_day(day, today, posters)),
     }
 }
 
 fn convert_event_day<'a>(
     value: &'a input::EventDay<'a>,
+    today: NaiveDate,
     posters: &mut Posters,
 ) -> output::EventDay<'a> {
     output::EventDay {
         name: value.info.name.as_deref(),
+        start: value
+            .start
+            .map(|s| (s.0 - NaiveTime::default()).num_minutes() as i32),
         duration: value.duration.map(|d| d.0.num_minutes() as i32),
+        note: value
+            .note
+            .as_ref()
+            .filter(|n| n.expires.as_ref().is_none_or(|e| today <= *e.as_ref()))
+            .map(|n| n.text.as_ref()),
+        platforms: value
+            .platforms
+            .iter()
+            .map(|(&platform, o)| {
+                (
+                    platform,
+                    output::PlatformOverride {
+                        start: o
+                            .start
+                            .map(|s| (s.0 - NaiveTime::default()).num_minutes() as i32),
+                        duration: o.duration.map(|d| d.0.num_minutes() as i32),
+                    },
+                )
+            })
+            .collect(),
+        worlds: Vec::new(),
         info: convert_event_info(&value.info, posters),
     }
 }
 
+/// Gives each day of an event that rotates between worlds the world it uses, or its worlds by the
+/// weeks of the month if that changes from week to week. Days that set their own world keep it.
+fn rotate_worlds<'a>(
+    mut days: output::EventDays<'a>,
+    info: &'a input::EventInfo<'a>,
+) -> output::EventDays<'a> {
+    let Some(worlds @ input::Worlds::Rotation(_)) = &info.world else {
+        return days;
+    };
+    for (weekday, day) in days.iter_mut() {
+        if day.info.world.is_some() {
+            continue;
+        }
+        let mut weeks = day
+            .info
+            .weeks
+            .or(info.weeks.as_deref())
+            .map_or_else(|| vec![1, 2, 3, 4, 5], <[u8]>::to_vec);
+        weeks.sort_unstable();
+        weeks.dedup();
+        let mut found: Vec<output::WeekWorld> = Vec::new();
+        for &week in &weeks {
+            let Some(world) = worlds.on(weekday, week) else {
+                continue;
+            };
+            match found.iter_mut().find(|found| ptr::eq(found.world, world)) {
+                Some(found) => found.weeks.push(week),
+                None => found.push(output::WeekWorld {
+                    weeks: vec![week],
+                    world,
+                }),
+            }
+        }
+        match &found[..] {
+            [only] if only.weeks == weeks => day.info.world = Some(only.world),
+            _ => day.worlds = found,
+        }
+    }
+    days
+}
+
 fn convert_event_info<'a>(
     value: &'a input::EventInfo<'a>,
     posters: &mut Posters,
 ) -> output::EventInfo<'a> {
+    // Markup that can't be parsed is reported by `check_descriptions`, and shown as it is written.
+    let (description, description_runs) = match value.description.as_deref().map(markup::parse) {
+        Some(Ok(description)) => (Some(description.text), description.runs),
+        Some(Err(_)) => (value.description.as_deref().map(Cow::Borrowed), None),
+        None => (None, None),
+    };
     output::EventInfo {
         poster: value
             .poster
             .as_deref()
-            .and_then(|p| try_load_poster(Cow::Borrowed(Path::new(p))))
+            .and_then(|p| posters.try_load(Cow::Borrowed(Path::new(p))))
             .and_then(|p| posters.try_get_output(&p)),
-        description: value.description.as_deref(),
+        poster_credit: value.poster_credit.as_deref(),
+        poster_license: value.poster_license.as_deref(),
+        color: value.color.map(|color| color.0),
+        description,
+        description_runs,
         web: value.web.as_deref(),
         discord: value.discord.as_deref(),
         group: value.group.as_deref(),
         hashtag: value.hashtag.as_deref().map(Hashtag::from),
         twitter: value.twitter.as_deref(),
+        bluesky: value
+            .bluesky
+            .as_deref()
+            .and_then(|h| Service::Bluesky.account(h)),
+        misskey: value
+            .misskey
+            .as_deref()
+            .and_then(|h| Service::Misskey.account(h)),
+        youtube: value
+            .youtube
+            .as_deref()
+            .and_then(|h| Service::YouTube.account(h)),
+        twitch: value
+            .twitch
+            .as_deref()
+            .and_then(|h| Service::Twitch.account(h)),
+        links: &value.links,
         join: &value.join,
-        world: value.world.as_ref(),
+        organizers: &value.organizers,
+        world: value.world.as_ref().and_then(input::Worlds::single),
         weeks: value.weeks.as_deref(),
+        tags: &value.tags,
+        capacity: value.capacity,
+        instance_type: value.instance_type,
+        join_via: value.join_via,
+        open_before: value
+            .open_before
+            .map(|open_before| open_before.0.num_minutes() as i32),
+        adult: value.adult,
     }
 }
 
 fn guess_poster(event: &Event, files: &BTreeSet<PathBuf>) -> Option<PathBuf> {
     let mut image_extensions = ["webp", "jpeg", "jpg", "png"].into_iter();
-    let mut image_path = PathBuf::from(event.source.path);
     let found = loop {
-        let Some(extension) = image_extensions.next() else {
-            return None;
-        };
-        image_path.set_extension(extension);
+        let image_path = event.poster_path(image_extensions.next()?);
         if files.contains(&image_path) {
-            break image_path.clone();
+            break image_path;
         }
     };
     loop {
         let Some(extension) = image_extensions.next() else {
             return Some(found);
         };
-        image_path.set_extension(extension);
+        let image_path = event.poster_path(extension);
         if files.contains(&image_path) {
             eprintln!(
                 "{:?}",
                 Report::new(MultiplePosters {
                     found: found.clone(),
-                    extra: image_path.clone(),
+                    extra: image_path,
                 })
             )
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct User<'a> {
     #[serde(borrow)]
@@ -806,7 +3552,19 @@ pub struct User<'a> {
     pub id: Cow<'a, str>,
 }
 
-#[derive(Deserialize, Serialize)]
+/// Someone credited for running the event, like a host, a DJ, or a translator.
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Organizer<'a> {
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub role: Option<Cow<'a, str>>,
+}
+
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct World<'a> {
     #[serde(borrow)]
@@ -842,3 +3600,103 @@ impl<'a> From<&'a str> for Hashtag<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn confirmed_dates_off_the_schedule_are_dropped() {
+        let file = EventFile::toml(
+            Path::new("event.toml"),
+            r#"
+                timezone = "Asia/Tokyo"
+                start = "21:00"
+                duration = "1:00"
+                confirmed = ["2027-01-04", "2027-01-05", "2027-01-11"]
+                every = 2
+                anchor = "2027-01-04"
+
+                [days.monday]
+            "#,
+        );
+        let event = file.parse();
+        let days_off = resolved::DaysOff::default();
+        let resolved = resolved::Event::resolve(&event, &days_off).unwrap();
+        let now = Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap();
+        let reported = std::cell::Cell::new(0);
+        let confirmed = upcoming_dates(
+            &resolved,
+            &event.event.confirmed,
+            now,
+            chrono::Duration::zero(),
+            |date| {
+                reported.set(reported.get() + 1);
+                miette!("{}", date.as_ref())
+            },
+        );
+        let output::DateSet::Dates(dates) = confirmed else {
+            panic!("no dates are left");
+        };
+        // The 5th is a Tuesday, and the 11th is the Monday of a week the event isn't held.
+        assert_eq!(dates, [NaiveDate::from_ymd_opt(2027, 1, 4).unwrap()]);
+        assert_eq!(reported.get(), 2);
+    }
+
+    #[test]
+    fn extends_inherits_every_field() {
+        let schema = schemars::schema_for!(input::Event).to_value();
+        for key in schema["properties"].as_object().unwrap().keys() {
+            assert!(
+                INHERITABLE_KEYS.contains(&key.as_str())
+                    || ["extends", "id"].contains(&key.as_str()),
+                "{key} is not inherited through extends",
+            );
+        }
+    }
+
+    #[test]
+    fn inherited_locations_are_shown_in_the_base() {
+        let files = [
+            EventFile::toml(
+                Path::new("base.toml"),
+                r#"
+                    # The comments put the dates past the end of the file that extends this one.
+                    timezone = "Asia/Tokyo"
+                    start = "21:00"
+                    duration = "1:00"
+                    canceled = ["2030-01-09"]
+
+                    [days.saturday]
+                "#,
+            ),
+            EventFile::toml(Path::new("child.toml"), "extends = \"base.toml\"\n"),
+        ];
+        let event = parse_event(&files[1], &files, &[]).unwrap();
+        let input::DateSet::Dates(canceled) = &event.event.canceled else {
+            panic!("the canceled dates aren't inherited");
+        };
+        let report = Report::new(CanceledOutOfRange {
+            date: *canceled[0].as_ref(),
+            src: event.file_of("canceled").into(),
+            location: canceled[0].span().into(),
+        });
+        assert!(format!("{report:?}").contains("base.toml"));
+        assert_eq!(event.file_of("extends").path, Path::new("child.toml"));
+    }
+
+    #[test]
+    fn languages_are_read_in_canonical_form() {
+        let code = |tag| Language::parse(tag).map(|language| language.code().to_owned());
+        assert_eq!(code("jpn").unwrap(), "ja");
+        assert_eq!(code("zh-hant").unwrap(), "zh-Hant");
+        assert_eq!(code("PT-br").unwrap(), "pt-BR");
+        // Toki Pona is newer than the code table.
+        assert_eq!(code("tok").unwrap(), "tok");
+        assert!(code("zz").is_err());
+        assert!(code("t0k").is_err());
+        assert!(code("en-x").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! The Markdown subset that descriptions can be written in. Descriptions are turned into runs of
+//! text with the same style, so the world can show them without parsing Markdown.
+//!
+//! `**Bold**`, `*italic*` and `[link](https://example.com)` text is supported, and every line break
+//! is kept. A backslash before `*`, `[`, `]` or another backslash writes the character as it is.
+//! An asterisk followed by a space can't start a style, so `5 * 3` doesn't need one, and like in
+//! CommonMark, asterisks that start a style which is never closed are written as they are.
+
+use std::borrow::Cow;
+
+use crate::{is_web_address, output::Run};
+
+pub struct Description<'a> {
+    /// The text without any markup, for frontends that don't show styles.
+    pub text: Cow<'a, str>,
+    /// The runs of the description, or `None` if it has no styles.
+    pub runs: Option<Vec<Run<'a>>>,
+}
+
+/// Parses a description, or returns what is wrong with it.
+pub fn parse(source: &str) -> Result<Description<'_>, String> {
+    // The asterisks that start a style which is never closed are only known at the end, so the
+    // description is parsed again with them written as they are, until every style is closed.
+    let mut literal = Vec::new();
+    let parser = loop {
+        let parser = parse_runs(source, &literal)?;
+        match parser.unclosed() {
+            Some(index) => literal.push(index),
+            None => break parser,
+        }
+    };
+
+    let text: String = parser.runs.iter().map(|run| &*run.text).collect();
+    let text = if text == source {
+        Cow::Borrowed(source)
+    } else {
+        Cow::Owned(text)
+    };
+    let styled = parser
+        .runs
+        .iter()
+        .any(|run| run.bold || run.italic || run.url.is_some());
+    Ok(Description {
+        text,
+        runs: styled.then_some(parser.runs),
+    })
+}
+
+/// Splits a description into runs, writing the asterisks at the indexes in `literal` as they are.
+fn parse_runs<'a>(source: &'a str, literal: &[usize]) -> Result<Parser<'a>, String> {
+    let mut parser = Parser {
+        runs: Vec::new(),
+        text: String::new(),
+        bold: false,
+        italic: false,
+        bold_start: 0,
+        italic_start: 0,
+        url: None,
+    };
+    let mut chars = source.char_indices().peekable();
+    // The index of the `]` that ends the text of the current link, and of the end of its address.
+    let mut link: Option<(usize, usize)> = None;
+    while let Some((index, c)) = chars.next() {
+        if let Some((text_end, end)) = link {
+            if index == text_end {
+                parser.flush();
+                parser.url = None;
+                link = None;
+                while chars.next_if(|&(index, _)| index < end).is_some() {}
+                continue;
+            }
+        }
+        match c {
+            '\\' => match chars.next_if(|&(_, c)| matches!(c, '\\' | '*' | '[' | ']')) {
+                Some((_, c)) => parser.text.push(c),
+                None => parser.text.push(c),
+            },
+            '\r' if chars.peek().is_some_and(|&(_, c)| c == '\n') => {}
+            '\n' => {
+                parser.flush();
+                parser.runs.push(Run::plain("\n".into()));
+            }
+            '*' => {
+                let mut count = 1;
+                while chars.next_if(|&(_, c)| c == '*').is_some() {
+                    count += 1;
+                }
+                let before = source[..index].chars().next_back();
+                let after = chars.peek().map(|&(_, c)| c);
+                let can_open = after.is_some_and(|c| !c.is_whitespace());
+                let can_close = before.is_some_and(|c| !c.is_whitespace());
+                if literal.contains(&index) {
+                    parser.text.extend(std::iter::repeat_n('*', count));
+                } else {
+                    parser.emphasis(index, count, can_open, can_close);
+                }
+            }
+            '[' if parser.url.is_none() => match find_link(source, index) {
+                Some((text_end, url, end)) => {
+                    if !is_web_address(url) {
+                        return Err(format!("a link to {url:?}, which is not a web address"));
+                    }
+                    parser.flush();
+                    parser.url = Some(url);
+                    link = Some((text_end, end));
+                }
+                None => parser.text.push(c),
+            },
+            c => parser.text.push(c),
+        }
+    }
+    parser.flush();
+    Ok(parser)
+}
+
+struct Parser<'a> {
+    runs: Vec<Run<'a>>,
+    /// The text of the current run.
+    text: String,
+    bold: bool,
+    italic: bool,
+    /// The index of the asterisks that started the current bold text.
+    bold_start: usize,
+    /// The index of the asterisks that started the current italic text.
+    italic_start: usize,
+    url: Option<&'a str>,
+}
+
+impl Parser<'_> {
+    /// Ends the current run.
+    fn flush(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        self.runs.push(Run {
+            text: std::mem::take(&mut self.text).into(),
+            bold: self.bold,
+            italic: self.italic,
+            url: self.url.map(Cow::Borrowed),
+        });
+    }
+
+    /// The index of the asterisks that started a style which is still open at the end.
+    fn unclosed(&self) -> Option<usize> {
+        match (self.bold, self.italic) {
+            (true, _) => Some(self.bold_start),
+            (false, true) => Some(self.italic_start),
+            (false, false) => None,
+        }
+    }
+
+    /// Opens or closes styles with the `count` asterisks at `index`, or writes them as they are if
+    /// they can't.
+    fn emphasis(&mut self, index: usize, count: usize, can_open: bool, can_close: bool) {
+        let (bold, italic) = match count {
+            1 => (self.bold, !self.italic),
+            2 => (!self.bold, self.italic),
+            3 if self.bold == self.italic => (!self.bold, !self.italic),
+            _ => (self.bold, self.italic),
+        };
+        // Styles can only be opened before text and closed after it.
+        let opens = bold && !self.bold || italic && !self.italic;
+        let closes = !bold && self.bold || !italic && self.italic;
+        let changed = (bold, italic) != (self.bold, self.italic);
+        if changed && (!opens || can_open) && (!closes || can_close) {
+            self.flush();
+            if bold && !self.bold {
+                self.bold_start = index;
+            }
+            if italic && !self.italic {
+                self.italic_start = index;
+            }
+            self.bold = bold;
+            self.italic = italic;
+        } else {
+            self.text.extend(std::iter::repeat_n('*', count));
+        }
+    }
+}
+
+/// Finds the link that starts with the `[` at `start`, and returns the index of the `]` that ends
+/// its text, its address, and the index after the `)` that ends the address.
+fn find_link(source: &str, start: usize) -> Option<(usize, &str, usize)> {
+    let mut chars = source[start + 1..].char_indices();
+    let text_end = loop {
+        match chars.next()? {
+            (_, '\\') => {
+                chars.next();
+            }
+            (_, '\n' | '[') => return None,
+            (index, ']') => break start + 1 + index,
+            _ => {}
+        }
+    };
+    let rest = source[text_end + 1..].strip_prefix('(')?;
+    let url = &rest[..rest.find(')')?];
+    if text_end == start + 1 || url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((text_end, url, text_end + 2 + url.len() + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_become_runs() {
+        let description = parse("**Bold**, *italic* and [a link](https://example.com)").unwrap();
+        assert_eq!(description.text, "Bold, italic and a link");
+        let runs = description.runs.unwrap();
+        assert_eq!(runs.len(), 5);
+        assert_eq!(runs[0].text, "Bold");
+        assert!(runs[0].bold && !runs[0].italic);
+        assert_eq!(runs[1].text, ", ");
+        assert!(!runs[1].bold && !runs[1].italic && runs[1].url.is_none());
+        assert_eq!(runs[2].text, "italic");
+        assert!(!runs[2].bold && runs[2].italic);
+        assert_eq!(runs[4].text, "a link");
+        assert_eq!(runs[4].url.as_deref(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn escaped_and_spaced_asterisks_are_text() {
+        let description = parse("5 * 3 is \\*not\\* italic").unwrap();
+        assert_eq!(description.text, "5 * 3 is *not* italic");
+        assert!(description.runs.is_none());
+    }
+
+    #[test]
+    fn line_breaks_are_kept() {
+        let description = parse("*Doors*\nopen").unwrap();
+        assert_eq!(description.text, "Doors\nopen");
+        let runs = description.runs.unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[1].text, "\n");
+    }
+
+    #[test]
+    fn links_need_web_addresses() {
+        assert!(parse("[Mail us](mailto:events@example.com)").is_err());
+    }
+
+    #[test]
+    fn unclosed_asterisks_are_kept() {
+        for source in ["*Bring a mic", "5 * 3", "**Bring a mic", "a *b **c"] {
+            let description = parse(source).unwrap();
+            assert_eq!(description.text, source);
+            assert!(description.runs.is_none());
+        }
+    }
+
+    #[test]
+    fn unclosed_asterisks_before_closed_styles_are_kept() {
+        let description = parse("*Bring **a mic**").unwrap();
+        assert_eq!(description.text, "*Bring a mic");
+        let runs = description.runs.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].text, "*Bring ");
+        assert!(!runs[0].bold && !runs[0].italic);
+        assert_eq!(runs[1].text, "a mic");
+        assert!(runs[1].bold && !runs[1].italic);
+    }
+}
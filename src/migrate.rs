@@ -0,0 +1,276 @@
+//! Renaming events without losing what the output directory knows about them.
+//!
+//! An event's id is its file name, so renaming the file changes the id. The `related` lists of
+//! other events and the state in the output directory refer to events by id, `extends` refers to
+//! the file, and links from outside the calendar may refer to the id too, so they are all updated
+//! together, and a redirect from the old id is added to the output.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Write,
+    iter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use miette::{miette, Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    collect_files, discover::FileFilter, load_state, normalize_path, parse_events,
+    read_event_files, report::Errors, retry::Retry, safely_save, Event,
+};
+
+#[derive(clap::Args)]
+pub struct MigrateArgs {
+    /// The directory containing the event files.
+    input: PathBuf,
+    /// The output directory of previous compiles, whose state is updated.
+    output: PathBuf,
+    /// The events to rename, like old-id=new-id.
+    #[arg(required = true, value_parser = parse_rename)]
+    renames: Vec<(String, String)>,
+    /// Show what would change without changing anything.
+    #[arg(long)]
+    dry_run: bool,
+    #[command(flatten)]
+    filter: FileFilter,
+}
+
+fn parse_rename(value: &str) -> Result<(String, String), String> {
+    let Some((old, new)) = value.split_once('=') else {
+        return Err("expected old-id=new-id".to_owned());
+    };
+    if new.is_empty() || new.contains(['/', '\\']) {
+        return Err(format!("{new:?} can't be used as a file name"));
+    }
+    Ok((old.to_owned(), new.to_owned()))
+}
+
+pub fn run(args: MigrateArgs, errors: Errors) -> ExitCode {
+    match migrate(&args, &errors) {
+        Ok(()) if errors.count() == 0 => ExitCode::SUCCESS,
+        Ok(()) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{error:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn migrate(args: &MigrateArgs, errors: &Errors) -> Result<()> {
+    if !args.input.is_dir() {
+        return Err(miette!(
+            "{} is not a directory. Archives can't be migrated.",
+            args.input.display(),
+        ));
+    }
+    let files = collect_files(&args.input, &args.filter);
+    let event_files = read_event_files(&files, errors);
+    let events = parse_events(&event_files, errors);
+    if errors.count() != 0 {
+        return Err(miette!(
+            "The events must be fixed before they can be renamed."
+        ));
+    }
+
+    let by_id: HashMap<_, _> = events.iter().map(|event| (event.id(), event)).collect();
+    let renames: BTreeMap<&str, &str> = args
+        .renames
+        .iter()
+        .map(|(old, new)| (old.as_str(), new.as_str()))
+        .collect();
+    let mut new_ids = HashMap::new();
+    for (&old, &new) in &renames {
+        if !by_id.contains_key(old) {
+            return Err(miette!("There is no event with the id {old:?}."));
+        }
+        if by_id.contains_key(new) {
+            return Err(miette!(
+                "{old:?} can't be renamed to {new:?}, because that event already exists."
+            ));
+        }
+        if let Some((meta, _)) = by_id[old].source.entry {
+            return Err(miette!(
+                "{old:?} is written in {}, where its id has to be changed by hand.",
+                meta.display(),
+            ));
+        }
+        if let Some(other) = new_ids.insert(new, old) {
+            return Err(miette!(
+                "{other:?} and {old:?} can't both be renamed to {new:?}."
+            ));
+        }
+    }
+
+    let updates: Vec<_> = events
+        .iter()
+        .filter_map(|event| Some((event, update_references(event, &by_id, &renames)?)))
+        .collect();
+    // Only TOML files can be edited in place, since the others are converted as they are read.
+    if let Some((event, _)) = updates
+        .iter()
+        .find(|(event, _)| !event.source.is_editable())
+    {
+        return Err(miette!(
+            "{} refers to a renamed event, but only TOML files of their own can be updated.",
+            event.source.describe(),
+        ));
+    }
+
+    // Nothing is changed until every rename and reference has been checked. A failure after this
+    // leaves the steps printed so far done, and the rest to finish by hand.
+    for (event, content) in updates {
+        let path = event.source.path;
+        println!("Updating references in {}", path.display());
+        if !args.dry_run {
+            let directory = path.parent().unwrap_or(Path::new("."));
+            safely_save(
+                directory,
+                path.file_name().unwrap(),
+                &Retry::default(),
+                |t| t.write_all(content.as_bytes()).into_diagnostic(),
+            )?;
+        }
+    }
+
+    for (&old, &new) in &renames {
+        let event = by_id[old];
+        // The poster is found by the file name, so it moves with the event file.
+        let posters = ["webp", "jpeg", "jpg", "png"]
+            .into_iter()
+            .map(|extension| event.poster_path(extension))
+            .filter(|path| files.contains(path));
+        for from in iter::once(event.source.path.to_owned()).chain(posters) {
+            // Everything after the id is kept, like `.event.json`.
+            let name = from.file_name().unwrap().to_string_lossy();
+            let to = from.with_file_name(format!("{new}{}", &name[old.len()..]));
+            println!("Renaming {} to {}", from.display(), to.display());
+            if !args.dry_run {
+                fs::rename(&from, &to)
+                    .into_diagnostic()
+                    .wrap_err_with(|| format!("Renaming {} failed.", from.display()))?;
+            }
+        }
+    }
+
+    let mut state = load_state(&args.output)?;
+    for (&old, &new) in &renames {
+        if let Some(timezone) = state.timezones.remove(old) {
+            state.timezones.insert(new.to_owned(), timezone);
+        }
+        // Earlier redirects to the old id follow it to the new one.
+        for target in state.redirects.values_mut() {
+            if target == old {
+                *target = new.to_owned();
+            }
+        }
+        state.redirects.remove(new);
+        state.redirects.insert(old.to_owned(), new.to_owned());
+    }
+    println!("Updating {}", args.output.join("state.json").display());
+    if !args.dry_run {
+        let mut json = serde_json::to_vec_pretty(&state).into_diagnostic()?;
+        json.push(b'\n');
+        safely_save(&args.output, "state.json", &Retry::default(), |t| {
+            t.write_all(&json).into_diagnostic()
+        })?;
+    }
+    Ok(())
+}
+
+/// The `extends` of an event file as it is written, since parsing the event clears it.
+#[derive(Deserialize)]
+struct Extends<'a> {
+    #[serde(borrow, default)]
+    extends: Option<Spanned<Cow<'a, str>>>,
+}
+
+/// Returns the content of the event file with renamed events replaced in `related` and
+/// `extends`, or `None` if it doesn't refer to any of them.
+fn update_references(
+    event: &Event,
+    by_id: &HashMap<Cow<str>, &Event>,
+    renames: &BTreeMap<&str, &str>,
+) -> Option<String> {
+    // Inherited related events are written in the file the event extends, which is updated on
+    // its own.
+    let related: &[_] = if event.inherits("related") {
+        &[]
+    } else {
+        &event.event.related
+    };
+    let mut replaced: Vec<_> = related
+        .iter()
+        .filter_map(|related| {
+            let new = renames.get(related.as_ref().as_ref())?;
+            Some((related.span(), toml::Value::from(*new).to_string()))
+        })
+        .collect();
+    let extends = event.source.deserialize::<Extends>().ok();
+    if let Some(extends) = extends.and_then(|e| e.extends) {
+        let directory = event.source.path.parent().unwrap_or(Path::new(""));
+        let path = normalize_path(&directory.join(extends.as_ref().as_ref()));
+        let renamed = renames
+            .iter()
+            .find(|(old, _)| normalize_path(by_id[**old].source.path) == path);
+        if let Some((old, new)) = renamed {
+            // Only the file name changes, since the file stays in its directory.
+            let written = extends.as_ref().as_ref();
+            let name = written.rfind(['/', '\\']).map_or(0, |i| i + 1);
+            if let Some(rest) = written[name..].strip_prefix(old) {
+                let new_path = format!("{}{new}{rest}", &written[..name]);
+                replaced.push((extends.span(), toml::Value::from(new_path).to_string()));
+            }
+        }
+    }
+    if replaced.is_empty() {
+        return None;
+    }
+    let mut content = event.source.content.to_string();
+    replaced.sort_by_key(|(span, _)| span.start);
+    for (span, new) in replaced.into_iter().rev() {
+        content.replace_range(span, &new);
+    }
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_event, EventFile};
+
+    #[test]
+    fn references_follow_the_renamed_file() {
+        let files = [
+            EventFile::toml(
+                Path::new("events/party.toml"),
+                r#"
+                    timezone = "Asia/Tokyo"
+                    start = "21:00"
+                    duration = "1:00"
+
+                    [days.friday]
+                "#,
+            ),
+            EventFile::toml(
+                Path::new("events/late/after.toml"),
+                "extends = \"../party.toml\"\nrelated = [\"party\"]\nstart = \"23:00\"\n",
+            ),
+        ];
+        let events: Vec<_> = files
+            .iter()
+            .map(|file| parse_event(file, &files, &[]).unwrap())
+            .collect();
+        let by_id = events.iter().map(|event| (event.id(), event)).collect();
+        let renames = BTreeMap::from([("party", "friday-party")]);
+        assert_eq!(update_references(&events[0], &by_id, &renames), None);
+        assert_eq!(
+            update_references(&events[1], &by_id, &renames).unwrap(),
+            "extends = \"../friday-party.toml\"\nrelated = [\"friday-party\"]\nstart = \"23:00\"\n",
+        );
+    }
+}
@@ -0,0 +1,668 @@
+//! Reading a published data.json back, so that the output formats can be rendered by people who
+//! only have the compiled calendar and not its event files.
+//!
+//! The output types borrow from the input, so these owned types mirror them for deserializing,
+//! and lend themselves back out as output types. Both short and long keys are read.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::Write,
+    num::NonZeroU16,
+    path::Path,
+    process::ExitCode,
+};
+
+use chrono::NaiveDate;
+use miette::{Context, IntoDiagnostic};
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    compress,
+    input::Links,
+    output::{self, DateSet, Feature, Keys, PlatformOverride, PosterInfo, Zone},
+    safely_save, CompileArgs, InstanceType, JoinVia, Language, Organizer, Platform, User, World,
+};
+
+#[derive(Deserialize)]
+pub struct Data {
+    meta: Meta,
+    events: Vec<Event>,
+    zones: BTreeMap<String, Zone>,
+    #[serde(default)]
+    redirects: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    title: String,
+    #[serde(rename = "desc", alias = "description")]
+    description: Option<String>,
+    link: Option<String>,
+    #[serde(rename = "home_tz", alias = "home_timezone")]
+    home_timezone: Option<String>,
+    #[serde(rename = "ts", alias = "compiled_time")]
+    compiled_time: i64,
+    #[serde(rename = "lang", alias = "languages", default)]
+    languages: BTreeMap<Language, MetaLanguage>,
+    #[serde(default)]
+    fallbacks: BTreeMap<Language, Vec<Language>>,
+    #[serde(default)]
+    series: Vec<String>,
+    features: BTreeSet<Feature>,
+}
+
+#[derive(Deserialize)]
+struct MetaLanguage {
+    title: Option<String>,
+    #[serde(rename = "desc", alias = "description")]
+    description: Option<String>,
+    link: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    name: String,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    every: Option<u8>,
+    anchor: Option<NaiveDate>,
+    monthly: Option<u8>,
+    dates: Option<Vec<NaiveDate>>,
+    #[serde(flatten)]
+    info: EventInfo,
+    #[serde(rename = "tz", alias = "timezone")]
+    timezone: String,
+    start: i32,
+    duration: i32,
+    platforms: Vec<Platform>,
+    #[serde(flatten)]
+    days: EventDays,
+    #[serde(rename = "lang", alias = "languages", default)]
+    languages: BTreeMap<Language, EventLanguage>,
+    #[serde(default = "DateSet::none")]
+    canceled: DateSet,
+    #[serde(default)]
+    canceled_reasons: BTreeMap<NaiveDate, String>,
+    #[serde(default = "DateSet::all")]
+    confirmed: DateSet,
+    #[serde(default = "DateSet::none")]
+    tentative: DateSet,
+    #[serde(default = "DateSet::none")]
+    skipped: DateSet,
+    hiatus: Option<Hiatus>,
+    #[serde(default)]
+    overrides: BTreeMap<NaiveDate, Override>,
+    #[serde(default)]
+    notes: BTreeMap<NaiveDate, String>,
+    #[serde(default)]
+    rsvps: BTreeMap<NaiveDate, u16>,
+    #[serde(default)]
+    featured: bool,
+    series: Option<String>,
+    #[serde(default)]
+    related: Vec<usize>,
+    unstable: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct EventDays {
+    monday: Option<EventDay>,
+    tuesday: Option<EventDay>,
+    wednesday: Option<EventDay>,
+    thursday: Option<EventDay>,
+    friday: Option<EventDay>,
+    saturday: Option<EventDay>,
+    sunday: Option<EventDay>,
+}
+
+#[derive(Deserialize)]
+struct EventDay {
+    name: Option<String>,
+    duration: Option<i32>,
+    note: Option<String>,
+    #[serde(default)]
+    platforms: BTreeMap<Platform, PlatformOverride>,
+    #[serde(default, deserialize_with = "week_worlds")]
+    worlds: Vec<(Vec<u8>, World<'static>)>,
+    #[serde(flatten)]
+    info: EventInfo,
+}
+
+#[derive(Deserialize)]
+struct Override {
+    name: Option<String>,
+    start: Option<i32>,
+    duration: Option<i32>,
+    #[serde(default, deserialize_with = "world")]
+    world: Option<World<'static>>,
+    poster: Option<PosterInfo>,
+    poster_credit: Option<String>,
+    poster_license: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Hiatus {
+    from: NaiveDate,
+    until: NaiveDate,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EventLanguage {
+    name: Option<String>,
+    #[serde(flatten)]
+    info: EventInfo,
+    #[serde(default)]
+    canceled_reasons: BTreeMap<NaiveDate, String>,
+    hiatus_reason: Option<String>,
+    #[serde(default)]
+    notes: BTreeMap<NaiveDate, String>,
+    #[serde(flatten)]
+    days: EventDays,
+}
+
+#[derive(Deserialize)]
+struct EventInfo {
+    poster: Option<PosterInfo>,
+    poster_credit: Option<String>,
+    poster_license: Option<String>,
+    color: Option<u32>,
+    web: Option<String>,
+    discord: Option<String>,
+    group: Option<String>,
+    hashtag: Option<Hashtag>,
+    twitter: Option<String>,
+    bluesky: Option<output::Account>,
+    misskey: Option<output::Account>,
+    youtube: Option<output::Account>,
+    twitch: Option<output::Account>,
+    #[serde(default)]
+    links: Links<'static>,
+    #[serde(default, deserialize_with = "users")]
+    join: Vec<User<'static>>,
+    #[serde(default, deserialize_with = "organizers")]
+    organizers: Vec<Organizer<'static>>,
+    #[serde(default, deserialize_with = "world")]
+    world: Option<World<'static>>,
+    weeks: Option<Vec<u8>>,
+    #[serde(rename = "desc", alias = "description")]
+    description: Option<String>,
+    #[serde(rename = "desc_runs", alias = "description_runs")]
+    description_runs: Option<Vec<output::Run<'static>>>,
+    #[serde(default)]
+    tags: Vec<Cow<'static, str>>,
+    #[serde(rename = "cap", alias = "capacity")]
+    capacity: Option<NonZeroU16>,
+    #[serde(rename = "inst", alias = "instance_type")]
+    instance_type: Option<InstanceType>,
+    #[serde(rename = "via", alias = "join_via")]
+    join_via: Option<JoinVia>,
+    open_before: Option<i32>,
+    adult: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Hashtag {
+    Safe(String),
+    Escaped { display: String, escaped: String },
+}
+
+/// A user or world, whose borrowing `Deserialize` can't outlive the JSON it was read from.
+#[derive(Deserialize)]
+struct Named {
+    id: String,
+    name: String,
+}
+
+fn users<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<User<'static>>, D::Error> {
+    let users = Vec::<Named>::deserialize(deserializer)?;
+    Ok(users
+        .into_iter()
+        .map(|user| User {
+            id: user.id.into(),
+            name: user.name.into(),
+        })
+        .collect())
+}
+
+fn organizers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Organizer<'static>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Credited {
+        id: String,
+        name: String,
+        role: Option<String>,
+    }
+
+    let organizers = Vec::<Credited>::deserialize(deserializer)?;
+    Ok(organizers
+        .into_iter()
+        .map(|organizer| Organizer {
+            id: organizer.id.into(),
+            name: organizer.name.into(),
+            role: organizer.role.map(Into::into),
+        })
+        .collect())
+}
+
+fn world<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<World<'static>>, D::Error> {
+    let world = Option::<Named>::deserialize(deserializer)?;
+    Ok(world.map(|world| World {
+        id: world.id.into(),
+        name: world.name.into(),
+    }))
+}
+
+fn week_worlds<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(Vec<u8>, World<'static>)>, D::Error> {
+    #[derive(Deserialize)]
+    struct WeekWorld {
+        weeks: Vec<u8>,
+        id: String,
+        name: String,
+    }
+
+    let worlds = Vec::<WeekWorld>::deserialize(deserializer)?;
+    Ok(worlds
+        .into_iter()
+        .map(|world| {
+            let named = World {
+                id: world.id.into(),
+                name: world.name.into(),
+            };
+            (world.weeks, named)
+        })
+        .collect())
+}
+
+pub fn read(path: &Path) -> miette::Result<Data> {
+    let content = fs::read(path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Reading {} failed.", path.display()))?;
+    serde_json::from_slice(&content)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("{} is not compiled calendar data.", path.display()))
+}
+
+/// Writes the formats chosen in `args` rendered from the data.json at `path` to `output`, with the
+/// compressed copies of data.json and the schema if they are asked for.
+pub fn render(path: &Path, output: &Path, args: &CompileArgs) -> ExitCode {
+    let mirror = match read(path) {
+        Ok(mirror) => mirror,
+        Err(e) => {
+            eprintln!("{e:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut meta = mirror.meta();
+    // The data may have been compiled with the other kind of keys.
+    meta.features.remove(&Feature::LongKeys);
+    if args.keys == Keys::Long {
+        meta.features.insert(Feature::LongKeys);
+    }
+    if args.compat_platforms {
+        meta.features.remove(&Feature::MobilePlatforms);
+    }
+    let events = mirror.events();
+    let data = output::Data {
+        meta: &meta,
+        events: &events,
+        zones: mirror.zones(),
+        redirects: mirror.redirects(),
+        keys: args.keys,
+        compat_platforms: args.compat_platforms,
+    };
+
+    let mut rendered = Vec::new();
+    for format in args.formats.iter().collect::<BTreeSet<_>>() {
+        let format = format.output_format();
+        match format
+            .render(&data)
+            .wrap_err_with(|| format!("Rendering {} failed.", format.file_name()))
+        {
+            Ok(content) => rendered.push((format.file_name().to_owned(), content)),
+            Err(e) => {
+                eprintln!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if args.precompress {
+        if let Err(e) = compress::add_copies("data.json", &mut rendered) {
+            eprintln!("{e:?}");
+            return ExitCode::FAILURE;
+        }
+    }
+    if args.emit_schema {
+        match output::schema(args.keys)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Rendering {} failed.", output::SCHEMA_FILE_NAME))
+        {
+            Ok(content) => rendered.push((output::SCHEMA_FILE_NAME.to_owned(), content)),
+            Err(e) => {
+                eprintln!("{e:?}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if let Err(e) = fs::create_dir_all(output)
+        .into_diagnostic()
+        .wrap_err("Could not create output directory")
+    {
+        eprintln!("{e:?}");
+        return ExitCode::FAILURE;
+    }
+    for (name, content) in &rendered {
+        if let Err(e) = safely_save(output, name, &args.retry, |t| {
+            t.write_all(content).into_diagnostic()
+        }) {
+            eprintln!("{e:?}");
+            return ExitCode::FAILURE;
+        }
+    }
+    if let Some(summary) = compress::summary("data.json", &rendered) {
+        eprintln!("{summary}");
+    }
+    ExitCode::SUCCESS
+}
+
+impl Data {
+    pub fn meta(&self) -> output::Meta<'_> {
+        let meta = &self.meta;
+        output::Meta {
+            title: &meta.title,
+            description: meta.description.as_deref(),
+            link: meta.link.as_deref(),
+            home_timezone: meta.home_timezone.as_deref(),
+            compiled_time: meta.compiled_time,
+            languages: meta
+                .languages
+                .iter()
+                .map(|(language_id, language)| {
+                    let language = output::MetaLanguage {
+                        title: language.title.as_deref(),
+                        description: language.description.as_deref(),
+                        link: language.link.as_deref(),
+                    };
+                    (language_id.clone(), language)
+                })
+                .collect(),
+            fallbacks: meta.fallbacks.clone(),
+            series: meta.series.iter().map(String::as_str).collect(),
+            features: meta.features.clone(),
+        }
+    }
+
+    pub fn events(&self) -> Vec<output::Event<'_>> {
+        self.events.iter().map(Event::lend).collect()
+    }
+
+    pub fn zones(&self) -> &BTreeMap<String, Zone> {
+        &self.zones
+    }
+
+    pub fn redirects(&self) -> BTreeMap<&str, &str> {
+        self.redirects
+            .iter()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect()
+    }
+}
+
+impl Event {
+    fn lend(&self) -> output::Event<'_> {
+        output::Event {
+            name: self.name.as_str().into(),
+            start_date: self.start_date,
+            end_date: self.end_date,
+            every: self.every,
+            anchor: self.anchor,
+            monthly: self.monthly,
+            dates: self.dates.clone(),
+            info: self.info.lend(),
+            timezone: &self.timezone,
+            start: self.start,
+            duration: self.duration,
+            platforms: &self.platforms,
+            days: self.days.lend(),
+            languages: self
+                .languages
+                .iter()
+                .map(|(language_id, language)| (language_id.clone(), language.lend()))
+                .collect(),
+            canceled: self.canceled.clone(),
+            canceled_reasons: lend_reasons(&self.canceled_reasons),
+            confirmed: self.confirmed.clone(),
+            tentative: self.tentative.clone(),
+            skipped: self.skipped.clone(),
+            hiatus: self.hiatus.as_ref().map(|hiatus| output::Hiatus {
+                from: hiatus.from,
+                until: hiatus.until,
+                reason: hiatus.reason.as_deref(),
+            }),
+            overrides: self
+                .overrides
+                .iter()
+                .map(|(&date, change)| {
+                    let change = output::Override {
+                        name: change.name.as_deref(),
+                        start: change.start,
+                        duration: change.duration,
+                        world: change.world.as_ref(),
+                        poster: change.poster,
+                        poster_credit: change.poster_credit.as_deref(),
+                        poster_license: change.poster_license.as_deref(),
+                    };
+                    (date, change)
+                })
+                .collect(),
+            notes: lend_reasons(&self.notes),
+            rsvps: self.rsvps.clone(),
+            featured: self.featured,
+            series: self.series.as_deref(),
+            related: self.related.clone(),
+            unstable: self.unstable.clone(),
+        }
+    }
+}
+
+impl EventDays {
+    fn lend(&self) -> output::EventDays<'_> {
+        output::EventDays {
+            monday: self.monday.as_ref().map(EventDay::lend),
+            tuesday: self.tuesday.as_ref().map(EventDay::lend),
+            wednesday: self.wednesday.as_ref().map(EventDay::lend),
+            thursday: self.thursday.as_ref().map(EventDay::lend),
+            friday: self.friday.as_ref().map(EventDay::lend),
+            saturday: self.saturday.as_ref().map(EventDay::lend),
+            sunday: self.sunday.as_ref().map(EventDay::lend),
+        }
+    }
+}
+
+impl EventDay {
+    fn lend(&self) -> output::EventDay<'_> {
+        output::EventDay {
+            name: self.name.as_deref(),
+            // data.json doesn't have the start times of days.
+            start: None,
+            duration: self.duration,
+            note: self.note.as_deref(),
+            platforms: self
+                .platforms
+                .iter()
+                .map(|(&platform, times)| {
+                    let times = PlatformOverride {
+                        start: times.start,
+                        duration: times.duration,
+                    };
+                    (platform, times)
+                })
+                .collect(),
+            worlds: self
+                .worlds
+                .iter()
+                .map(|(weeks, world)| output::WeekWorld {
+                    weeks: weeks.clone(),
+                    world,
+                })
+                .collect(),
+            info: self.info.lend(),
+        }
+    }
+}
+
+impl EventLanguage {
+    fn lend(&self) -> output::EventLanguage<'_> {
+        output::EventLanguage {
+            name: self.name.as_deref(),
+            info: self.info.lend(),
+            canceled_reasons: lend_reasons(&self.canceled_reasons),
+            hiatus_reason: self.hiatus_reason.as_deref(),
+            notes: lend_reasons(&self.notes),
+            days: self.days.lend(),
+        }
+    }
+}
+
+impl EventInfo {
+    fn lend(&self) -> output::EventInfo<'_> {
+        output::EventInfo {
+            poster: self.poster,
+            poster_credit: self.poster_credit.as_deref(),
+            poster_license: self.poster_license.as_deref(),
+            color: self.color,
+            web: self.web.as_deref(),
+            discord: self.discord.as_deref(),
+            group: self.group.as_deref(),
+            hashtag: self.hashtag.as_ref().map(|hashtag| match hashtag {
+                Hashtag::Safe(tag) => output::Hashtag::Safe(tag),
+                Hashtag::Escaped { display, escaped } => output::Hashtag::Escaped {
+                    display,
+                    escaped: escaped.clone(),
+                },
+            }),
+            twitter: self.twitter.as_deref(),
+            bluesky: self.bluesky.clone(),
+            misskey: self.misskey.clone(),
+            youtube: self.youtube.clone(),
+            twitch: self.twitch.clone(),
+            links: &self.links,
+            join: &self.join,
+            organizers: &self.organizers,
+            world: self.world.as_ref(),
+            weeks: self.weeks.as_deref(),
+            description: self.description.as_deref().map(Cow::Borrowed),
+            description_runs: self.description_runs.clone(),
+            tags: &self.tags,
+            capacity: self.capacity,
+            instance_type: self.instance_type,
+            join_via: self.join_via,
+            open_before: self.open_before,
+            adult: self.adult,
+        }
+    }
+}
+
+fn lend_reasons(reasons: &BTreeMap<NaiveDate, String>) -> BTreeMap<NaiveDate, &str> {
+    reasons
+        .iter()
+        .map(|(&date, reason)| (date, reason.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::Format;
+
+    #[test]
+    fn links_keep_their_labels_and_order() {
+        let data: Data = serde_json::from_str(
+            r#"{
+                "meta": { "title": "Events", "ts": 1700000000, "features": [] },
+                "events": [{
+                    "name": "Party",
+                    "links": { "s": "https://example.com/s", "b": "https://example.com/b" },
+                    "tz": "Asia/Tokyo",
+                    "start": 1260,
+                    "duration": 60,
+                    "platforms": ["pc", "android"],
+                    "saturday": {}
+                }],
+                "zones": {}
+            }"#,
+        )
+        .unwrap();
+        let meta = data.meta();
+        let events = data.events();
+        for compat_platforms in [false, true] {
+            let output = output::Data {
+                meta: &meta,
+                events: &events,
+                zones: data.zones(),
+                redirects: data.redirects(),
+                keys: Keys::Long,
+                compat_platforms,
+            };
+            let json = Format::Json.output_format().render(&output).unwrap();
+            let json = String::from_utf8(json).unwrap();
+            assert!(
+                json.contains(
+                    r#""links":{"s":"https://example.com/s","b":"https://example.com/b"}"#
+                ),
+                "{json}",
+            );
+        }
+    }
+
+    #[test]
+    fn runs_are_spelled_out_and_links_named_like_them_are_not() {
+        let data: Data = serde_json::from_str(
+            r#"{
+                "meta": { "title": "Events", "ts": 1700000000, "features": [] },
+                "events": [{
+                    "name": "Party",
+                    "desc": "Bring a mic",
+                    "desc_runs": [{ "t": "Bring " }, { "t": "a mic", "b": true, "i": true }],
+                    "links": { "t": "https://example.com/t", "b": "https://example.com/b" },
+                    "tz": "Asia/Tokyo",
+                    "start": 1260,
+                    "duration": 60,
+                    "platforms": ["pc"],
+                    "saturday": {}
+                }],
+                "zones": {}
+            }"#,
+        )
+        .unwrap();
+        let meta = data.meta();
+        let events = data.events();
+        let output = output::Data {
+            meta: &meta,
+            events: &events,
+            zones: data.zones(),
+            redirects: data.redirects(),
+            keys: Keys::Long,
+            compat_platforms: false,
+        };
+        let json = Format::Json.output_format().render(&output).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(
+            json.contains(
+                r#""description_runs":[{"text":"Bring "},{"text":"a mic","bold":true,"italic":true}]"#
+            ),
+            "{json}",
+        );
+        assert!(
+            json.contains(r#""links":{"t":"https://example.com/t","b":"https://example.com/b"}"#),
+            "{json}",
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+    sync::Arc,
+};
+
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use miette::{miette, Context, IntoDiagnostic, NamedSource, Result};
+use serde::Deserialize;
+
+use crate::{error::EventParseError, input};
+
+#[derive(clap::Args)]
+pub struct NewArgs {
+    /// The name of the event.
+    name: String,
+    /// The directory to create the event file in.
+    #[arg(long, default_value = ".")]
+    dir: PathBuf,
+    /// The IANA time zone name, like America/New_York.
+    #[arg(long)]
+    timezone: Option<String>,
+    /// The start time, like 22:00.
+    #[arg(long)]
+    start: Option<String>,
+    /// The duration, like 1:30.
+    #[arg(long)]
+    duration: Option<String>,
+    /// The days of the week the event is held. The event is daily if no days are given.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    days: Vec<Day>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Day {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Day {
+    fn name(self) -> &'static str {
+        match self {
+            Day::Monday => "monday",
+            Day::Tuesday => "tuesday",
+            Day::Wednesday => "wednesday",
+            Day::Thursday => "thursday",
+            Day::Friday => "friday",
+            Day::Saturday => "saturday",
+            Day::Sunday => "sunday",
+        }
+    }
+}
+
+pub fn run(args: NewArgs) -> ExitCode {
+    match create(args) {
+        Ok(path) => {
+            eprintln!("Created {}", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{error:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn create(args: NewArgs) -> Result<PathBuf> {
+    let timezone = match args.timezone {
+        Some(timezone) => timezone,
+        None => prompt("--timezone", "Time zone (like America/New_York)", None)?,
+    };
+    if Tz::from_str(&timezone).is_err() {
+        return Err(miette!("Unknown time zone {timezone:?}"));
+    }
+    let start = match args.start {
+        Some(start) => start,
+        None => prompt("--start", "Start time (like 22:00)", None)?,
+    };
+    let duration = match args.duration {
+        Some(duration) => duration,
+        None => prompt("--duration", "Duration", Some("1:00"))?,
+    };
+
+    let file_stem = file_stem(&args.name);
+    let content = render(
+        &args.name, &file_stem, &timezone, &start, &duration, &args.days,
+    );
+
+    let path = args.dir.join(format!("{file_stem}.toml"));
+    let content = Arc::new(content);
+    input::Event::deserialize(toml::Deserializer::new(&content))
+        .map_err(|error| EventParseError {
+            src: NamedSource::new(path.to_string_lossy(), content.clone()),
+            location: error.span().map(|s| s.into()),
+            error,
+        })
+        .wrap_err("The event file would not be valid.")?;
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not create {}", path.display()))?;
+    Ok(path)
+}
+
+/// Asks for a value that wasn't given on the command line.
+fn prompt(flag: &str, question: &str, default: Option<&str>) -> Result<String> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(miette!(
+            "{flag} must be specified when not running interactively."
+        ));
+    }
+    loop {
+        match default {
+            Some(default) => eprint!("{question} [{default}]: "),
+            None => eprint!("{question}: "),
+        }
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line).into_diagnostic()?;
+        let line = line.trim();
+        if !line.is_empty() {
+            return Ok(line.to_owned());
+        }
+        if let Some(default) = default {
+            return Ok(default.to_owned());
+        }
+    }
+}
+
+/// Replaces characters that can't be used in file names on common platforms.
+fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn render(
+    name: &str,
+    file_stem: &str,
+    timezone: &str,
+    start: &str,
+    duration: &str,
+    days: &[Day],
+) -> String {
+    let quote = |s: &str| toml::Value::String(s.to_owned()).to_string();
+
+    let mut content = String::new();
+    if name != file_stem {
+        writeln!(content, "name = {}", quote(name)).unwrap();
+    }
+    writeln!(content, "timezone = {}", quote(timezone)).unwrap();
+    writeln!(content, "start = {}", quote(start)).unwrap();
+    writeln!(content, "duration = {}", quote(duration)).unwrap();
+    content.push_str(
+        r#"
+# The rest are optional.
+
+# description = "This is my cool event."
+# group = "MYGRP.2493"
+# platforms = ["pc", "quest"]
+# hashtag = "MyEvent"
+# web = "https://example.com/"
+# twitter = "MyEvent"
+# discord = "nRszqyu"
+# start_date = "2023-06-26"
+# end_date = "2023-07-31"
+"#,
+    );
+
+    let all_days = Day::value_variants();
+    if !days.is_empty() && !all_days.iter().all(|d| days.contains(d)) {
+        content.push('\n');
+        for day in all_days.iter().filter(|d| days.contains(d)) {
+            writeln!(content, "[days.{}]", day.name()).unwrap();
+        }
+    }
+
+    content.push_str(
+        r#"
+# If the event is in a public instance, specify the world.
+# [world]
+# id = "wrld_a97970e3-8d89-41ae-82d8-6340e29385df"
+# name = "My event world"
+
+# If the event is friends+ or friends-only, list the organizers.
+# [[join]]
+# id = "usr_0f7ecc5d-1c48-4bd3-b490-5ca7850e358d"
+# name = "Organizer A"
+"#,
+    );
+    content
+}
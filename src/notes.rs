@@ -0,0 +1,171 @@
+//! notes.json, a flat list of what is special about the upcoming occurrences.
+//!
+//! Notification bots can diff this list between compiles and post the new entries, without
+//! knowing how days, languages, overrides and cancellations combine in data.json.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use miette::Result;
+use serde::Serialize;
+
+use crate::{input, resolved, Event, Language};
+
+#[derive(Serialize)]
+pub struct Feed<'a> {
+    #[serde(rename = "ts")]
+    pub compiled_time: i64,
+    /// How many days ahead the entries go.
+    pub days: u32,
+    pub notes: Vec<Entry<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct Entry<'a> {
+    /// The event's id.
+    pub event: Cow<'a, str>,
+    pub name: Cow<'a, str>,
+    pub kind: Kind,
+    pub date: NaiveDate,
+    /// The start of the occurrence, as a Unix timestamp. Breaks don't have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    /// The last day of a break.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    /// The note, the name of a special occurrence, or the reason for a cancellation or break.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<&'a str>,
+    #[serde(rename = "lang", skip_serializing_if = "BTreeMap::is_empty")]
+    pub languages: BTreeMap<Language, &'a str>,
+}
+
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// A note for the occurrence, or a day's note that expires, so it is about particular
+    /// occurrences.
+    Note,
+    /// An occurrence with a different name or time from `overrides`.
+    Substitution,
+    Canceled,
+    Hiatus,
+}
+
+pub fn collect<'a>(
+    events: &[&'a Event<'a>],
+    days_off: &'a resolved::DaysOff,
+    now: DateTime<Utc>,
+    days: u32,
+) -> Result<Feed<'a>> {
+    let mut notes = Vec::new();
+    for &event in events {
+        collect_event(event, days_off, now, days, &mut notes)?;
+    }
+    notes.sort_by_key(|note| (note.date, note.start, note.kind));
+    Ok(Feed {
+        compiled_time: now.timestamp(),
+        days,
+        notes,
+    })
+}
+
+fn collect_event<'a>(
+    event: &'a Event<'a>,
+    days_off: &'a resolved::DaysOff,
+    now: DateTime<Utc>,
+    days: u32,
+    notes: &mut Vec<Entry<'a>>,
+) -> Result<()> {
+    let input = &event.event;
+    let resolved = resolved::Event::resolve(event, days_off)?;
+    let today = now.with_timezone(&resolved.timezone).date_naive();
+    let last = today + Days::new(days.into());
+    let entry = |kind, date, text| Entry {
+        event: event.id(),
+        name: event.name(),
+        kind,
+        date,
+        start: None,
+        until: None,
+        text,
+        languages: BTreeMap::new(),
+    };
+
+    if let Some(hiatus) = &input.hiatus {
+        let hiatus = hiatus.as_ref();
+        if today <= hiatus.until && hiatus.from <= last {
+            notes.push(Entry {
+                until: Some(hiatus.until),
+                languages: translations(input, |language| language.hiatus_reason.as_deref()),
+                ..entry(Kind::Hiatus, hiatus.from, hiatus.reason.as_deref())
+            });
+        }
+    }
+
+    for occurrence in resolved.current_occurrences(now, last) {
+        let date = occurrence.date;
+        let start = Some(occurrence.start.timestamp());
+        if occurrence.canceled {
+            notes.push(Entry {
+                start,
+                languages: translations(input, |language| {
+                    language.canceled_reasons.get(&date).map(|r| r.as_ref())
+                }),
+                ..entry(Kind::Canceled, date, occurrence.canceled_reason)
+            });
+            // Nothing else about the occurrence matters once it's canceled.
+            continue;
+        }
+        if input.overrides.contains_key(&date) {
+            notes.push(Entry {
+                start,
+                ..entry(Kind::Substitution, date, occurrence.name)
+            });
+        }
+        let current = |note: Option<&'a input::Note<'a>>| {
+            note.filter(|note| note.expires.as_ref().is_some_and(|e| date <= *e.as_ref()))
+                .map(|note| note.text.as_ref())
+        };
+        let languages = translations(input, |language| {
+            let day = language
+                .days
+                .iter()
+                .find(|(weekday, _)| *weekday == date.weekday())?
+                .1;
+            current(day.note.as_ref())
+        });
+        let text = current(occurrence.day.note);
+        if text.is_some() || !languages.is_empty() {
+            notes.push(Entry {
+                start,
+                languages,
+                ..entry(Kind::Note, date, text)
+            });
+        }
+        let languages = translations(input, |language| {
+            language.notes.get(&date).map(|note| note.as_ref())
+        });
+        let text = input.notes.get(&date).map(|note| note.as_ref());
+        if text.is_some() || !languages.is_empty() {
+            notes.push(Entry {
+                start,
+                languages,
+                ..entry(Kind::Note, date, text)
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The text for each language that has one.
+fn translations<'a>(
+    event: &'a input::Event<'a>,
+    text: impl Fn(&'a input::EventLanguage<'a>) -> Option<&'a str>,
+) -> BTreeMap<Language, &'a str> {
+    event
+        .languages
+        .iter()
+        .filter_map(|(language_id, language)| Some((language_id.clone(), text(language)?)))
+        .collect()
+}
@@ -1,24 +1,240 @@
-use std::{borrow::Cow, collections::BTreeMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroU16,
+};
 
-use chrono::NaiveDate;
-use serde::Serialize;
+use chrono::{NaiveDate, Weekday};
+use schemars::{generate::SchemaSettings, JsonSchema};
+use serde::{ser::Error, Deserialize, Serialize, Serializer};
 
-use crate::{Language, Platform, User, World};
+use crate::{
+    input::Links,
+    rename::{Rename, Renamed},
+    InstanceType, JoinVia, Language, Organizer, Platform, User, World,
+};
 
-#[derive(Serialize)]
+#[derive(JsonSchema, Serialize)]
 pub struct Data<'a> {
     pub meta: &'a Meta<'a>,
     pub events: &'a [Event<'a>],
     pub zones: &'a BTreeMap<String, Zone>,
+    /// Old event ids, and the ids of the events they were renamed to.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub redirects: BTreeMap<&'a str, &'a str>,
+    /// How the keys of data.json and data.msgpack are written.
+    #[serde(skip)]
+    pub keys: Keys,
+    /// Whether Android and iOS are written as Quest in data.json and data.msgpack.
+    #[serde(skip)]
+    pub compat_platforms: bool,
+}
+
+impl<'a> Data<'a> {
+    /// The data, serialized with the keys and platforms written as chosen.
+    pub fn written(&'a self) -> Written<'a> {
+        Written(self)
+    }
+}
+
+/// Serializes data.json and data.msgpack with the keys and platforms written as chosen.
+pub struct Written<'a>(&'a Data<'a>);
+
+impl Serialize for Written<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = Renamed {
+            value: self.0,
+            rename: self.0.keys.rename(),
+        };
+        if !self.0.compat_platforms {
+            return data.serialize(serializer);
+        }
+        let mut value = serde_json::to_value(&data).map_err(S::Error::custom)?;
+        fold_mobile_platforms(&mut value);
+        value.serialize(serializer)
+    }
+}
+
+/// The names used for keys in the output.
+#[derive(Clone, Copy, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum Keys {
+    /// Abbreviated keys like `tz` and `desc`, which keep the output small for the world.
+    #[default]
+    Short,
+    /// Spelled out keys like `timezone` and `description`, for other tools.
+    Long,
+}
+
+impl Keys {
+    /// Gives the name each field is written with.
+    fn rename(self) -> Rename {
+        match self {
+            Keys::Short => |_| None,
+            Keys::Long => spelled_out,
+        }
+    }
+}
+
+/// The abbreviated keys, and their spelled out names.
+const LONG_KEYS: &[(&str, &str)] = &[
+    ("desc", "description"),
+    ("desc_runs", "description_runs"),
+    ("home_tz", "home_timezone"),
+    ("ts", "compiled_time"),
+    ("lang", "languages"),
+    ("tz", "timezone"),
+    ("n", "number"),
+    ("w", "width"),
+    ("h", "height"),
+    ("r", "offsets"),
+    ("s", "start"),
+    ("o", "offset"),
+    ("cap", "capacity"),
+    ("inst", "instance_type"),
+    ("via", "join_via"),
+    ("t", "text"),
+    ("b", "bold"),
+    ("i", "italic"),
+];
+
+/// Keys whose values are copied from the input as they are.
+const VERBATIM_KEYS: &[&str] = &["unstable"];
+
+/// Platforms that worlds made before them don't know, and which run the same builds of worlds as
+/// Quest.
+const MOBILE_PLATFORMS: &[&str] = &["android", "ios"];
+
+/// Writes Android and iOS as Quest in serialized output. Platform overrides for Quest take
+/// precedence over the ones for the other platforms.
+fn fold_mobile_platforms(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                match value {
+                    _ if VERBATIM_KEYS.contains(&key.as_str()) => {}
+                    serde_json::Value::Array(platforms) if key == "platforms" => {
+                        let mut seen = Vec::new();
+                        platforms.retain_mut(|platform| {
+                            if MOBILE_PLATFORMS.iter().any(|&mobile| *platform == mobile) {
+                                *platform = "quest".into();
+                            }
+                            let new = !seen.contains(platform);
+                            seen.push(platform.clone());
+                            new
+                        });
+                    }
+                    serde_json::Value::Object(overrides) if key == "platforms" => {
+                        for mobile in MOBILE_PLATFORMS {
+                            if let Some(platform_override) = overrides.remove(*mobile) {
+                                overrides.entry("quest").or_insert(platform_override);
+                            }
+                        }
+                    }
+                    value => fold_mobile_platforms(value),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(fold_mobile_platforms),
+        _ => {}
+    }
+}
+
+/// The spelled out name of a field, if it is abbreviated.
+fn spelled_out(key: &str) -> Option<&'static str> {
+    LONG_KEYS
+        .iter()
+        .find(|(short, _)| *short == key)
+        .map(|(_, long)| *long)
+}
+
+/// The file the JSON Schema of data.json is written to.
+pub const SCHEMA_FILE_NAME: &str = "data.schema.json";
+
+/// A JSON Schema of data.json and data.msgpack with the keys written as chosen, derived from the
+/// types in this module.
+pub fn schema(keys: Keys) -> serde_json::Result<Vec<u8>> {
+    let mut schema = crate::schema::generator(SchemaSettings::draft2020_12().for_serialize())
+        .into_root_schema_for::<Data>()
+        .to_value();
+    if keys == Keys::Long {
+        spell_out_schema_keys(&mut schema);
+    }
+    let mut json = serde_json::to_vec_pretty(&schema)?;
+    json.push(b'\n');
+    Ok(json)
+}
+
+/// Replaces the abbreviated property names in a schema with their spelled out names, like
+/// `Keys::Long` does for the data.
+fn spell_out_schema_keys(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                match value {
+                    serde_json::Value::Object(properties) if key == "properties" => {
+                        *properties = std::mem::take(properties)
+                            .into_iter()
+                            .map(|(name, mut property)| {
+                                spell_out_schema_keys(&mut property);
+                                let name = spelled_out(&name).map_or(name, str::to_owned);
+                                (name, property)
+                            })
+                            .collect();
+                    }
+                    serde_json::Value::Array(names) if key == "required" => {
+                        for name in names {
+                            if let serde_json::Value::String(name) = name {
+                                if let Some(long) = spelled_out(name) {
+                                    *name = long.to_owned();
+                                }
+                            }
+                        }
+                    }
+                    value => spell_out_schema_keys(value),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(spell_out_schema_keys),
+        _ => {}
+    }
 }
 
+/// The version of the data.json structure. Additions are announced with `Feature` instead.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A summary of data.json that loaders read first, to decide whether they can handle it.
 #[derive(Serialize)]
+pub struct Loader {
+    pub version: u32,
+    /// The size of data.json.
+    pub bytes: usize,
+    /// The number of poster slots in use.
+    pub posters: usize,
+    /// The recommended polling interval in seconds.
+    pub poll: i64,
+}
+
+#[derive(JsonSchema, Serialize)]
 pub struct Event<'a> {
     pub name: Cow<'a, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_date: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end_date: Option<i64>,
+    /// The event is held every this many weeks, counting from the week of `anchor`. Weeks start
+    /// on Monday.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub every: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<NaiveDate>,
+    /// The event is held on this day of every month, or on the last day of shorter months. All
+    /// days of the week are listed, and the event is only held on this day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly: Option<u8>,
+    /// The upcoming dates of an event that only happens on these dates. This is empty once they
+    /// have all passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dates: Option<Vec<NaiveDate>>,
     #[serde(flatten)]
     pub info: EventInfo<'a>,
     #[serde(rename = "tz")]
@@ -32,11 +248,44 @@ pub struct Event<'a> {
     pub languages: BTreeMap<Language, EventLanguage<'a>>,
     #[serde(skip_serializing_if = "DateSet::is_none")]
     pub canceled: DateSet,
+    /// Why the event is canceled on some of the dates in `canceled`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub canceled_reasons: BTreeMap<NaiveDate, &'a str>,
     #[serde(skip_serializing_if = "DateSet::is_all")]
     pub confirmed: DateSet,
+    /// Dates that may still change, which can be shown differently from confirmed ones.
+    #[serde(skip_serializing_if = "DateSet::is_none")]
+    pub tentative: DateSet,
+    /// Dates the event is not held, which aren't shown at all.
+    #[serde(skip_serializing_if = "DateSet::is_none")]
+    pub skipped: DateSet,
+    /// A current or upcoming break, for showing that the event is on a break.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hiatus: Option<Hiatus<'a>>,
+    /// Changes to upcoming occurrences.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<NaiveDate, Override<'a>>,
+    /// Notes about upcoming occurrences.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<NaiveDate, &'a str>,
+    /// How many people have signed up for upcoming occurrences.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rsvps: BTreeMap<NaiveDate, u16>,
+    /// The event is shown in the world's pickup slots.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub featured: bool,
+    /// The id of the series the event belongs to, which is listed in `Meta::series`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<&'a str>,
+    /// Indexes of related events in `Data::events`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<usize>,
+    /// Fields that are being tried out, as they were written in the event file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unstable: Option<serde_json::Value>,
 }
 
-#[derive(Serialize)]
+#[derive(JsonSchema, Serialize)]
 pub struct EventDays<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub monday: Option<EventDay<'a>>,
@@ -54,41 +303,109 @@ pub struct EventDays<'a> {
     pub sunday: Option<EventDay<'a>>,
 }
 
-#[derive(Serialize)]
+#[derive(JsonSchema, Serialize)]
 pub struct EventDay<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<&'a str>,
+    /// The start time of the day, for the formats rendered from this model. It isn't part of
+    /// data.json, which has always left it out.
+    #[serde(skip)]
+    pub start: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub platforms: BTreeMap<Platform, PlatformOverride>,
+    /// The worlds of an event that rotates between worlds by the week of the month, instead of
+    /// `world`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub worlds: Vec<WeekWorld<'a>>,
     #[serde(flatten)]
     pub info: EventInfo<'a>,
 }
 
-#[derive(Serialize)]
+#[derive(JsonSchema, Serialize)]
+pub struct WeekWorld<'a> {
+    /// The weeks of the month the world is used on.
+    pub weeks: Vec<u8>,
+    #[serde(flatten)]
+    pub world: &'a World<'a>,
+}
+
+#[derive(JsonSchema, Serialize)]
+pub struct Override<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub world: Option<&'a World<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster: Option<PosterInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster_credit: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster_license: Option<&'a str>,
+}
+
+#[derive(JsonSchema, Serialize)]
+pub struct Hiatus<'a> {
+    pub from: NaiveDate,
+    /// The last day of the break.
+    pub until: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'a str>,
+}
+
+#[derive(Deserialize, JsonSchema, Serialize)]
+pub struct PlatformOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<i32>,
+}
+
+#[derive(JsonSchema, Serialize)]
 pub struct EventLanguage<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<&'a str>,
     #[serde(flatten)]
     pub info: EventInfo<'a>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub canceled_reasons: BTreeMap<NaiveDate, &'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hiatus_reason: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<NaiveDate, &'a str>,
     #[serde(flatten)]
     pub days: EventDays<'a>,
 }
 
-#[derive(Clone, Copy, Serialize)]
+#[derive(Clone, Copy, Deserialize, JsonSchema, Serialize)]
 pub struct PosterInfo {
-    #[serde(rename = "n")]
+    #[serde(rename = "n", alias = "number")]
     pub number: u8,
-    #[serde(rename = "w")]
+    #[serde(rename = "w", alias = "width")]
     pub width: u16,
-    #[serde(rename = "h")]
+    #[serde(rename = "h", alias = "height")]
     pub height: u16,
 }
 
-#[derive(Serialize)]
+#[derive(JsonSchema, Serialize)]
 pub struct EventInfo<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub poster: Option<PosterInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster_credit: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poster_license: Option<&'a str>,
+    /// The accent color as `0xrrggbb`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub discord: Option<&'a str>,
@@ -98,31 +415,64 @@ pub struct EventInfo<'a> {
     pub hashtag: Option<Hashtag<'a>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub twitter: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bluesky: Option<Account>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub misskey: Option<Account>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub youtube: Option<Account>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub twitch: Option<Account>,
+    #[serde(skip_serializing_if = "Links::is_empty")]
+    pub links: &'a Links<'a>,
     #[serde(skip_serializing_if = "<[_]>::is_empty")]
     pub join: &'a [User<'a>],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub organizers: &'a [Organizer<'a>],
     #[serde(skip_serializing_if = "Option::is_none")]
     pub world: Option<&'a World<'a>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub weeks: Option<&'a [u8]>,
+    /// The description without any markup.
     #[serde(rename = "desc", skip_serializing_if = "Option::is_none")]
-    pub description: Option<&'a str>,
+    pub description: Option<Cow<'a, str>>,
+    /// The description as runs of styled text, if it has any styles.
+    #[serde(rename = "desc_runs", skip_serializing_if = "Option::is_none")]
+    pub description_runs: Option<Vec<Run<'a>>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub tags: &'a [Cow<'a, str>],
+    #[serde(rename = "cap", skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<NonZeroU16>,
+    #[serde(rename = "inst", skip_serializing_if = "Option::is_none")]
+    pub instance_type: Option<InstanceType>,
+    #[serde(rename = "via", skip_serializing_if = "Option::is_none")]
+    pub join_via: Option<JoinVia>,
+    /// How many minutes before the start the instance opens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_before: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adult: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct Zone {
-    #[serde(rename = "r")]
+    #[serde(rename = "r", alias = "offsets")]
     pub offsets: Vec<Rule>,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct Rule {
-    #[serde(rename = "s", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "s", alias = "start", skip_serializing_if = "Option::is_none")]
     pub start: Option<i64>,
-    #[serde(rename = "o", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "o",
+        alias = "offset",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub offset: Option<i16>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum DateSet {
     All(bool),
@@ -137,22 +487,201 @@ impl DateSet {
     pub fn is_all(&self) -> bool {
         matches!(self, DateSet::All(true))
     }
+
+    pub fn none() -> Self {
+        DateSet::All(false)
+    }
+
+    pub fn all() -> Self {
+        DateSet::All(true)
+    }
+
+    pub fn contains(&self, date: &NaiveDate) -> bool {
+        match self {
+            DateSet::All(all) => *all,
+            DateSet::Dates(dates) => dates.contains(date),
+        }
+    }
 }
 
-#[derive(Serialize)]
+#[derive(JsonSchema, Serialize)]
 pub struct Meta<'a> {
     pub title: &'a str,
     #[serde(rename = "desc", skip_serializing_if = "Option::is_none")]
     pub description: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub link: Option<&'a str>,
+    #[serde(rename = "home_tz", skip_serializing_if = "Option::is_none")]
+    pub home_timezone: Option<&'a str>,
     #[serde(rename = "ts")]
     pub compiled_time: i64,
     #[serde(rename = "lang", skip_serializing_if = "BTreeMap::is_empty")]
     pub languages: BTreeMap<Language, MetaLanguage<'a>>,
+    /// The languages each language falls back to, in order, for events without a section for it.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fallbacks: BTreeMap<Language, Vec<Language>>,
+    /// The ids of all series, sorted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<&'a str>,
+    pub features: BTreeSet<Feature>,
 }
 
-#[derive(Serialize)]
+/// Optional structures that older frontends may not understand.
+#[derive(Clone, Copy, Deserialize, Eq, JsonSchema, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    Notes,
+    PlatformOverrides,
+    Related,
+    CanceledReasons,
+    Interval,
+    Dates,
+    Overrides,
+    Monthly,
+    Skipped,
+    Hiatus,
+    Tentative,
+    Redirects,
+    Rsvps,
+    Featured,
+    OccurrenceNotes,
+    RotatingWorlds,
+    Series,
+    StyledDescriptions,
+    /// Some events are on Android or iOS, which are written as platforms of their own.
+    MobilePlatforms,
+    /// Keys are spelled out, like `timezone` instead of `tz`.
+    LongKeys,
+    /// Some events have fields that are being tried out.
+    Unstable,
+}
+
+impl Event<'_> {
+    pub fn collect_features(&self, features: &mut BTreeSet<Feature>) {
+        if !self.related.is_empty() {
+            features.insert(Feature::Related);
+        }
+        if self.unstable.is_some() {
+            features.insert(Feature::Unstable);
+        }
+        if self.every.is_some() {
+            features.insert(Feature::Interval);
+        }
+        if self.dates.is_some() {
+            features.insert(Feature::Dates);
+        }
+        if !self.skipped.is_none() {
+            features.insert(Feature::Skipped);
+        }
+        if !self.tentative.is_none() {
+            features.insert(Feature::Tentative);
+        }
+        if self.hiatus.is_some() {
+            features.insert(Feature::Hiatus);
+        }
+        if self.monthly.is_some() {
+            features.insert(Feature::Monthly);
+        }
+        if !self.overrides.is_empty() {
+            features.insert(Feature::Overrides);
+        }
+        if !self.rsvps.is_empty() {
+            features.insert(Feature::Rsvps);
+        }
+        if self.featured {
+            features.insert(Feature::Featured);
+        }
+        if self.series.is_some() {
+            features.insert(Feature::Series);
+        }
+        if !self.notes.is_empty()
+            || self
+                .languages
+                .values()
+                .any(|language| !language.notes.is_empty())
+        {
+            features.insert(Feature::OccurrenceNotes);
+        }
+        if !self.canceled_reasons.is_empty()
+            || self
+                .languages
+                .values()
+                .any(|language| !language.canceled_reasons.is_empty())
+        {
+            features.insert(Feature::CanceledReasons);
+        }
+        if self.info.description_runs.is_some()
+            || self
+                .languages
+                .values()
+                .any(|language| language.info.description_runs.is_some())
+        {
+            features.insert(Feature::StyledDescriptions);
+        }
+        let mobile = |platform: &Platform| matches!(platform, Platform::Android | Platform::Ios);
+        if self.platforms.iter().any(mobile)
+            || self
+                .days
+                .iter()
+                .any(|(_, day)| day.platforms.keys().any(mobile))
+        {
+            features.insert(Feature::MobilePlatforms);
+        }
+        self.days.collect_features(features);
+        for language in self.languages.values() {
+            language.days.collect_features(features);
+        }
+    }
+}
+
+impl<'a> EventDays<'a> {
+    pub fn iter(&self) -> impl Iterator<Item = (Weekday, &EventDay<'_>)> {
+        [
+            (Weekday::Mon, &self.monday),
+            (Weekday::Tue, &self.tuesday),
+            (Weekday::Wed, &self.wednesday),
+            (Weekday::Thu, &self.thursday),
+            (Weekday::Fri, &self.friday),
+            (Weekday::Sat, &self.saturday),
+            (Weekday::Sun, &self.sunday),
+        ]
+        .into_iter()
+        .filter_map(|(weekday, day)| day.as_ref().map(|day| (weekday, day)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Weekday, &mut EventDay<'a>)> {
+        [
+            (Weekday::Mon, &mut self.monday),
+            (Weekday::Tue, &mut self.tuesday),
+            (Weekday::Wed, &mut self.wednesday),
+            (Weekday::Thu, &mut self.thursday),
+            (Weekday::Fri, &mut self.friday),
+            (Weekday::Sat, &mut self.saturday),
+            (Weekday::Sun, &mut self.sunday),
+        ]
+        .into_iter()
+        .filter_map(|(weekday, day)| day.as_mut().map(|day| (weekday, day)))
+    }
+
+    fn collect_features(&self, features: &mut BTreeSet<Feature>) {
+        for (_, day) in self.iter() {
+            if day.note.is_some() {
+                features.insert(Feature::Notes);
+            }
+            if !day.platforms.is_empty() {
+                features.insert(Feature::PlatformOverrides);
+            }
+            if !day.worlds.is_empty() {
+                features.insert(Feature::RotatingWorlds);
+            }
+            if day.info.description_runs.is_some() {
+                features.insert(Feature::StyledDescriptions);
+            }
+        }
+    }
+}
+
+#[derive(JsonSchema, Serialize)]
 pub struct MetaLanguage<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<&'a str>,
@@ -162,7 +691,48 @@ pub struct MetaLanguage<'a> {
     pub link: Option<&'a str>,
 }
 
-#[derive(Serialize)]
+/// A piece of a description with the same style. Each line break is a run of its own.
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
+pub struct Run<'a> {
+    #[serde(rename = "t", alias = "text")]
+    pub text: Cow<'a, str>,
+    #[serde(
+        rename = "b",
+        alias = "bold",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub bold: bool,
+    #[serde(
+        rename = "i",
+        alias = "italic",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<Cow<'a, str>>,
+}
+
+impl<'a> Run<'a> {
+    pub fn plain(text: Cow<'a, str>) -> Self {
+        Run {
+            text,
+            bold: false,
+            italic: false,
+            url: None,
+        }
+    }
+}
+
+/// An account on a social platform, with the address of its page.
+#[derive(Clone, Deserialize, JsonSchema, Serialize)]
+pub struct Account {
+    pub handle: String,
+    pub url: String,
+}
+
+#[derive(JsonSchema, Serialize)]
 #[serde(untagged)]
 pub enum Hashtag<'a> {
     Safe(&'a str),
@@ -0,0 +1,220 @@
+use std::{path::PathBuf, process::ExitCode, str::FromStr};
+
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use miette::Result;
+
+use crate::{
+    collect_files, discover::FileFilter, input, parse_events, read_days_off, read_event_files,
+    report::Errors, resolved, Event,
+};
+
+#[derive(clap::Args)]
+pub struct PreviewDateArgs {
+    /// The directory containing the event files.
+    input: PathBuf,
+    /// The date to show, in the time zone given by `--timezone`.
+    date: NaiveDate,
+    #[command(flatten)]
+    filter: FileFilter,
+    /// The time zone of the calendar's viewer.
+    #[arg(long, default_value = "UTC", value_parser = parse_time_zone)]
+    timezone: Tz,
+}
+
+fn parse_time_zone(name: &str) -> Result<Tz, String> {
+    Tz::from_str(name)
+}
+
+/// One line of the preview, with the details printed under it.
+struct Entry {
+    start: DateTime<Utc>,
+    lines: Vec<String>,
+}
+
+pub fn run(args: PreviewDateArgs, errors: Errors) -> ExitCode {
+    let files = collect_files(&args.input, &args.filter);
+    let event_files = read_event_files(&files, &errors);
+    let events = parse_events(&event_files, &errors);
+
+    let day_start = local_midnight(args.date, args.timezone);
+    let day_end = local_midnight(args.date + Days::new(1), args.timezone);
+    let days_off = read_days_off(&files, Utc::now());
+
+    let mut entries = Vec::new();
+    for event in &events {
+        match event_entries(event, &days_off, &args, day_start, day_end) {
+            Ok(found) => entries.extend(found),
+            Err(error) => eprintln!("{error:?}"),
+        }
+    }
+    entries.sort_by_key(|entry| entry.start);
+
+    println!("{} ({})", args.date.format("%Y-%m-%d %a"), args.timezone);
+    for entry in &entries {
+        for line in &entry.lines {
+            println!("{line}");
+        }
+    }
+    if entries.is_empty() {
+        println!("  No events");
+    }
+
+    if errors.count() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn local_midnight(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    // Midnight can be skipped by a change of offset, but some time on the date exists.
+    (0..24)
+        .find_map(|hour| {
+            date.and_time(NaiveTime::from_hms_opt(hour, 0, 0)?)
+                .and_local_timezone(timezone)
+                .earliest()
+        })
+        .map_or_else(
+            || Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)),
+            |time| time.with_timezone(&Utc),
+        )
+}
+
+/// The occurrences of `event` that overlap the viewer's day, including ones that are scheduled but
+/// not shown, since those are the ones that prompt the question.
+fn event_entries(
+    event: &Event,
+    days_off: &resolved::DaysOff,
+    args: &PreviewDateArgs,
+    day_start: DateTime<Utc>,
+    day_end: DateTime<Utc>,
+) -> Result<Vec<Entry>> {
+    let event = resolved::Event::resolve(event, days_off)?;
+    let input = &event.source.event;
+
+    let first = day_start.with_timezone(&event.timezone).date_naive();
+    let last = day_end.with_timezone(&event.timezone).date_naive();
+    // Only occurrences at least this long can reach the day from an earlier date.
+    let earlier = event.longest().num_days() as u64 + 1;
+
+    let mut entries = Vec::new();
+    for date in (first - Days::new(earlier)).iter_days() {
+        if last < date {
+            break;
+        }
+        if let Some(occurrence) = event.occurrence_on(date) {
+            if occurrence.end <= day_start || day_end <= occurrence.start {
+                continue;
+            }
+            let status = match (occurrence.canceled, occurrence.canceled_reason) {
+                (true, Some(reason)) => format!("canceled: {reason}"),
+                (true, None) => "canceled".to_owned(),
+                (false, _) if occurrence.tentative => "tentative".to_owned(),
+                (false, _) if occurrence.confirmed => "confirmed".to_owned(),
+                (false, _) => "unconfirmed".to_owned(),
+            };
+            let mut lines = vec![heading(
+                &event,
+                args.timezone,
+                occurrence.start,
+                occurrence.end,
+                &status,
+            )];
+            if let Some(name) = occurrence.name {
+                lines.push(format!("    Shown as {name}"));
+            }
+            if let Some(change) = input.overrides.get(&date) {
+                lines.push(format!("    Override for {date}: {}", describe(change)));
+            }
+            let note = occurrence.day.note.filter(|note| {
+                note.expires
+                    .as_ref()
+                    .is_none_or(|expires| date <= *expires.as_ref())
+            });
+            if let Some(note) = note {
+                lines.push(format!("    Note: {}", note.text));
+            }
+            if let Some(note) = input.notes.get(&date) {
+                lines.push(format!("    Note for {date}: {note}"));
+            }
+            entries.push(Entry {
+                start: occurrence.start.with_timezone(&Utc),
+                lines,
+            });
+        } else if let Some(day) = event.scheduled_on(date) {
+            let Some(start) = date
+                .and_time(day.start)
+                .and_local_timezone(event.timezone)
+                .earliest()
+            else {
+                continue;
+            };
+            let end = start + day.duration;
+            if end <= day_start || day_end <= start {
+                continue;
+            }
+            let hiatus = input
+                .hiatus
+                .as_ref()
+                .map(|hiatus| hiatus.as_ref())
+                .filter(|hiatus| hiatus.contains(date));
+            let status = match hiatus {
+                Some(input::Hiatus {
+                    reason: Some(reason),
+                    ..
+                }) => format!("not shown, on a break: {reason}"),
+                Some(_) => "not shown, on a break".to_owned(),
+                None => "not shown, skipped".to_owned(),
+            };
+            entries.push(Entry {
+                start: start.with_timezone(&Utc),
+                lines: vec![heading(&event, args.timezone, start, end, &status)],
+            });
+        }
+    }
+    Ok(entries)
+}
+
+fn heading(
+    event: &resolved::Event,
+    timezone: Tz,
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+    status: &str,
+) -> String {
+    format!(
+        "  {}-{}  {} {}  {} UTC  {} ({status})",
+        start.with_timezone(&timezone).format("%H:%M"),
+        end.with_timezone(&timezone).format("%H:%M"),
+        start.format("%a %H:%M"),
+        event.timezone,
+        start.with_timezone(&Utc).format("%a %H:%M"),
+        event.name,
+    )
+}
+
+/// Lists the fields an override changes.
+fn describe(change: &input::Override) -> String {
+    let mut fields = Vec::new();
+    if let Some(name) = &change.name {
+        fields.push(format!("name {name}"));
+    }
+    if let Some(start) = &change.start {
+        fields.push(format!("start {}", start.0.format("%H:%M")));
+    }
+    if let Some(duration) = &change.duration {
+        let minutes = duration.0.num_minutes();
+        fields.push(format!("duration {}:{:02}", minutes / 60, minutes % 60));
+    }
+    if let Some(world) = &change.world {
+        fields.push(format!("world {}", world.name));
+    }
+    if let Some(poster) = &change.poster {
+        fields.push(format!("poster {poster}"));
+    }
+    if change.poster_credit.is_some() || change.poster_license.is_some() {
+        fields.push("poster credit".to_owned());
+    }
+    fields.join(", ")
+}
@@ -0,0 +1,398 @@
+//! Serializing with the names of struct fields changed as they are written.
+//!
+//! Only the fields of structs are renamed. The keys of maps are data, like language codes, dates
+//! and link labels, so they are written as they are however they are spelled. Structs with
+//! flattened fields are serialized as maps of unknown length with their fields as keys, while maps
+//! of data know their length, so the fields of those are still renamed.
+
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize, Serializer,
+};
+
+/// Gives the name a struct field is written with, if it isn't its own.
+pub type Rename = fn(&str) -> Option<&'static str>;
+
+/// `value`, serialized with its struct fields and those of everything in it renamed.
+pub struct Renamed<T> {
+    pub value: T,
+    pub rename: Rename,
+}
+
+impl<T: Serialize> Serialize for Renamed<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(Renaming {
+            inner: serializer,
+            rename: self.rename,
+        })
+    }
+}
+
+/// Wraps a serializer, or one of its compound serializers, to rename the fields given to it.
+struct Renaming<S> {
+    inner: S,
+    rename: Rename,
+}
+
+macro_rules! forward {
+    ($($method:ident($($argument:ident: $type:ty),*);)*) => {
+        $(
+            fn $method(self, $($argument: $type),*) -> Result<S::Ok, S::Error> {
+                self.inner.$method($($argument),*)
+            }
+        )*
+    };
+}
+
+impl<S: Serializer> Serializer for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Renaming<S::SerializeSeq>;
+    type SerializeTuple = Renaming<S::SerializeTuple>;
+    type SerializeTupleStruct = Renaming<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Renaming<S::SerializeTupleVariant>;
+    type SerializeMap = RenamingMap<S::SerializeMap>;
+    type SerializeStruct = Renaming<S::SerializeStruct>;
+    type SerializeStructVariant = Renaming<S::SerializeStructVariant>;
+
+    forward! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_i128(v: i128);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_u128(v: u128);
+        serialize_f32(v: f32);
+        serialize_f64(v: f64);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(&Renamed {
+            value,
+            rename: self.rename,
+        })
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_newtype_struct(
+            name,
+            &Renamed {
+                value,
+                rename: self.rename,
+            },
+        )
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_newtype_variant(
+            name,
+            index,
+            variant,
+            &Renamed {
+                value,
+                rename: self.rename,
+            },
+        )
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        let rename = self.rename;
+        let inner = self.inner.serialize_seq(len)?;
+        Ok(Renaming { inner, rename })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        let rename = self.rename;
+        let inner = self.inner.serialize_tuple(len)?;
+        Ok(Renaming { inner, rename })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let rename = self.rename;
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(Renaming { inner, rename })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let rename = self.rename;
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, index, variant, len)?;
+        Ok(Renaming { inner, rename })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let rename = self.rename;
+        let inner = self.inner.serialize_map(len)?;
+        Ok(RenamingMap {
+            inner,
+            rename,
+            fields: len.is_none(),
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let rename = self.rename;
+        let inner = self.inner.serialize_struct(name, len)?;
+        Ok(Renaming { inner, rename })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let rename = self.rename;
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, index, variant, len)?;
+        Ok(Renaming { inner, rename })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<S: SerializeSeq> SerializeSeq for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeTuple> SerializeTuple for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeTupleStruct> SerializeTupleStruct for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeTupleVariant> SerializeTupleVariant for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+/// Wraps the serializer of a map, which is a struct with flattened fields if `fields` is set.
+struct RenamingMap<S> {
+    inner: S,
+    rename: Rename,
+    fields: bool,
+}
+
+impl<S: SerializeMap> SerializeMap for RenamingMap<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), S::Error> {
+        if self.fields {
+            // The keys of fields are strings, which only a serializer can take out of `key`.
+            if let Ok(serde_json::Value::String(name)) = serde_json::to_value(key) {
+                if let Some(renamed) = (self.rename)(&name) {
+                    return self.inner.serialize_key(renamed);
+                }
+            }
+        }
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        self.inner.serialize_value(&value)
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeStruct> SerializeStruct for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        let key = (self.rename)(key).unwrap_or(key);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field((self.rename)(key).unwrap_or(key))
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+impl<S: SerializeStructVariant> SerializeStructVariant for Renaming<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), S::Error> {
+        let value = Renamed {
+            value,
+            rename: self.rename,
+        };
+        let key = (self.rename)(key).unwrap_or(key);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> {
+        self.inner.skip_field((self.rename)(key).unwrap_or(key))
+    }
+
+    fn end(self) -> Result<S::Ok, S::Error> {
+        self.inner.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Outer {
+        s: u8,
+        #[serde(flatten)]
+        inner: Inner,
+        map: BTreeMap<&'static str, Inner>,
+    }
+
+    #[derive(Serialize)]
+    struct Inner {
+        t: u8,
+    }
+
+    fn spelled_out(name: &str) -> Option<&'static str> {
+        match name {
+            "s" => Some("start"),
+            "t" => Some("text"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn fields_are_renamed_and_map_keys_are_not() {
+        let value = Outer {
+            s: 1,
+            inner: Inner { t: 2 },
+            map: BTreeMap::from([("s", Inner { t: 3 }), ("t", Inner { t: 4 })]),
+        };
+        let json = serde_json::to_string(&Renamed {
+            value: &value,
+            rename: spelled_out,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"start":1,"text":2,"map":{"s":{"text":3},"t":{"text":4}}}"#
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use miette::{Diagnostic, Severity};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Default, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Human readable reports on stderr.
+    #[default]
+    Human,
+    /// One JSON object per line on stderr instead of the human readable reports, or in a file
+    /// next to them with `--error-file`.
+    Json,
+}
+
+#[derive(clap::Args)]
+pub struct ReportArgs {
+    /// How to report errors and warnings.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    pub error_format: ErrorFormat,
+    /// Where to write JSON diagnostics, keeping the human readable reports on stderr.
+    #[arg(long, global = true)]
+    pub error_file: Option<PathBuf>,
+    /// Treat warnings as errors.
+    #[arg(long, global = true)]
+    pub strict: bool,
+    /// Stop after this many errors.
+    #[arg(long, global = true)]
+    pub max_errors: Option<usize>,
+    /// Stop after the first error.
+    #[arg(long, global = true, conflicts_with = "max_errors")]
+    pub fail_fast: bool,
+}
+
+impl ReportArgs {
+    /// Opens the file for JSON diagnostics, if they were requested with `--error-file`.
+    pub fn open(&self) -> io::Result<Option<Mutex<Box<dyn Write + Send>>>> {
+        match (&self.error_format, &self.error_file) {
+            (ErrorFormat::Json, Some(path)) => Ok(Some(Mutex::new(Box::new(File::create(path)?)))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether diagnostics are written to stderr as JSON instead of the human readable reports.
+    /// stdout is left to the output of subcommands like `upcoming`.
+    pub fn json_on_stderr(&self) -> bool {
+        matches!(self.error_format, ErrorFormat::Json) && self.error_file.is_none()
+    }
+
+    pub fn errors(&self) -> Errors {
+        Errors {
+            count: Arc::new(AtomicUsize::new(0)),
+            max: if self.fail_fast {
+                Some(1)
+            } else {
+                self.max_errors
+            },
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// The number of errors reported so far, shared with the miette hook.
+#[derive(Clone)]
+pub struct Errors {
+    count: Arc<AtomicUsize>,
+    max: Option<usize>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Errors {
+    pub fn add(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Whether `--max-errors` was reached. Loops over input files check this to stop early.
+    pub fn should_stop(&self) -> bool {
+        let Some(max) = self.max else {
+            return false;
+        };
+        let count = self.count();
+        if count < max {
+            return false;
+        }
+        if !self.stopped.swap(true, Ordering::SeqCst) {
+            let plural = if count == 1 { "" } else { "s" };
+            eprintln!("Stopping after {count} error{plural}.");
+        }
+        true
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    code: Option<String>,
+    severity: &'static str,
+    file: Option<String>,
+    span: Option<JsonSpan<'a>>,
+    message: String,
+    causes: Vec<String>,
+    help: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan<'a> {
+    offset: usize,
+    length: usize,
+    label: Option<&'a str>,
+}
+
+/// Writes a diagnostic as a single line of JSON.
+pub fn write_json(diagnostic: &dyn Diagnostic, writer: &mut dyn Write) -> io::Result<()> {
+    // Errors wrapped with context keep the code and location on the inner diagnostic.
+    let mut chain = vec![diagnostic];
+    while let Some(source) = chain.last().unwrap().diagnostic_source() {
+        chain.push(source);
+    }
+
+    let code = chain
+        .iter()
+        .find_map(|d| d.code())
+        .map(|code| code.to_string());
+    let severity = match diagnostic.severity().unwrap_or(Severity::Error) {
+        Severity::Advice => "advice",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    let labelled = chain.iter().find_map(|d| Some((*d, d.labels()?.next()?)));
+    let file = labelled.as_ref().and_then(|(d, label)| {
+        let contents = d.source_code()?.read_span(label.inner(), 0, 0).ok()?;
+        contents.name().map(str::to_owned)
+    });
+    let span = labelled.as_ref().map(|(_, label)| JsonSpan {
+        offset: label.offset(),
+        length: label.len(),
+        label: label.label(),
+    });
+    let mut causes = Vec::new();
+    let mut source = diagnostic.source();
+    while let Some(error) = source {
+        causes.push(error.to_string());
+        source = error.source();
+    }
+    let help = chain
+        .iter()
+        .find_map(|d| d.help())
+        .map(|help| help.to_string());
+
+    let line = JsonDiagnostic {
+        code,
+        severity,
+        file,
+        span,
+        message: diagnostic.to_string(),
+        causes,
+        help,
+    };
+    serde_json::to_writer(&mut *writer, &line)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
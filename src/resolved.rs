@@ -0,0 +1,352 @@
+//! Events with their defaults applied and day overrides merged.
+//!
+//! The input types mirror the TOML files, so every consumer would otherwise have to know that a
+//! missing day section means "not held", that day sections fall back to the event's times, and
+//! how `weeks`, `start_date`, `end_date`, skipped dates, and breaks limit the occurrences. The
+//! blackout dates and holidays of the meta file cancel occurrences here too.
+
+use std::{borrow::Cow, num::NonZeroU8, str::FromStr};
+
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use miette::Result;
+
+use crate::{error::MissingTimeZone, input};
+
+pub struct Event<'a> {
+    pub source: &'a crate::Event<'a>,
+    pub days_off: &'a DaysOff,
+    pub name: Cow<'a, str>,
+    pub timezone: Tz,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    /// How many weeks apart the event is held, and a date in a week when it is held.
+    pub interval: Option<(NonZeroU8, NaiveDate)>,
+    /// The day of the month the event is held, if it is monthly.
+    pub monthly: Option<NonZeroU8>,
+    /// The only dates the event is held, if it doesn't repeat.
+    pub dates: Option<Vec<NaiveDate>>,
+    /// The days the event is held, indexed from Monday.
+    pub days: [Option<Day<'a>>; 7],
+}
+
+/// The dates the meta file cancels events on.
+#[derive(Default)]
+pub struct DaysOff {
+    /// The blackout dates, when every event is canceled.
+    pub blackout: Vec<NaiveDate>,
+    /// The holidays, when the events with `cancel_on_holidays` are canceled.
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl DaysOff {
+    /// The dates `event` is canceled on.
+    pub fn of<'a>(&'a self, event: &input::Event) -> impl Iterator<Item = NaiveDate> + 'a {
+        let holidays = if event.cancel_on_holidays {
+            &self.holidays[..]
+        } else {
+            &[]
+        };
+        self.blackout.iter().chain(holidays).copied()
+    }
+}
+
+/// The details of an event on one day of the week.
+pub struct Day<'a> {
+    /// The name of the event on this day, if it differs.
+    pub name: Option<&'a str>,
+    pub start: NaiveTime,
+    pub duration: Duration,
+    /// The weeks of the month the event is held on this day, or `None` for every week.
+    pub weeks: Option<&'a [u8]>,
+    pub note: Option<&'a input::Note<'a>>,
+}
+
+/// One time an event is held.
+pub struct Occurrence<'a, 'b> {
+    pub date: NaiveDate,
+    pub start: DateTime<Tz>,
+    pub end: DateTime<Tz>,
+    pub day: &'b Day<'a>,
+    /// The name of the event for this occurrence, if it differs.
+    pub name: Option<&'a str>,
+    pub canceled: bool,
+    pub canceled_reason: Option<&'a str>,
+    pub confirmed: bool,
+    pub tentative: bool,
+}
+
+impl<'a> Event<'a> {
+    pub fn resolve(event: &'a crate::Event<'a>, days_off: &'a DaysOff) -> Result<Self> {
+        let input = &event.event;
+        let Ok(timezone) = Tz::from_str(input.timezone().as_ref().as_ref()) else {
+            return Err(MissingTimeZone::new(event).into());
+        };
+        let days = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ]
+        .map(|weekday| {
+            let day = day_of(&input.days, weekday)?;
+            Some(Day {
+                name: day.info.name.as_deref(),
+                start: day.start.unwrap_or(input.start()).0,
+                duration: day.duration.map_or(input.duration(), |d| d.0),
+                weeks: day.info.weeks.as_deref().or(input.info.weeks.as_deref()),
+                note: day.note.as_ref(),
+            })
+        });
+        Ok(Event {
+            source: event,
+            days_off,
+            name: event.name(),
+            timezone,
+            start_date: input.start_date,
+            end_date: input.end_date,
+            interval: input
+                .every
+                .as_ref()
+                .zip(input.anchor.as_ref())
+                .map(|(every, anchor)| (*every.as_ref(), *anchor.as_ref())),
+            monthly: input.monthly.as_ref().map(|day| *day.as_ref()),
+            dates: input
+                .dates
+                .as_ref()
+                .map(|dates| dates.iter().map(|date| *date.as_ref()).collect()),
+            days,
+        })
+    }
+
+    pub fn day(&self, weekday: Weekday) -> Option<&Day<'a>> {
+        self.days[weekday.num_days_from_monday() as usize].as_ref()
+    }
+
+    /// Whether the event is held on `date`.
+    pub fn occurs_on(&self, date: NaiveDate) -> Option<&Day<'a>> {
+        let event = &self.source.event;
+        if event.skipped.contains(date)
+            || event
+                .hiatus
+                .as_ref()
+                .is_some_and(|hiatus| hiatus.as_ref().contains(date))
+        {
+            return None;
+        }
+        self.scheduled_on(date)
+    }
+
+    /// Whether the schedule has the event on `date`, even if it is skipped or on a break.
+    pub fn scheduled_on(&self, date: NaiveDate) -> Option<&Day<'a>> {
+        if self.start_date.is_some_and(|start| date < start)
+            || self.end_date.is_some_and(|end| end < date)
+        {
+            return None;
+        }
+        if self
+            .interval
+            .is_some_and(|(every, anchor)| !input::in_interval(date, anchor, every))
+            || self
+                .monthly
+                .is_some_and(|day| !input::is_monthly_day(date, day))
+            || self
+                .dates
+                .as_ref()
+                .is_some_and(|dates| !dates.contains(&date))
+        {
+            return None;
+        }
+        let day = self.day(date.weekday())?;
+        let week = (date.day0() / 7 + 1) as u8;
+        if day.weeks.is_some_and(|weeks| !weeks.contains(&week)) {
+            return None;
+        }
+        Some(day)
+    }
+
+    /// The occurrence on `date`, if the event is held then.
+    pub fn occurrence_on(&self, date: NaiveDate) -> Option<Occurrence<'a, '_>> {
+        let day = self.occurs_on(date)?;
+        let input = &self.source.event;
+        let change = input.overrides.get(&date);
+        let start = date
+            .and_time(change.and_then(|c| c.start).map_or(day.start, |t| t.0))
+            .and_local_timezone(self.timezone)
+            .earliest()?;
+        let duration = change
+            .and_then(|c| c.duration)
+            .map_or(day.duration, |d| d.0);
+        Some(Occurrence {
+            date,
+            start,
+            end: start + duration,
+            day,
+            name: change.and_then(|c| c.name.as_deref()).or(day.name),
+            canceled: input.canceled.contains(date)
+                || self.days_off.of(input).any(|day_off| day_off == date),
+            canceled_reason: input.canceled_reasons.get(&date).map(|r| r.as_ref()),
+            confirmed: input.confirmed.contains(date),
+            tentative: input.tentative.contains(date),
+        })
+    }
+
+    /// When the occurrence on `date` ends, or would have ended if it is skipped or on a break.
+    pub fn scheduled_end(&self, date: NaiveDate) -> Option<DateTime<Tz>> {
+        if let Some(occurrence) = self.occurrence_on(date) {
+            return Some(occurrence.end);
+        }
+        let day = self.scheduled_on(date)?;
+        let start = date
+            .and_time(day.start)
+            .and_local_timezone(self.timezone)
+            .earliest()?;
+        Some(start + day.duration)
+    }
+
+    /// The occurrences that haven't ended at `now`, through `until`, in order. This includes
+    /// occurrences that started on an earlier day and are still going.
+    pub fn current_occurrences(
+        &self,
+        now: DateTime<Utc>,
+        until: NaiveDate,
+    ) -> impl Iterator<Item = Occurrence<'a, '_>> {
+        let today = now.with_timezone(&self.timezone).date_naive();
+        // Only occurrences at least this long can still be going from an earlier day.
+        let days = self.longest().num_days() as u64 + 1;
+        self.occurrences(today - Days::new(days), until)
+            .filter(move |occurrence| now < occurrence.end)
+    }
+
+    /// The longest an occurrence lasts.
+    pub fn longest(&self) -> Duration {
+        let days = self.days.iter().flatten().map(|day| day.duration);
+        let overrides = self
+            .source
+            .event
+            .overrides
+            .values()
+            .filter_map(|change| Some(change.duration?.0));
+        days.chain(overrides).max().unwrap_or_else(Duration::zero)
+    }
+
+    /// The occurrences from `from` through `until`, in order.
+    pub fn occurrences(
+        &self,
+        from: NaiveDate,
+        until: NaiveDate,
+    ) -> impl Iterator<Item = Occurrence<'a, '_>> {
+        from.iter_days()
+            .take_while(move |date| *date <= until)
+            .filter_map(move |date| self.occurrence_on(date))
+    }
+}
+
+fn day_of<'a, 'b>(
+    days: &'b input::EventDays<'a>,
+    weekday: Weekday,
+) -> Option<&'b input::EventDay<'a>> {
+    days.iter().find(|(w, _)| *w == weekday).map(|(_, day)| day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::EventFile;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 6, day).unwrap()
+    }
+
+    #[test]
+    fn day_weeks_select_weekdays_of_the_month() {
+        let file = EventFile::toml(
+            Path::new("event.toml"),
+            r#"
+                timezone = "Asia/Tokyo"
+                start = "21:00"
+                duration = "1:00"
+
+                [days.saturday]
+                weeks = [1, 3]
+
+                [days.sunday]
+                weeks = [2]
+            "#,
+        );
+        let event = file.parse();
+        let days_off = DaysOff::default();
+        let resolved = Event::resolve(&event, &days_off).unwrap();
+        // The Saturdays of June 2023 are the 3rd, 10th, 17th and 24th.
+        let dates: Vec<_> = resolved
+            .occurrences(date(1), date(30))
+            .map(|occurrence| occurrence.date)
+            .collect();
+        assert_eq!(dates, [date(3), date(11), date(17)]);
+    }
+
+    #[test]
+    fn blackout_dates_cancel_occurrences() {
+        let file = EventFile::toml(
+            Path::new("event.toml"),
+            r#"
+                timezone = "Asia/Tokyo"
+                start = "21:00"
+                duration = "1:00"
+
+                [days.saturday]
+            "#,
+        );
+        let event = file.parse();
+        let days_off = DaysOff {
+            blackout: vec![date(10), date(11)],
+            ..DaysOff::default()
+        };
+        let resolved = Event::resolve(&event, &days_off).unwrap();
+        let canceled: Vec<_> = resolved
+            .occurrences(date(1), date(30))
+            .map(|occurrence| (occurrence.date, occurrence.canceled))
+            .collect();
+        assert_eq!(
+            canceled,
+            [
+                (date(3), false),
+                (date(10), true),
+                (date(17), false),
+                (date(24), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn holidays_cancel_occurrences_of_events_off_on_them() {
+        let days_off = DaysOff {
+            holidays: vec![date(10)],
+            ..DaysOff::default()
+        };
+        for cancel_on_holidays in [false, true] {
+            let file = EventFile::toml(
+                Path::new("event.toml"),
+                &format!(
+                    r#"
+                        timezone = "Asia/Tokyo"
+                        start = "21:00"
+                        duration = "1:00"
+                        cancel_on_holidays = {cancel_on_holidays}
+
+                        [days.saturday]
+                    "#
+                ),
+            );
+            let event = file.parse();
+            let resolved = Event::resolve(&event, &days_off).unwrap();
+            let occurrence = resolved.occurrence_on(date(10)).unwrap();
+            assert_eq!(occurrence.canceled, cancel_on_holidays);
+        }
+    }
+}
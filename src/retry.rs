@@ -0,0 +1,72 @@
+use std::{io, path::Path, thread, time::Duration};
+
+use miette::Report;
+
+use crate::error::RetryingIo;
+
+/// How to retry output operations that fail for transient reasons, like another program
+/// holding a lock on the file.
+#[derive(Clone, Copy, clap::Args)]
+#[command(about = None, long_about = None)]
+pub struct Retry {
+    /// How many times to retry writing an output file after a transient error.
+    #[arg(long = "retries", default_value_t = 3)]
+    pub attempts: u32,
+    /// How long to wait before the first retry, in milliseconds. The wait doubles each time.
+    #[arg(long = "retry-delay", default_value_t = 200)]
+    pub delay_ms: u64,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            delay_ms: 200,
+        }
+    }
+}
+
+impl Retry {
+    pub fn run<T>(
+        &self,
+        path: &Path,
+        mut operation: impl FnMut() -> io::Result<T>,
+    ) -> io::Result<T> {
+        let mut delay = Duration::from_millis(self.delay_ms);
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Err(error) if attempt < self.attempts && is_transient(&error) => {
+                    attempt += 1;
+                    eprintln!(
+                        "{:?}",
+                        Report::new(RetryingIo {
+                            path: path.to_path_buf(),
+                            attempt,
+                            attempts: self.attempts,
+                            error,
+                        }),
+                    );
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+fn is_transient(error: &io::Error) -> bool {
+    // Windows reports files locked by sync clients and virus scanners as sharing (32) or lock
+    // (33) violations.
+    if cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33)) {
+        return true;
+    }
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ResourceBusy
+    )
+}
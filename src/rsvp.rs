@@ -0,0 +1,125 @@
+//! Fetching how many people have signed up for upcoming occurrences.
+//!
+//! The source is either a JSON object of event ids to dates to counts, or a CSV file with `event`
+//! and `date` columns, like a Google Form's response sheet published as CSV. Each row of a CSV
+//! file adds its `count` column, or one if it doesn't have one.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use miette::{miette, IntoDiagnostic, Result};
+
+/// RSVP counts by event id and date.
+pub type Counts = BTreeMap<String, BTreeMap<NaiveDate, u16>>;
+
+pub fn fetch(url: &str) -> Result<Counts> {
+    let body = ureq::get(url)
+        .call()
+        .into_diagnostic()?
+        .into_string()
+        .into_diagnostic()?;
+    if body.trim_start().starts_with('{') {
+        serde_json::from_str(&body).into_diagnostic()
+    } else {
+        parse_csv(&body)
+    }
+}
+
+fn parse_csv(body: &str) -> Result<Counts> {
+    let mut rows = split_rows(body).into_iter();
+    let header = rows.next().unwrap_or_default();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(event), Some(date)) = (column("event"), column("date")) else {
+        return Err(miette!("The CSV file needs event and date columns."));
+    };
+    let count = column("count");
+
+    let mut counts = Counts::new();
+    for (line, row) in rows.enumerate() {
+        let field = |index: usize| row.get(index).map_or("", |field| field.trim());
+        let parsed = NaiveDate::parse_from_str(field(date), "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(field(date), "%Y/%m/%d"));
+        let Ok(parsed) = parsed else {
+            return Err(miette!(
+                "Row {} has {:?}, which is not a date.",
+                line + 2,
+                field(date),
+            ));
+        };
+        let amount = match count {
+            Some(count) => field(count).parse::<u16>().map_err(|_| {
+                miette!(
+                    "Row {} has {:?}, which is not a count.",
+                    line + 2,
+                    field(count),
+                )
+            })?,
+            None => 1,
+        };
+        let total = counts
+            .entry(field(event).to_owned())
+            .or_default()
+            .entry(parsed)
+            .or_default();
+        *total = total.saturating_add(amount);
+    }
+    Ok(counts)
+}
+
+/// Splits CSV text into rows of fields. Quoted fields may have commas, quotes written as `""`,
+/// and line breaks. Blank lines are left out.
+fn split_rows(body: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                let row = std::mem::replace(&mut fields, vec![String::new()]);
+                if !is_blank(&row) {
+                    rows.push(row);
+                }
+            }
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    if !is_blank(&fields) {
+        rows.push(fields);
+    }
+    rows
+}
+
+fn is_blank(row: &[String]) -> bool {
+    matches!(row, [field] if field.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_fields_can_span_lines() {
+        let body = "event,date,comment\r\n\
+            party,2030-01-05,\"Bringing a friend,\r\nand \"\"snacks\"\"\"\r\n\
+            \r\n\
+            party,2030-01-05,\r\n";
+        let rows = split_rows(body);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1][2], "Bringing a friend,\r\nand \"snacks\"");
+        let counts = parse_csv(body).unwrap();
+        let date = NaiveDate::from_ymd_opt(2030, 1, 5).unwrap();
+        assert_eq!(counts["party"][&date], 2);
+    }
+}
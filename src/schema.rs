@@ -0,0 +1,152 @@
+//! JSON Schemas of the input files, for editors to complete and check fields as they are written,
+//! before the compiler is run.
+
+use std::{fs, io::Write, path::PathBuf, process::ExitCode};
+
+use miette::{Context, IntoDiagnostic, Result};
+use schemars::{
+    generate::SchemaSettings, transform::RecursiveTransform, JsonSchema, Schema, SchemaGenerator,
+};
+use serde_json::Value;
+
+use crate::{
+    deprecated::{self, Renamed},
+    input,
+    retry::Retry,
+    safely_save,
+};
+
+#[derive(clap::Args)]
+pub struct SchemaArgs {
+    /// The directory to write the schemas to.
+    #[arg(default_value = ".")]
+    output: PathBuf,
+}
+
+pub fn run(args: SchemaArgs) -> ExitCode {
+    match write(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn write(args: &SchemaArgs) -> Result<()> {
+    fs::create_dir_all(&args.output)
+        .into_diagnostic()
+        .wrap_err("Could not create output directory")?;
+    let schemas = [
+        (
+            "event.schema.json",
+            input_schema::<input::Event>(deprecated::EVENT),
+        ),
+        (
+            "meta.schema.json",
+            input_schema::<input::Meta>(deprecated::META),
+        ),
+        ("defaults.schema.json", input_schema::<input::Defaults>(&[])),
+    ];
+    for (name, schema) in schemas {
+        let mut json = serde_json::to_vec_pretty(&schema).into_diagnostic()?;
+        json.push(b'\n');
+        safely_save(&args.output, name, &Retry::default(), |t| {
+            t.write_all(&json).into_diagnostic()
+        })?;
+        eprintln!("Wrote {}", args.output.join(name).display());
+    }
+    Ok(())
+}
+
+/// A generator with `settings` that leaves `null` out of the schemas.
+pub fn generator(mut settings: SchemaSettings) -> SchemaGenerator {
+    settings
+        .transforms
+        .push(Box::new(RecursiveTransform(remove_null as fn(&mut Schema))));
+    settings.into_generator()
+}
+
+/// Takes `null` out of the schemas of optional fields. TOML has no null, and the output leaves
+/// fields out instead of writing them as null.
+fn remove_null(schema: &mut Schema) {
+    if let Some(Value::Array(types)) = schema.get_mut("type") {
+        types.retain(|kind| kind != "null");
+        if let [kind] = &types[..] {
+            let kind = kind.clone();
+            schema.insert("type".to_owned(), kind);
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get_mut("anyOf") {
+        options.retain(|option| option != &serde_json::json!({ "type": "null" }));
+        if let [Value::Object(_)] = &options[..] {
+            let Some(Value::Object(option)) = options.pop() else {
+                unreachable!();
+            };
+            schema.remove("anyOf");
+            for (key, value) in option {
+                schema.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The schema of an input file read as `T`. Draft 7 is used because editors don't all support
+/// later drafts.
+fn input_schema<T: JsonSchema>(renamed: &[Renamed]) -> Value {
+    let mut settings = SchemaSettings::draft07();
+    // Fields that are read but never written are marked `writeOnly`, which is every field of an
+    // input file.
+    settings
+        .transforms
+        .push(Box::new(RecursiveTransform(|schema: &mut Schema| {
+            schema.remove("writeOnly");
+        })));
+    let mut schema = generator(settings).into_root_schema_for::<T>().to_value();
+    for field in renamed {
+        add_renamed(&mut schema, field);
+    }
+    schema
+}
+
+/// Adds the old name of a renamed field as a deprecated copy of the new one, since the old name
+/// still works.
+fn add_renamed(schema: &mut Value, field: &Renamed) {
+    let mut pointer = String::new();
+    for key in field.path {
+        pointer = resolve(schema, pointer);
+        if *key == "*" {
+            pointer.push_str("/additionalProperties");
+        } else {
+            pointer.push_str(&format!("/properties/{key}"));
+        }
+    }
+    let pointer = resolve(schema, pointer);
+    let Some(Value::Object(properties)) = schema.pointer_mut(&format!("{pointer}/properties"))
+    else {
+        return;
+    };
+    let Some(Value::Object(new)) = properties.get(field.new) else {
+        return;
+    };
+    let mut old = new.clone();
+    old.insert(
+        "description".to_owned(),
+        format!("Renamed to `{}`.", field.new).into(),
+    );
+    old.insert("deprecated".to_owned(), true.into());
+    properties.insert(field.old.to_owned(), old.into());
+}
+
+/// Follows the reference in the schema at `pointer` to the definition it names.
+fn resolve(schema: &Value, pointer: String) -> String {
+    match schema
+        .pointer(&pointer)
+        .and_then(|s| s.get("$ref"))
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix('#'))
+    {
+        Some(definition) => definition.to_owned(),
+        None => pointer,
+    }
+}
@@ -0,0 +1,394 @@
+//! Checks the time zone and recurrence handling against generated edge cases.
+//!
+//! The zone tables in data.json come from the bundled tz files, while occurrences are resolved
+//! with the time zone data compiled into chrono-tz. The two are updated separately, so the checks
+//! also catch them disagreeing after an update.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroU8,
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+    sync::Arc,
+};
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, LocalResult, NaiveDate, Offset, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::{
+    event_format::EventFormat,
+    input,
+    output::{Rule, Zone},
+    parse_events,
+    report::Errors,
+    resolved, time, EventFile,
+};
+
+/// Zones with daylight saving time that have unusual transitions: at midnight, by half an hour,
+/// in the southern hemisphere, or with an offset that isn't whole hours.
+const DST_ZONES: &[&str] = &[
+    "America/New_York",
+    "America/Los_Angeles",
+    "America/Havana",
+    "America/Santiago",
+    "Europe/London",
+    "Europe/Berlin",
+    "Australia/Sydney",
+    "Australia/Lord_Howe",
+    "Pacific/Auckland",
+    "Pacific/Chatham",
+];
+
+/// Years checked besides the ones the zone tables cover, for the century leap year rules and
+/// years with 53 weeks.
+const EXTRA_YEARS: &[i32] = &[1900, 2000, 2004, 2015, 2020, 2100];
+
+/// How many failures of each check are printed without `--verbose`.
+const SHOWN_FAILURES: usize = 5;
+
+#[derive(clap::Args)]
+pub struct SelftestArgs {
+    /// Print every failure, instead of the first few of each check.
+    #[arg(long)]
+    verbose: bool,
+}
+
+struct Check {
+    name: &'static str,
+    count: usize,
+    failures: Vec<String>,
+}
+
+impl Check {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            count: 0,
+            failures: Vec::new(),
+        }
+    }
+
+    fn assert(&mut self, ok: bool, failure: impl FnOnce() -> String) {
+        self.count += 1;
+        if !ok {
+            self.failures.push(failure());
+        }
+    }
+}
+
+pub fn run(args: SelftestArgs, errors: Errors) -> ExitCode {
+    let now = Utc::now();
+    let (zones, broken_zones) = time::collect_zones(now);
+    let mut years: Vec<i32> = (now.year()..=time::limit(now).year()).collect();
+    years.extend(EXTRA_YEARS);
+
+    let checks = [
+        zone_tables(&zones, &broken_zones, now),
+        dst_transitions(&zones, &errors),
+        monthly_days(&years),
+        weeks_of_month(&years, &errors),
+        intervals(&years),
+    ];
+
+    let mut failed = errors.count() != 0;
+    for check in &checks {
+        println!(
+            "{}: {} checks, {} failed",
+            check.name,
+            check.count,
+            check.failures.len()
+        );
+        let shown = if args.verbose {
+            check.failures.len()
+        } else {
+            SHOWN_FAILURES
+        };
+        for failure in check.failures.iter().take(shown) {
+            println!("  {failure}");
+        }
+        if check.failures.len() > shown {
+            println!("  and {} more", check.failures.len() - shown);
+        }
+        failed |= !check.failures.is_empty();
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// The offset in minutes that the world would use at `timestamp`.
+fn table_offset(rules: &[Rule], timestamp: i64) -> i32 {
+    rules
+        .iter()
+        .take_while(|rule| rule.start.is_none_or(|start| start <= timestamp))
+        .last()
+        .and_then(|rule| rule.offset)
+        .map_or(0, i32::from)
+}
+
+fn chrono_offset(timezone: Tz, timestamp: i64) -> Option<i32> {
+    let utc = DateTime::from_timestamp(timestamp, 0)?.naive_utc();
+    Some(
+        timezone
+            .offset_from_utc_datetime(&utc)
+            .fix()
+            .local_minus_utc()
+            / 60,
+    )
+}
+
+/// The zone tables agree with chrono-tz every day, and around every change of offset.
+fn zone_tables(
+    zones: &BTreeMap<String, Zone>,
+    broken_zones: &BTreeSet<String>,
+    now: DateTime<Utc>,
+) -> Check {
+    let mut check = Check::new("Zone tables");
+    for name in broken_zones {
+        check.assert(false, || {
+            format!("{name} could not be read from the bundled tz files.")
+        });
+    }
+    let from = now.timestamp();
+    let until = time::limit(now).timestamp();
+    for (name, zone) in zones {
+        let Ok(timezone) = Tz::from_str(name) else {
+            check.assert(false, || {
+                format!("{name} is in the bundled tz files, but not in chrono-tz.")
+            });
+            continue;
+        };
+        let changes = zone
+            .offsets
+            .iter()
+            .filter_map(|rule| rule.start)
+            .flat_map(|start| [start - 1, start]);
+        let days = (from..until).step_by(24 * 60 * 60);
+        for timestamp in changes.chain(days) {
+            let expected = table_offset(&zone.offsets, timestamp);
+            let actual = chrono_offset(timezone, timestamp);
+            check.assert(actual == Some(expected), || {
+                format!(
+                    "{name} at {timestamp} is {expected} minutes from UTC in the zone table, but {} in chrono-tz.",
+                    actual.map_or("unknown".into(), |offset| offset.to_string()),
+                )
+            });
+        }
+    }
+    check
+}
+
+/// Builds event files from generated sources.
+fn event_files(sources: &[(PathBuf, String)]) -> Vec<EventFile<'_>> {
+    sources
+        .iter()
+        .map(|(path, content)| EventFile {
+            path,
+            format: EventFormat::Toml,
+            entry: None,
+            content: Arc::new(content.clone()),
+            original: None,
+        })
+        .collect()
+}
+
+/// On the days the clocks change, an occurrence is held at its local time if that time exists,
+/// the world shows it at that time, and it lasts as long as it should.
+fn dst_transitions(zones: &BTreeMap<String, Zone>, errors: &Errors) -> Check {
+    let mut check = Check::new("DST transitions");
+    let duration = Duration::hours(1);
+    let mut sources = Vec::new();
+    for zone in DST_ZONES {
+        for minutes in (0..24 * 60).step_by(15) {
+            let content = format!(
+                "timezone = \"{zone}\"\nstart = \"{}:{:02}\"\nduration = \"1:00\"\n",
+                minutes / 60,
+                minutes % 60,
+            );
+            sources.push((
+                PathBuf::from(format!("selftest/{zone}/{minutes}.toml")),
+                content,
+            ));
+        }
+    }
+    let event_files = event_files(&sources);
+    let days_off = resolved::DaysOff::default();
+    for event in parse_events(&event_files, errors) {
+        let resolved = match resolved::Event::resolve(&event, &days_off) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                check.assert(false, || format!("{error}"));
+                continue;
+            }
+        };
+        let zone = resolved.timezone.name();
+        let Some(rules) = zones.get(zone).map(|zone| &zone.offsets[..]) else {
+            check.assert(false, || format!("{zone} is not in the bundled tz files."));
+            continue;
+        };
+        let start = event.event.start().0;
+        let dates = rules
+            .iter()
+            .filter_map(|rule| rule.start)
+            .flat_map(|change| {
+                // The local dates just before and after the change.
+                [change - 1, change].map(|timestamp| {
+                    let local = timestamp + i64::from(table_offset(rules, timestamp)) * 60;
+                    DateTime::from_timestamp(local, 0).map(|local| local.date_naive())
+                })
+            })
+            .flatten();
+        for date in dates {
+            let local = date.and_time(start);
+            let expected = local.and_local_timezone(resolved.timezone);
+            let occurrence = resolved.occurrence_on(date);
+            match (&occurrence, expected) {
+                (None, LocalResult::None) => check.assert(true, String::new),
+                (None, _) => check.assert(false, || {
+                    format!("{zone} has no occurrence at {local}, although that time exists.")
+                }),
+                (Some(_), LocalResult::None) => check.assert(false, || {
+                    format!(
+                        "{zone} has an occurrence at {local}, although that time doesn't exist."
+                    )
+                }),
+                (
+                    Some(occurrence),
+                    LocalResult::Single(expected) | LocalResult::Ambiguous(expected, _),
+                ) => {
+                    check.assert(occurrence.start == expected, || {
+                        format!(
+                            "{zone} has the occurrence at {local} at {}, instead of the earlier {expected}.",
+                            occurrence.start,
+                        )
+                    });
+                    let timestamp = occurrence.start.timestamp();
+                    let shown = timestamp + i64::from(table_offset(rules, timestamp)) * 60;
+                    check.assert(shown == local.and_utc().timestamp(), || {
+                        format!(
+                            "{zone} has the occurrence at {local} at another time in the world."
+                        )
+                    });
+                    check.assert(occurrence.end - occurrence.start == duration, || {
+                        format!(
+                            "{zone} has the occurrence at {local} lasting {} minutes.",
+                            (occurrence.end - occurrence.start).num_minutes(),
+                        )
+                    });
+                }
+            }
+        }
+    }
+    check
+}
+
+fn last_day(year: i32, month: u32) -> u32 {
+    (28..=31)
+        .filter(|&day| NaiveDate::from_ymd_opt(year, month, day).is_some())
+        .max()
+        .unwrap_or(28)
+}
+
+/// Each day of the month from 1 to 31 falls on exactly one date in every month, including in
+/// February of leap years.
+fn monthly_days(years: &[i32]) -> Check {
+    let mut check = Check::new("Monthly days");
+    for &year in years {
+        for month in 1..=12 {
+            let last = last_day(year, month);
+            for day in 1..=31 {
+                let Some(day) = NonZeroU8::new(day) else {
+                    continue;
+                };
+                let expected = u32::from(day.get()).min(last);
+                for date_day in 1..=last {
+                    let Some(date) = NaiveDate::from_ymd_opt(year, month, date_day) else {
+                        continue;
+                    };
+                    check.assert(
+                        input::is_monthly_day(date, day) == (date_day == expected),
+                        || format!("monthly = {day} is wrong on {date}."),
+                    );
+                }
+            }
+        }
+    }
+    check
+}
+
+/// Weeks 1 to 4 have each weekday once a month, and week 5 has the days from the 29th on.
+fn weeks_of_month(years: &[i32], errors: &Errors) -> Check {
+    let mut check = Check::new("Weeks of the month");
+    let sources: Vec<_> = (1..=5)
+        .map(|week| {
+            let content = format!(
+                "timezone = \"UTC\"\nstart = \"12:00\"\nduration = \"1:00\"\nweeks = [{week}]\n"
+            );
+            (PathBuf::from(format!("selftest/week{week}.toml")), content)
+        })
+        .collect();
+    let event_files = event_files(&sources);
+    let days_off = resolved::DaysOff::default();
+    for (event, week) in parse_events(&event_files, errors).iter().zip(1..) {
+        let resolved = match resolved::Event::resolve(event, &days_off) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                check.assert(false, || format!("{error}"));
+                continue;
+            }
+        };
+        for &year in years {
+            for month in 1..=12 {
+                let last = last_day(year, month);
+                let dates: Vec<_> = (1..=last)
+                    .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+                    .filter(|&date| resolved.scheduled_on(date).is_some())
+                    .collect();
+                let weekdays = dates
+                    .iter()
+                    .map(|date| date.weekday().num_days_from_monday())
+                    .collect::<BTreeSet<_>>();
+                let ok = if week == 5 {
+                    dates.iter().all(|date| date.day() >= 29) && dates.len() as u32 == last - 28
+                } else {
+                    dates.len() == 7 && weekdays.len() == 7
+                };
+                check.assert(ok, || {
+                    format!("weeks = [{week}] is held on {dates:?} in {year}-{month:02}.")
+                });
+            }
+        }
+    }
+    check
+}
+
+/// Events held every few weeks keep their rhythm across year boundaries, including after years
+/// with 53 ISO weeks, and are held for whole weeks from Monday to Sunday.
+fn intervals(years: &[i32]) -> Check {
+    let mut check = Check::new("Intervals");
+    for &year in years {
+        let Some(new_year) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+            continue;
+        };
+        for anchor in (new_year - Days::new(4)).iter_days().take(8) {
+            let anchor_monday = anchor.week(Weekday::Mon).first_day();
+            for every in (1..=4).filter_map(NonZeroU8::new) {
+                let from = anchor - Days::new(7 * 60);
+                for date in from.iter_days().take(7 * 120) {
+                    let weeks =
+                        (date.week(Weekday::Mon).first_day() - anchor_monday).num_days() / 7;
+                    let expected = weeks % i64::from(every.get()) == 0;
+                    check.assert(input::in_interval(date, anchor, every) == expected, || {
+                        format!("every = {every} with anchor {anchor} is wrong on {date}.")
+                    });
+                }
+            }
+        }
+    }
+    check
+}
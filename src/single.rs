@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+use chrono::{Duration, Utc};
+use miette::{Context, IntoDiagnostic, Result};
+
+use crate::{
+    cancel_days_off, check_accounts, check_characters, check_descriptions, check_holidays,
+    check_join_via, check_language_days, check_lengths, check_links, check_tags, check_time_zone,
+    check_unstable, check_worlds, error::BrokenTimeZone, event_format::EventFormat, fallbacks,
+    load_holidays, normalize, parse_events, parse_meta, prepare_event, report::Errors, resolved,
+    retry::Retry, state::State, time, EventFile, Posters, DEFAULT_GRACE_MINUTES,
+};
+
+#[derive(clap::Args)]
+pub struct CompileEventArgs {
+    /// The event's id, which is normally its file name without the extension.
+    #[arg(long, default_value = "event")]
+    id: String,
+    /// A meta.toml to check the event against, for organizer time zones.
+    #[arg(long)]
+    meta: Option<PathBuf>,
+    /// Keep confirmations and cancellations for occurrences that ended up to this many minutes
+    /// ago.
+    #[arg(long, value_name = "MINUTES", default_value_t = DEFAULT_GRACE_MINUTES)]
+    grace: u32,
+    /// Include the fields from the [unstable] section, which may change in any version.
+    #[arg(long)]
+    unstable: bool,
+}
+
+/// Compiles one event from stdin and prints its output JSON to stdout.
+pub fn run(args: CompileEventArgs, errors: Errors) -> ExitCode {
+    match compile(args, &errors) {
+        Ok(()) if errors.count() == 0 => ExitCode::SUCCESS,
+        Ok(()) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{error:?}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn compile(args: CompileEventArgs, errors: &Errors) -> Result<()> {
+    let mut content = String::new();
+    io::stdin()
+        .read_to_string(&mut content)
+        .into_diagnostic()
+        .wrap_err("Could not read the event from stdin.")?;
+
+    let meta_file = args
+        .meta
+        .as_ref()
+        .map(|path| {
+            fs::read_to_string(path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Reading {} failed.", path.display()))
+                .map(|content| Arc::new(normalize(content.into()).into_owned()))
+        })
+        .transpose()?;
+    let meta = meta_file.as_ref().map(parse_meta).transpose()?;
+
+    let path = PathBuf::from(format!("{}.toml", args.id));
+    let meta_path = PathBuf::from("meta.toml");
+    let mut event_files = vec![EventFile {
+        path: &path,
+        format: EventFormat::Toml,
+        entry: None,
+        content: Arc::new(normalize(content.into()).into_owned()),
+        original: None,
+    }];
+    // The meta file's defaults apply to the event too.
+    if let Some(meta_file) = &meta_file {
+        event_files.push(EventFile {
+            path: &meta_path,
+            format: EventFormat::Toml,
+            entry: None,
+            content: meta_file.clone(),
+            original: None,
+        });
+    }
+    let events = parse_events(&event_files, errors);
+    let Some(event) = events.first() else {
+        // The parse error was already reported.
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let (zones, broken_zones) = time::collect_zones(now);
+    if broken_zones.contains(event.event.timezone().as_ref().as_ref()) {
+        return Err(BrokenTimeZone::new(event).into());
+    }
+    // There is no output directory, so poster slots are assigned as if the calendar were empty.
+    let state = State::default();
+    let mut posters = Posters::load(PathBuf::new(), &state, now, true, false, Retry::default());
+    let grace = Duration::minutes(args.grace.into());
+    let mut output_event = prepare_event(
+        event,
+        &BTreeSet::new(),
+        &zones,
+        now,
+        grace,
+        &mut posters,
+        None,
+    )?;
+    if let (Some(meta), Some(meta_file)) = (&meta, &meta_file) {
+        let holidays = if event.event.cancel_on_holidays {
+            let directory = args.meta.as_deref().and_then(Path::parent);
+            let directory = directory.unwrap_or(Path::new(""));
+            load_holidays(meta, meta_file, directory, now)
+        } else {
+            Vec::new()
+        };
+        let days_off = resolved::DaysOff {
+            blackout: meta.blackout.clone(),
+            holidays,
+        };
+        cancel_days_off(&mut output_event, event, &days_off, now, grace);
+        check_holidays(event, meta);
+        check_time_zone(event, meta, &state, &zones);
+        check_tags(event, meta);
+        check_lengths(event, meta);
+        fallbacks::check_event(event, meta);
+    }
+    check_unstable(event, args.unstable);
+    check_characters(event);
+    check_links(event);
+    check_descriptions(event);
+    check_accounts(event);
+    check_worlds(event);
+    check_join_via(event);
+    check_language_days(event);
+
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &output_event).into_diagnostic()?;
+    writeln!(stdout).into_diagnostic()
+}
@@ -0,0 +1,135 @@
+//! Accounts of events on social platforms, which are checked and linked to by their handles.
+
+use std::ops::RangeInclusive;
+
+use crate::{input::EventInfo, output::Account};
+
+#[derive(Clone, Copy)]
+pub enum Service {
+    Bluesky,
+    Misskey,
+    YouTube,
+    Twitch,
+}
+
+impl Service {
+    pub const ALL: [Service; 4] = [
+        Service::Bluesky,
+        Service::Misskey,
+        Service::YouTube,
+        Service::Twitch,
+    ];
+
+    /// The field of the service in event files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Service::Bluesky => "bluesky",
+            Service::Misskey => "misskey",
+            Service::YouTube => "youtube",
+            Service::Twitch => "twitch",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Bluesky => "Bluesky",
+            Service::Misskey => "Misskey",
+            Service::YouTube => "YouTube",
+            Service::Twitch => "Twitch",
+        }
+    }
+
+    /// What a handle looks like, for error messages.
+    pub fn expected(self) -> &'static str {
+        match self {
+            Service::Bluesky => "Bluesky handles are domain names, like myevent.bsky.social",
+            Service::Misskey => "Misskey handles include the server, like @myevent@misskey.io",
+            Service::YouTube => {
+                "YouTube channels are given by their handle, like @myevent, or their id, which \
+                 starts with UC"
+            }
+            Service::Twitch => {
+                "Twitch usernames have 4 to 25 letters, digits and underscores, like my_event"
+            }
+        }
+    }
+
+    pub fn handle<'b>(self, info: &'b EventInfo) -> Option<&'b str> {
+        match self {
+            Service::Bluesky => info.bluesky.as_deref(),
+            Service::Misskey => info.misskey.as_deref(),
+            Service::YouTube => info.youtube.as_deref(),
+            Service::Twitch => info.twitch.as_deref(),
+        }
+    }
+
+    /// The handle as it is shown and the address of the account, or `None` if the handle is not
+    /// valid.
+    pub fn account(self, handle: &str) -> Option<Account> {
+        let (handle, url) = match self {
+            Service::Bluesky => {
+                let handle = handle.strip_prefix('@').unwrap_or(handle).to_lowercase();
+                if !is_domain(&handle) {
+                    return None;
+                }
+                let url = format!("https://bsky.app/profile/{handle}");
+                (handle, url)
+            }
+            Service::Misskey => {
+                let (user, host) = handle.strip_prefix('@').unwrap_or(handle).split_once('@')?;
+                let host = host.to_lowercase();
+                if !is_name(user, 1..=usize::MAX, &['_']) || !is_domain(&host) {
+                    return None;
+                }
+                let url = format!("https://{host}/@{user}");
+                (format!("@{user}@{host}"), url)
+            }
+            Service::YouTube => {
+                if let Some(name) = handle.strip_prefix('@') {
+                    if !is_name(name, 3..=30, &['_', '-', '.']) {
+                        return None;
+                    }
+                    let url = format!("https://www.youtube.com/@{name}");
+                    (handle.to_owned(), url)
+                } else if handle.starts_with("UC") && is_name(handle, 24..=24, &['_', '-']) {
+                    let url = format!("https://www.youtube.com/channel/{handle}");
+                    (handle.to_owned(), url)
+                } else {
+                    return None;
+                }
+            }
+            Service::Twitch => {
+                if !is_name(handle, 4..=25, &['_']) {
+                    return None;
+                }
+                let url = format!("https://www.twitch.tv/{}", handle.to_lowercase());
+                (handle.to_owned(), url)
+            }
+        };
+        Some(Account { handle, url })
+    }
+}
+
+/// Whether `name` has a length in `length`, and only ASCII letters, digits, and `extra`.
+fn is_name(name: &str, length: RangeInclusive<usize>, extra: &[char]) -> bool {
+    length.contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || extra.contains(&c))
+}
+
+/// Whether `name` is a lowercase domain name with at least two labels.
+fn is_domain(name: &str) -> bool {
+    let labels: Vec<_> = name.split('.').collect();
+    labels.len() >= 2
+        && name.len() <= 253
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        })
+        && !labels[labels.len() - 1].starts_with(|c: char| c.is_ascii_digit())
+}
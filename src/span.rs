@@ -0,0 +1,199 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use miette::SourceSpan;
+use toml_edit::{ImDocument, InlineTable, Item, Table, Value};
+
+/// The locations of every key in a TOML document.
+///
+/// Spans are lost when deserializing fields inside `#[serde(flatten)]` structs, so diagnostics
+/// about those fields look up their location by path instead.
+pub struct SourceMap {
+    root: BTreeMap<String, Located>,
+}
+
+struct Located {
+    /// Tables created implicitly by dotted headers like `[languages.ja]` have no span.
+    span: Option<Range<usize>>,
+    /// The span of the key the value is under, if it is in a table.
+    key: Option<Range<usize>>,
+    node: Node,
+}
+
+enum Node {
+    Table(BTreeMap<String, Located>),
+    Array(Vec<Located>),
+    Value,
+}
+
+impl SourceMap {
+    pub fn parse(content: &str) -> Option<Self> {
+        let document = ImDocument::parse(content).ok()?;
+        Some(SourceMap {
+            root: located_table(document.as_table()),
+        })
+    }
+
+    /// Finds the span of the value at `path`. Array elements are indexed by their position.
+    pub fn span<S: AsRef<str>>(&self, path: &[S]) -> Option<SourceSpan> {
+        let (first, rest) = path.split_first()?;
+        let mut located = self.root.get(first.as_ref())?;
+        for key in rest {
+            located = match &located.node {
+                Node::Table(table) => table.get(key.as_ref())?,
+                Node::Array(array) => array.get(key.as_ref().parse::<usize>().ok()?)?,
+                Node::Value => return None,
+            };
+        }
+        let Range { start, end } = located.span.clone()?;
+        Some((start, end - start).into())
+    }
+
+    /// The locations in one of the tables in the array at `key`, like an `[[event]]` of meta.toml.
+    pub fn entry(mut self, key: &str, index: usize) -> Option<Self> {
+        let Node::Array(mut array) = self.root.remove(key)?.node else {
+            return None;
+        };
+        if array.len() <= index {
+            return None;
+        }
+        let Node::Table(root) = array.swap_remove(index).node else {
+            return None;
+        };
+        Some(SourceMap { root })
+    }
+
+    /// Every key and value with a span, by its path, with the span of its key and of its value.
+    pub fn all(&self) -> Spans {
+        let mut all = Vec::new();
+        let mut path = Vec::new();
+        all_in_table(&self.root, &mut path, &mut all);
+        all
+    }
+
+    /// Finds every key matching `pattern`, where `*` matches any key. Keys of implicit tables
+    /// are located by the first of their contents that has a span.
+    pub fn find(&self, pattern: &[&str]) -> Vec<Option<SourceSpan>> {
+        let mut found = Vec::new();
+        find_in(&self.root, pattern, &mut found);
+        found
+    }
+}
+
+fn find_in(
+    table: &BTreeMap<String, Located>,
+    pattern: &[&str],
+    found: &mut Vec<Option<SourceSpan>>,
+) {
+    let Some((&first, rest)) = pattern.split_first() else {
+        return;
+    };
+    let matches = table
+        .iter()
+        .filter(|(key, _)| first == "*" || key.as_str() == first);
+    for (_, located) in matches {
+        if rest.is_empty() {
+            found.push(
+                located
+                    .first_span()
+                    .map(|Range { start, end }| (start, end - start).into()),
+            );
+        } else if let Node::Table(table) = &located.node {
+            find_in(table, rest, found);
+        }
+    }
+}
+
+/// Keys and values by their path, with the spans of the key and of the value.
+pub type Spans = Vec<(Vec<String>, Option<Range<usize>>, Option<Range<usize>>)>;
+
+fn all_in_table(table: &BTreeMap<String, Located>, path: &mut Vec<String>, all: &mut Spans) {
+    for (key, located) in table {
+        path.push(key.clone());
+        all_in(located, path, all);
+        path.pop();
+    }
+}
+
+fn all_in(located: &Located, path: &mut Vec<String>, all: &mut Spans) {
+    if located.key.is_some() || located.span.is_some() {
+        all.push((path.clone(), located.key.clone(), located.span.clone()));
+    }
+    match &located.node {
+        Node::Table(table) => all_in_table(table, path, all),
+        Node::Array(array) => {
+            for (index, located) in array.iter().enumerate() {
+                path.push(index.to_string());
+                all_in(located, path, all);
+                path.pop();
+            }
+        }
+        Node::Value => {}
+    }
+}
+
+impl Located {
+    fn first_span(&self) -> Option<Range<usize>> {
+        self.span.clone().or_else(|| match &self.node {
+            Node::Table(table) => table.values().find_map(Located::first_span),
+            Node::Array(array) => array.iter().find_map(Located::first_span),
+            Node::Value => None,
+        })
+    }
+}
+
+fn located_table(table: &Table) -> BTreeMap<String, Located> {
+    table
+        .iter()
+        .filter_map(|(key, _)| table.get_key_value(key))
+        .map(|(key, item)| (key.get().to_owned(), located_item(item, key.span())))
+        .collect()
+}
+
+fn located_inline_table(table: &InlineTable) -> BTreeMap<String, Located> {
+    table
+        .iter()
+        .filter_map(|(key, _)| table.get_key_value(key))
+        .map(|(key, item)| (key.get().to_owned(), located_item(item, key.span())))
+        .collect()
+}
+
+fn located_item(item: &Item, key: Option<Range<usize>>) -> Located {
+    let node = match item {
+        Item::Table(table) => Node::Table(located_table(table)),
+        Item::ArrayOfTables(array) => Node::Array(
+            array
+                .iter()
+                .map(|table| Located {
+                    span: table.span(),
+                    key: None,
+                    node: Node::Table(located_table(table)),
+                })
+                .collect(),
+        ),
+        Item::Value(value) => {
+            return Located {
+                key,
+                ..located_value(value)
+            }
+        }
+        Item::None => Node::Value,
+    };
+    Located {
+        span: item.span(),
+        key,
+        node,
+    }
+}
+
+fn located_value(value: &Value) -> Located {
+    let node = match value {
+        Value::Array(array) => Node::Array(array.iter().map(located_value).collect()),
+        Value::InlineTable(table) => Node::Table(located_inline_table(table)),
+        _ => Node::Value,
+    };
+    Located {
+        span: value.span(),
+        key: None,
+        node,
+    }
+}
@@ -1,11 +1,45 @@
+use std::collections::BTreeMap;
+
 use base64::prelude::*;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{digest::Output, Sha256};
 
+use crate::output::Rule;
+
 #[derive(Default, Deserialize, Serialize)]
 pub struct State {
     pub posters: Vec<Poster>,
+    #[serde(default)]
+    pub timezones: BTreeMap<String, String>,
+    /// The ids of the events in data.json, in the same order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    /// Old event ids, and the ids the events were renamed to by the migrate command.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub redirects: BTreeMap<String, String>,
+    /// The time zone data used by the last compile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tzdb: Option<Tzdb>,
+    /// The RSVP counts last fetched with `--rsvp`, by event id and date.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rsvps: BTreeMap<String, BTreeMap<NaiveDate, u16>>,
+    /// When `rsvps` was fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rsvps_fetched: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Tzdb {
+    #[serde(
+        serialize_with = "serialize_hash",
+        deserialize_with = "deserialize_hash"
+    )]
+    pub sha256: Output<Sha256>,
+    /// The end of the period covered by `zones`, as a Unix timestamp.
+    pub until: i64,
+    /// The offsets of the zones used by events, from the time of the compile until `until`.
+    pub zones: BTreeMap<String, Vec<Rule>>,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -16,6 +50,28 @@ pub struct Poster {
         deserialize_with = "deserialize_hash"
     )]
     pub sha256: Output<Sha256>,
+    /// Set when the poster was compiled with `--similar-posters`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageHash>,
+}
+
+/// A perceptual hash of a poster, which barely changes when the image is saved in another format.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct ImageHash {
+    /// Whether each pixel of a 9×8 grayscale thumbnail is brighter than the one to its right.
+    pub dhash: u64,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl ImageHash {
+    /// Whether the images could be the same. This lets through images that differ in details
+    /// too small for the hash, so matches need to be compared.
+    pub fn is_close(&self, other: &ImageHash) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && (self.dhash ^ other.dhash).count_ones() <= 6
+    }
 }
 
 fn serialize_hash<S>(hash: &Output<Sha256>, serializer: S) -> Result<S::Ok, S::Error>
@@ -0,0 +1,142 @@
+use std::{collections::BTreeMap, path::PathBuf, process::ExitCode};
+
+use chrono::Weekday;
+
+use crate::{
+    collect_files, discover::FileFilter, input::day_key, load_state, mirror, parse_events,
+    read_event_files, report::Errors, state::State, Language, Platform,
+};
+
+#[derive(clap::Args)]
+pub struct StatsArgs {
+    /// The directory containing the event files.
+    input: PathBuf,
+    /// Read the input as a compiled data.json instead of event files.
+    #[arg(long, conflicts_with = "output")]
+    from_data: bool,
+    #[command(flatten)]
+    filter: FileFilter,
+    /// The output directory of previous compiles, for poster cache statistics.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+pub fn run(args: StatsArgs, errors: Errors) -> ExitCode {
+    if args.from_data {
+        let mirror = match mirror::read(&args.input) {
+            Ok(mirror) => mirror,
+            Err(error) => {
+                eprintln!("{error:?}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let events = mirror.events();
+        let mut counts = Counts::default();
+        for event in &events {
+            counts.add(
+                event.days.iter().map(|(weekday, _)| weekday),
+                event.languages.keys(),
+                event.platforms,
+                event.timezone,
+            );
+        }
+        counts.print(events.len());
+        return ExitCode::SUCCESS;
+    }
+
+    let files = collect_files(&args.input, &args.filter);
+    let event_files = read_event_files(&files, &errors);
+    let events = parse_events(&event_files, &errors);
+
+    let mut counts = Counts::default();
+    for event in &events {
+        counts.add(
+            event.event.days.iter().map(|(weekday, _)| weekday),
+            event.event.languages.keys(),
+            &event.event.platforms,
+            event.event.timezone().as_ref().as_ref(),
+        );
+    }
+    counts.print(events.len());
+
+    if let Some(output) = &args.output {
+        match load_state(output) {
+            Ok(state) => print_posters(&state),
+            Err(error) => eprintln!("{error:?}"),
+        }
+    }
+
+    if errors.count() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[derive(Default)]
+struct Counts<'a> {
+    weekdays: BTreeMap<u32, usize>,
+    languages: BTreeMap<&'a str, usize>,
+    platforms: BTreeMap<&'static str, usize>,
+    timezones: BTreeMap<&'a str, usize>,
+}
+
+impl<'a> Counts<'a> {
+    fn add(
+        &mut self,
+        weekdays: impl Iterator<Item = Weekday>,
+        languages: impl Iterator<Item = &'a Language>,
+        platforms: &[Platform],
+        timezone: &'a str,
+    ) {
+        for weekday in weekdays {
+            *self
+                .weekdays
+                .entry(weekday.num_days_from_monday())
+                .or_default() += 1;
+        }
+        for language in languages {
+            *self.languages.entry(language.code()).or_default() += 1;
+        }
+        for platform in platforms {
+            *self.platforms.entry(platform.name()).or_default() += 1;
+        }
+        *self.timezones.entry(timezone).or_default() += 1;
+    }
+
+    fn print(self, events: usize) {
+        println!("Events: {events}");
+        print_section(
+            "By weekday",
+            self.weekdays.iter().map(|(&weekday, &count)| {
+                (day_key(Weekday::try_from(weekday as u8).unwrap()), count)
+            }),
+        );
+        print_section("By language", self.languages.into_iter());
+        print_section("By platform", self.platforms.into_iter());
+        print_section("By time zone", self.timezones.into_iter());
+    }
+}
+
+fn print_section<'a>(title: &str, counts: impl Iterator<Item = (&'a str, usize)>) {
+    println!();
+    println!("{title}:");
+    for (name, count) in counts {
+        println!("  {name:<32} {count:>5}");
+    }
+}
+
+fn print_posters(state: &State) {
+    println!();
+    println!("Poster slots: {} of 255 used", state.posters.len());
+    // Posters referenced by the last compile were all marked with the same time.
+    if let Some(latest) = state.posters.iter().map(|p| p.last_used).max() {
+        let current = state
+            .posters
+            .iter()
+            .filter(|p| p.last_used == latest)
+            .count();
+        println!("  {:<32} {current:>5}", "Used by the last compile");
+        println!("  {:<32} {:>5}", "Stale", state.posters.len() - current);
+    }
+}
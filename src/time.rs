@@ -1,13 +1,19 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use chrono::{DateTime, Days, Utc};
+use miette::{NamedSource, Report};
 use parse_zoneinfo::{
     line::{Line, LineParser},
-    table::TableBuilder,
+    table::{Saving, TableBuilder},
     transitions::TableTransitions,
 };
+use sha2::{digest::Output, Digest, Sha256};
 
-use crate::output::{Rule, Zone};
+use crate::{
+    error::TzdbLineError,
+    output::{Rule, Zone},
+    state::Tzdb,
+};
 
 struct TzFile {
     name: &'static str,
@@ -34,49 +40,163 @@ const FILES: &[TzFile] = &[
     include_tz!("southamerica"),
 ];
 
-pub fn collect_zones(now: DateTime<Utc>) -> BTreeMap<String, Zone> {
+/// The end of the period that the zones are collected for.
+pub fn limit(now: DateTime<Utc>) -> DateTime<Utc> {
+    now + Days::new(365 * 5)
+}
+
+/// The checksum of the bundled time zone data.
+pub fn checksum() -> Output<Sha256> {
+    let mut hasher = Sha256::new();
+    for file in FILES {
+        hasher.update(file.name);
+        hasher.update([0]);
+        hasher.update(file.content);
+    }
+    hasher.finalize()
+}
+
+/// The zones in `previous` whose offsets changed in the period both compiles cover.
+pub fn changed_zones(
+    previous: &Tzdb,
+    zones: &BTreeMap<String, Zone>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let now = now.timestamp();
+    previous
+        .zones
+        .iter()
+        .filter(|(name, rules)| {
+            zones.get(*name).is_none_or(|zone| {
+                offsets_between(rules, now, previous.until)
+                    != offsets_between(&zone.offsets, now, previous.until)
+            })
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// The offset at `from`, followed by the changes before `until`.
+fn offsets_between(rules: &[Rule], from: i64, until: i64) -> Vec<(i64, Option<i16>)> {
+    let current = rules
+        .iter()
+        .take_while(|rule| rule.start.is_none_or(|start| start <= from))
+        .last()
+        .map(|rule| (from, rule.offset));
+    current
+        .into_iter()
+        .chain(rules.iter().filter_map(|rule| {
+            let start = rule.start.filter(|&start| from < start && start < until)?;
+            Some((start, rule.offset))
+        }))
+        .collect()
+}
+
+/// Builds the zone table for the period from `now`. Lines of the bundled tz files that can't be
+/// read are reported and skipped, and the zones they break are left out of the table and
+/// returned separately.
+pub fn collect_zones(now: DateTime<Utc>) -> (BTreeMap<String, Zone>, BTreeSet<String>) {
     let parser = LineParser::new();
     let mut table = TableBuilder::new();
+    let mut broken_zones = BTreeSet::new();
+    let mut broken_rules = BTreeSet::new();
 
     let now_ts = now.timestamp();
-    let limit = now + Days::new(365 * 5);
-    let limit_ts = limit.timestamp();
+    let limit_ts = limit(now).timestamp();
 
     for file in FILES {
-        for (line_index, line) in file.content.lines().enumerate() {
+        // The zone that continuation lines belong to, and whether it is broken.
+        let mut current = None;
+        let mut skipping = false;
+        let mut offset = 0;
+        for (line_index, full_line) in file.content.split_inclusive('\n').enumerate() {
+            let line_start = offset;
+            offset += full_line.len();
+            let line = full_line.trim_end_matches(['\r', '\n']);
             let line = if let Some(index) = line.find('#') {
                 &line[..index]
             } else {
                 line
             };
-            let line = match parser.parse_str(line) {
-                Ok(line) => line,
-                Err(error) => {
-                    panic!(
-                        "Syntax error at {}:{}: {:?}",
-                        file.name,
-                        line_index + 1,
-                        error,
-                    );
+            let result = match parser.parse_str(line) {
+                Ok(Line::Space) => Ok(()),
+                Ok(Line::Continuation(_)) if skipping => Ok(()),
+                Ok(Line::Continuation(continuation)) => table
+                    .add_continuation_line(continuation)
+                    .map_err(|error| format!("{error:?}")),
+                Ok(Line::Zone(zone)) => {
+                    current = Some(zone.name);
+                    skipping = false;
+                    table
+                        .add_zone_line(zone)
+                        .map_err(|error| format!("{error:?}"))
+                }
+                Ok(Line::Rule(rule)) => {
+                    current = None;
+                    table
+                        .add_rule_line(rule)
+                        .map_err(|error| format!("{error:?}"))
                 }
+                Ok(Line::Link(link)) => {
+                    current = None;
+                    table
+                        .add_link_line(link)
+                        .map_err(|error| format!("{error:?}"))
+                }
+                Err(error) => Err(format!("{error:?}")),
             };
-            let result = match line {
-                Line::Space => Ok(()),
-                Line::Zone(zone) => table.add_zone_line(zone),
-                Line::Continuation(continuation) => table.add_continuation_line(continuation),
-                Line::Rule(rule) => table.add_rule_line(rule),
-                Line::Link(link) => table.add_link_line(link),
+            let Err(message) = result else {
+                continue;
             };
-            if let Err(error) = result {
-                panic!("Error at {}:{}: {}", file.name, line_index + 1, error);
+
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("Rule") => {
+                    broken_rules.extend(fields.next());
+                }
+                Some("Zone") => {
+                    broken_zones.extend(fields.next().map(str::to_owned));
+                    // The zone's continuation lines would otherwise be added to the zone before.
+                    skipping = true;
+                }
+                Some(_) if line.starts_with(char::is_whitespace) => {
+                    broken_zones.extend(current.map(str::to_owned));
+                    skipping = true;
+                }
+                _ => {}
             }
+            eprintln!(
+                "{:?}",
+                Report::new(TzdbLineError {
+                    line: line_index + 1,
+                    message,
+                    src: NamedSource::new(format!("tz/{}", file.name), file.content),
+                    location: (line_start, line.len()).into(),
+                }),
+            );
         }
     }
 
     let table = table.build();
     let mut zones = BTreeMap::new();
 
+    // Zones that use missing rules can't be built.
+    for (zone_name, infos) in &table.zonesets {
+        let broken = infos.iter().any(|info| match &info.saving {
+            Saving::Multiple(rules) => {
+                broken_rules.contains(rules.as_str()) || !table.rulesets.contains_key(rules)
+            }
+            _ => false,
+        });
+        if broken {
+            broken_zones.insert(zone_name.clone());
+        }
+    }
+
     for zone_name in table.zonesets.keys() {
+        if broken_zones.contains(zone_name) {
+            continue;
+        }
         let Some(timespans) = table.timespans(zone_name) else {
             continue;
         };
@@ -106,5 +226,5 @@ pub fn collect_zones(now: DateTime<Utc>) -> BTreeMap<String, Zone> {
         );
     }
 
-    zones
+    (zones, broken_zones)
 }
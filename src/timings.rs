@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+/// How long each phase of a compile took, printed when dropped if `--timings` was given.
+pub struct Timings {
+    enabled: bool,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Self {
+        Timings {
+            enabled,
+            last: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Records the time since the previous phase ended.
+    pub fn phase(&mut self, name: &'static str) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.last));
+        self.last = now;
+    }
+
+    /// Records the time since the previous phase ended, less `part`, which was spent on a
+    /// separately measured phase called `part_name` in the middle of it.
+    pub fn phase_with_part(&mut self, name: &'static str, part_name: &'static str, part: Duration) {
+        let now = Instant::now();
+        self.phases.push((part_name, part));
+        self.phases
+            .push((name, (now - self.last).saturating_sub(part)));
+        self.last = now;
+    }
+}
+
+impl Drop for Timings {
+    fn drop(&mut self) {
+        if !self.enabled || self.phases.is_empty() {
+            return;
+        }
+        let total: Duration = self.phases.iter().map(|(_, d)| *d).sum();
+        for (name, duration) in &self.phases {
+            eprintln!("{name:<24} {:>10.1?}", duration);
+        }
+        eprintln!("{:<24} {:>10.1?}", "Total", total);
+    }
+}
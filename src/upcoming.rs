@@ -0,0 +1,101 @@
+use std::{path::PathBuf, process::ExitCode, str::FromStr};
+
+use chrono::{DateTime, Days, Utc};
+use chrono_tz::Tz;
+use miette::Result;
+
+use crate::{
+    collect_files, discover::FileFilter, parse_events, read_days_off, read_event_files,
+    report::Errors, resolved, Event,
+};
+
+#[derive(clap::Args)]
+pub struct UpcomingArgs {
+    /// The directory containing the event files.
+    input: PathBuf,
+    #[command(flatten)]
+    filter: FileFilter,
+    /// The number of occurrences to list for each event.
+    #[arg(long, default_value_t = 5)]
+    count: usize,
+    /// The time zone to show the times in.
+    #[arg(long, default_value = "UTC", value_parser = parse_time_zone)]
+    timezone: Tz,
+}
+
+fn parse_time_zone(name: &str) -> Result<Tz, String> {
+    Tz::from_str(name)
+}
+
+/// How far ahead to look for occurrences.
+const HORIZON_DAYS: u64 = 366;
+
+pub fn run(args: UpcomingArgs, errors: Errors) -> ExitCode {
+    let files = collect_files(&args.input, &args.filter);
+    let event_files = read_event_files(&files, &errors);
+    let events = parse_events(&event_files, &errors);
+
+    let now = Utc::now();
+    let days_off = read_days_off(&files, now);
+    for event in &events {
+        if let Err(error) = print_event(event, &days_off, &args, now) {
+            eprintln!("{error:?}");
+        }
+    }
+
+    if errors.count() == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_event(
+    event: &Event,
+    days_off: &resolved::DaysOff,
+    args: &UpcomingArgs,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let event = resolved::Event::resolve(event, days_off)?;
+
+    println!("{}", event.name);
+    let today = now.with_timezone(&event.timezone).date_naive();
+    let occurrences = event
+        .current_occurrences(now, today + Days::new(HORIZON_DAYS))
+        .take(args.count);
+    let mut found = 0;
+    for occurrence in occurrences {
+        found += 1;
+        let status = match (occurrence.canceled, occurrence.canceled_reason) {
+            (true, Some(reason)) => format!(" (canceled: {reason})"),
+            (true, None) => " (canceled)".to_owned(),
+            (false, _) if occurrence.tentative => " (tentative)".to_owned(),
+            (false, _) if !occurrence.confirmed => " (unconfirmed)".to_owned(),
+            (false, _) => String::new(),
+        };
+        println!(
+            "  {}  {} {}{status}",
+            occurrence
+                .start
+                .with_timezone(&args.timezone)
+                .format("%Y-%m-%d %a %H:%M %Z"),
+            occurrence.start.format("%H:%M"),
+            event.timezone,
+        );
+        if let Some(name) = occurrence.name {
+            println!("    {name}");
+        }
+        let note = occurrence.day.note.filter(|note| {
+            note.expires
+                .as_ref()
+                .is_none_or(|expires| occurrence.date <= *expires.as_ref())
+        });
+        if let Some(note) = note {
+            println!("    {}", note.text);
+        }
+    }
+    if found == 0 {
+        println!("  No upcoming occurrences");
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::{input::day_key, output, Event};
+
+/// Where an event refers to a poster.
+enum Level<'a> {
+    Base,
+    Day(&'static str),
+    Language(&'a str),
+    LanguageDay(&'a str, &'static str),
+    Override(NaiveDate),
+}
+
+/// Prints which event files and which of their sections use each poster slot.
+pub fn report(sources: &[&Event], events: &[output::Event]) {
+    let mut slots = BTreeMap::<u8, Vec<(&Event, Level)>>::new();
+    let mut add = |poster: Option<output::PosterInfo>, source, level| {
+        if let Some(poster) = poster {
+            slots
+                .entry(poster.number)
+                .or_default()
+                .push((source, level));
+        }
+    };
+    for (&source, event) in sources.iter().zip(events) {
+        add(event.info.poster, source, Level::Base);
+        for (weekday, day) in event.days.iter() {
+            add(day.info.poster, source, Level::Day(day_key(weekday)));
+        }
+        for (language_id, language) in &event.languages {
+            let code = language_id.code();
+            add(language.info.poster, source, Level::Language(code));
+            for (weekday, day) in language.days.iter() {
+                add(
+                    day.info.poster,
+                    source,
+                    Level::LanguageDay(code, day_key(weekday)),
+                );
+            }
+        }
+        for (&date, change) in &event.overrides {
+            add(change.poster, source, Level::Override(date));
+        }
+    }
+
+    if slots.is_empty() {
+        println!("No posters are used");
+        return;
+    }
+    for (slot, users) in slots {
+        println!("Poster {slot:02x}:");
+        for (source, level) in users {
+            let path = source.source.path.display();
+            match level {
+                Level::Base => println!("  {path}"),
+                Level::Day(day) => println!("  {path} ({day})"),
+                Level::Language(code) => println!("  {path} ({code})"),
+                Level::LanguageDay(code, day) => println!("  {path} ({code} {day})"),
+                Level::Override(date) => println!("  {path} ({date})"),
+            }
+        }
+    }
+}